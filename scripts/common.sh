@@ -0,0 +1,30 @@
+# Sourced by each scripts/test-*.sh, which are run from the repository root
+# after `cargo build`. Sets:
+#
+# - `sea`, the compiler (`SEA`, ./target/debug/sea by default)
+# - `std`, the directory with the standard library (`SEA_STD`, the repository
+#   by default)
+# - `cc`, the C compiler (`CC`, tcc by default, like `sea c`)
+# - `root`, the repository, and `tmp`, a directory that's removed on exit
+#
+# along with `fail`, which reports a failed check, and `finish`, which reports
+# the result and exits.
+
+root=$(pwd)
+sea=$(realpath "${SEA:-./target/debug/sea}")
+std=$(realpath "${SEA_STD:-.}")
+cc=${CC:-tcc}
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+failed=0
+fail() {
+	echo "FAILED: $1"
+	failed=1
+}
+
+# i.e, `finish "sea fmt"` prints `ok: sea fmt` if nothing failed
+finish() {
+	[ $failed -eq 0 ] && echo "ok: $1"
+	exit $failed
+}
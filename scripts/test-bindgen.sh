@@ -0,0 +1,45 @@
+#!/usr/bin/env sh
+
+# Generates bindings for tests/bindgen/shapes.h with `sea bindgen`, checks them,
+# then builds and runs tests/bindgen/main.sea against them and shapes.c.
+
+. scripts/common.sh
+
+mkdir "$tmp/shapes"
+"$sea" bindgen tests/bindgen/shapes.h -o "$tmp/shapes/lib.sea" >/dev/null 2>&1 ||
+	fail "sea bindgen exited with an error"
+
+expect() {
+	grep -qxF -- "	$1" "$tmp/shapes/lib.sea" || fail "the bindings are missing: $1"
+}
+
+expect "let SHAPES_MAX_POINTS: int = 16"
+expect "rec Polygon(points: shapes'Point[SHAPES_MAX_POINTS], count: int, name: ^char)"
+expect "#(extern, union)"
+expect "rec Circle(center: shapes'Point, radius: f32)"
+expect "tag ShapeKind(SHAPE_POINT, SHAPE_POLYGON, SHAPE_CIRCLE)"
+expect "rec Canvas()"
+expect "rec Brush()"
+expect "fun brush_new(): ^shapes'Brush"
+expect "def AreaFn = fun (Any): f32"
+expect "fun canvas_new(width: usize, height: usize): ^shapes'Canvas"
+expect "fun polygon_each(polygon: ^shapes'Polygon, fn: fun (shapes'Point, Any), data: Any)"
+expect "fun shape_sides(kind: shapes'ShapeKind, in_: int): u8"
+expect "// skipped \`shapes_log\`: variadic functions aren't supported"
+expect "// skipped \`shapes_precise_area\`: \`long double\` isn't supported"
+expect "// skipped \`SHAPES_SQUARE\`: function-like macros aren't supported"
+expect "// skipped \`SHAPES_SHIFT\`: only macros that are integer literals are supported"
+expect "fun shapes_square(x: f32): f32"
+grep -qxF "typedef struct Circle Circle;" "$tmp/shapes/lib.sea" || fail "struct Circle wasn't typedef'd"
+grep -qxF "typedef struct Brush Brush;" "$tmp/shapes/lib.sea" || fail "struct Brush wasn't typedef'd"
+grep -v "// skipped" "$tmp/shapes/lib.sea" | grep -q "SHAPES_SQUARE\|SHAPES_NAME\|SHAPES_SHIFT" && fail "non-integer macros were bound"
+"$sea" fmt --check "$tmp/shapes/lib.sea" >/dev/null || fail "the bindings aren't formatted"
+
+# The C code always goes to .sea/build, so compile from inside the temporary directory
+output=$(cd "$tmp" && "$sea" c "$root/tests/bindgen/main.sea" -l "$tmp" -c "$cc" \
+	-f "-I$root/tests/bindgen" -f "$root/tests/bindgen/shapes.c" -s "$std" -r 2>&1)
+for line in "3 16 4" "12.0 12.0" "4 0"; do
+	echo "$output" | grep -qxF "$line" || fail "the program didn't print: $line"
+done
+
+finish "sea bindgen"
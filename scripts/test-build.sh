@@ -0,0 +1,26 @@
+#!/usr/bin/env sh
+
+# Builds tests/build/project with `sea build` from one of its subdirectories,
+# then checks the errors for tests/build/broken/build.reef.
+
+. scripts/common.sh
+
+cp -r tests/build/project tests/build/broken "$tmp"
+
+(cd "$tmp/project/src" && "$sea" build -c "$cc" >/dev/null 2>&1) || fail "sea build exited with an error"
+[ -x "$tmp/project/out/answer" ] || fail "out/answer wasn't built"
+output=$(cd "$tmp/project" && ./out/answer)
+[ "$output" = "Hello, reef!
+42" ] || fail "unexpected output: $output"
+
+errors=$(cd "$tmp/broken" && "$sea" build --error-format json)
+[ $? -ne 0 ] || fail "sea build succeeded with a broken build.reef"
+expect() {
+	echo "$errors" | grep -qF -- "$1" || fail "missing error: $1"
+}
+expect '"line":1,"message":"no such file: `main.sea`"'
+expect '"line":2,"message":"`nostd` should be a boolean, but it'"'"'s a string"'
+expect '"help":"did you mean `link`?","kind":"UnknownKey","length":3,"line":3'
+expect '"kind":"ExpectedEquals","length":6,"line":4'
+
+finish "sea build"
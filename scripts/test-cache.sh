@@ -0,0 +1,49 @@
+#!/usr/bin/env sh
+
+# Compiles tests/cache/main.sea twice, checking that its module is reused from
+# .sea/cache the second time and compiled again after it changes, with
+# `--no-cache`, and after `sea clean`, and that a separate `-I` still reaches
+# the modules.
+
+. scripts/common.sh
+
+cp -r tests/cache/* "$tmp"
+cd "$tmp" || exit 1
+
+compile() {
+	"$sea" c main.sea -S -l . -c "$cc" -r "$@" >"$tmp/log" 2>&1 || fail "sea c exited with an error: $(cat "$tmp/log")"
+}
+reused() {
+	grep -q "Reused $1 of 2 cached modules" "$tmp/log" || fail "expected $1 reused modules: $(cat "$tmp/log")"
+}
+
+compile
+grep -q "Reused" "$tmp/log" && fail "the first build reused modules"
+grep -qx "2" "$tmp/log" || fail "the program didn't print 2"
+compile
+reused 2
+[ -f .sea/build/modules/counter.c ] || fail "the module wasn't written to its own unit"
+
+# Changing a function's body only rebuilds its module
+sed 's/+ 1/+ 2/' counter/lib.sea >counter/lib.new && mv counter/lib.new counter/lib.sea
+compile
+reused 1
+grep -qx "4" "$tmp/log" || fail "the changed module wasn't used"
+
+compile --no-cache
+grep -q "Reused" "$tmp/log" && fail "--no-cache reused modules"
+
+"$sea" clean >/dev/null || fail "sea clean exited with an error"
+[ -d .sea/cache ] && fail "sea clean didn't remove .sea/cache"
+[ -d .sea/build ] && fail "sea clean didn't remove .sea/build"
+compile
+grep -q "Reused" "$tmp/log" && fail "modules were reused after sea clean"
+
+# A separate `-I` keeps its directory when compiling modules
+mkdir include
+echo '#define STEP 2' >include/step.h
+printf 'raw [\n#include "step.h"\n]\n\n' | cat - counter/lib.sea >counter/lib.new && mv counter/lib.new counter/lib.sea
+compile -f -I -f include
+grep -qx "4" "$tmp/log" || fail "the module wasn't compiled with -I include"
+
+finish "module cache"
@@ -0,0 +1,26 @@
+#!/usr/bin/env sh
+
+# Runs `sea check` on a valid test and an erroneous one from an empty directory,
+# checking the exit status, that errors are printed (with the signature, for
+# arguments of the wrong type), and that nothing is written. Also checks that
+# `sea check` and `sea c` report files that can't be read.
+
+. scripts/common.sh
+
+cd "$tmp" || exit 1
+
+"$sea" check "$root/tests/std/strings.sea" -s "$std" >"$tmp/log" 2>&1 || fail "a valid file didn't pass: $(cat "$tmp/log")"
+
+"$sea" check "$root/tests/erroneous/call_errors.sea" -s "$std" --error-format json >"$tmp/log" 2>&1 && fail "an erroneous file passed"
+[ "$(grep -c '"severity":"error"' "$tmp/log")" -gt 1 ] || fail "not every error was printed: $(cat "$tmp/log")"
+grep -qF '"kind":"WrongArgumentCount","length":3,"line":8' "$tmp/log" || fail "the error for \`add(1)\` didn't cover \`add\`: $(cat "$tmp/log")"
+grep '"kind":"ImplicitConversion"' "$tmp/log" | grep -q 'expected signature: fun add' || fail "a conversion error in a call didn't show the signature: $(cat "$tmp/log")"
+
+for command in check c; do
+	"$sea" $command missing.sea -s "$std" >"$tmp/log" 2>&1
+	[ $? -eq 1 ] && grep -q "failed to read" "$tmp/log" || fail "sea $command didn't report a missing file: $(cat "$tmp/log")"
+done
+
+[ -z "$(ls -A "$tmp" | grep -v log)" ] || fail "sea check wrote files: $(ls -A "$tmp")"
+
+finish "sea check"
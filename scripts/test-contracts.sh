@@ -0,0 +1,20 @@
+#!/usr/bin/env sh
+
+# Runs tests/syntax/contracts.sea, whose last call breaks a `@ret`, checking
+# that the failed contract is reported with where it was declared, and that
+# `--prod` leaves the checks out.
+
+. scripts/common.sh
+
+cp tests/syntax/contracts.sea "$tmp"
+cd "$tmp" || exit 1
+
+"$sea" c contracts.sea -s "$std" -c "$cc" >"$tmp/log" 2>&1 || fail "sea c exited with an error: $(cat "$tmp/log")"
+.sea/build/main >/dev/null 2>"$tmp/stderr" && fail "the broken contract didn't stop the program"
+grep -qx "contracts.sea:21:2: contract failed: @ret(ret \* 3 == n)" "$tmp/stderr" || fail "the failed contract wasn't reported: $(cat "$tmp/stderr")"
+
+"$sea" c contracts.sea -s "$std" -c "$cc" --prod >"$tmp/log" 2>&1 || fail "sea c --prod exited with an error: $(cat "$tmp/log")"
+.sea/build/main >/dev/null 2>"$tmp/stderr" || fail "--prod still checked the contracts: $(cat "$tmp/stderr")"
+grep -q "contract failed" .sea/build/main.c && fail "--prod wrote the checks"
+
+finish "contracts"
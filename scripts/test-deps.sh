@@ -0,0 +1,52 @@
+#!/usr/bin/env sh
+
+# Builds tests/deps/project, whose dependencies are a directory and a vendored
+# archive, then checks that `sea deps` notices when either of them changes.
+
+. scripts/common.sh
+
+cp -r tests/deps/project tests/deps/greet "$tmp"
+mkdir "$tmp/project/vendor"
+cp -r tests/deps/shout "$tmp/shout-1.0"
+tar -cf "$tmp/project/vendor/shout.tar" -C "$tmp" shout-1.0
+cd "$tmp/project" || exit 1
+
+build() {
+	"$sea" build -c "$cc" >"$tmp/build.log" 2>&1
+}
+
+build || fail "sea build exited with an error: $(cat "$tmp/build.log")"
+output=$(./.sea/build/main)
+[ "$output" = "Hello, deps!
+locked!!" ] || fail "unexpected output: $output"
+[ -f .sea/deps/shout/lib.sea ] || fail "the archive wasn't unpacked to .sea/deps/shout"
+grep -q '^greet_sha256 = "[0-9a-f]\{64\}"$' sea.lock || fail "greet wasn't locked"
+grep -q '^shout_source = "vendor/shout.tar"$' sea.lock || fail "shout wasn't locked"
+"$sea" deps --verify >/dev/null || fail "sea deps --verify failed after building"
+
+# Changing the resolved copy is caught by both `sea build` and `sea deps`
+echo "// changed" >>.sea/deps/greet/lib.sea
+build && fail "sea build succeeded with a modified dependency"
+"$sea" deps --verify 2>/dev/null | grep "modified" >/dev/null || fail "sea deps didn't report the modified dependency"
+"$sea" deps --refresh >/dev/null || fail "sea deps --refresh failed"
+"$sea" deps --verify >/dev/null || fail "sea deps --verify failed after refreshing"
+
+# So is changing the source, until the lock is refreshed
+lock=$(cat sea.lock)
+sed 's/Hello/Howdy/' ../greet/lib.sea >../greet/lib.new && mv ../greet/lib.new ../greet/lib.sea
+"$sea" deps --verify 2>/dev/null | grep "source changed" >/dev/null || fail "sea deps didn't report the changed source"
+rm -rf .sea/deps
+build && fail "sea build succeeded with a changed source"
+[ "$(cat sea.lock)" = "$lock" ] || fail "sea build changed sea.lock"
+"$sea" deps --refresh >/dev/null || fail "sea deps --refresh failed"
+build || fail "sea build failed after refreshing: $(cat "$tmp/build.log")"
+[ "$(./.sea/build/main | head -1)" = "Howdy, deps!" ] || fail "the refreshed dependency wasn't used"
+
+# Dependencies removed from the manifest are forgotten
+grep -v dep_shout build.reef >build.new && mv build.new build.reef
+sed 's/^use shout$//; s/^\tshout.*$//' main.sea >main.new && mv main.new main.sea
+build || fail "sea build failed after removing a dependency: $(cat "$tmp/build.log")"
+[ -d .sea/deps/shout ] && fail ".sea/deps/shout wasn't removed"
+grep -q shout sea.lock && fail "shout is still in sea.lock"
+
+finish "sea deps"
@@ -0,0 +1,25 @@
+#!/usr/bin/env sh
+
+# Checks that `sea doc` documents tests/doc/shapes and warns about its broken
+# link.
+
+. scripts/common.sh
+
+"$sea" doc tests/doc/shapes -s "$std" -o "$tmp" >"$tmp/output.txt" 2>&1 || fail "sea doc exited with an error"
+
+expect() {
+	grep -qF -- "$2" "$tmp/$1" || fail "$1 is missing: $2"
+}
+
+expect output.txt "doc comment links to an unknown symbol: \`<grow>\`"
+expect output.txt "1 warning emitted"
+expect index.md "- [\`shape\`](shape.md)"
+expect shape.md "Start with [\`shape'Circle\`](shape.md#Circle)."
+expect shape.md "## \`fun shape'area(circle: shape'Circle): f32\`"
+expect shape.md "## \`fun shape'grown(circle: shape'Circle, by: f32): shape'Circle @arg(by > 0.0)\`"
+expect shape.md "A circle centered at the origin, see [\`area\`](shape.md#area)."
+expect shape.md "Gets the area of \`circle\`, which is ***never*** negative."
+expect shape.html "<strong><em>never</em></strong>"
+grep -q "_scale" "$tmp/shape.md" && fail "private functions were documented"
+
+finish "sea doc"
@@ -0,0 +1,15 @@
+#!/usr/bin/env sh
+
+# Checks that `sea fmt` turns tests/format/unformatted.sea into
+# tests/format/formatted.sea.
+
+. scripts/common.sh
+
+cp tests/format/unformatted.sea "$tmp/main.sea"
+
+"$sea" fmt --check "$tmp/main.sea" >/dev/null && fail "--check accepted an unformatted file"
+"$sea" fmt "$tmp/main.sea" >/dev/null || fail "formatting exited with an error"
+diff -u tests/format/formatted.sea "$tmp/main.sea" || fail "the output does not match tests/format/formatted.sea"
+"$sea" fmt --check "$tmp/main.sea" >/dev/null || fail "--check rejected a formatted file"
+
+finish "sea fmt"
@@ -0,0 +1,31 @@
+#!/usr/bin/env sh
+
+# Checks the declarations in the header that `sea c --header` writes for
+# tests/header/shapes.sea.
+
+. scripts/common.sh
+
+# The C code always goes to .sea/build, so compile from inside the temporary directory
+(cd "$tmp" && "$sea" c "$root/tests/header/shapes.sea" -s "$std" -n --header shapes.h >/dev/null 2>&1) ||
+	fail "sea c exited with an error"
+
+expect() {
+	grep -qxF -- "$1" "$tmp/shapes.h" || fail "shapes.h is missing: $1"
+}
+
+expect "#ifndef SHAPES_H"
+expect "#endif // SHAPES_H"
+expect "} Color;"
+expect "extern const Color Color\$entries[];"
+expect "String Color\$to_str(Color it);"
+expect "} Shape;"
+expect "	void(* on_move)(Point*);"
+expect "typedef f64 Meters;"
+expect "extern int shapes\$count;"
+expect "extern const int shapes\$max;"
+expect "f32 shapes\$area(Shape shape);"
+expect "void describe(Color color);"
+grep -q "helper" "$tmp/shapes.h" && fail "#static functions were declared"
+grep -q ")$" "$tmp/shapes.h" && fail "function bodies were written"
+
+finish "sea c --header"
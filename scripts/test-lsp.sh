@@ -0,0 +1,51 @@
+#!/usr/bin/env sh
+
+# Talks to `sea lsp` like an editor would, using tests/lsp/main.sea, and checks
+# the responses.
+
+. scripts/common.sh
+
+file="$root/tests/lsp/main.sea"
+uri="file://$file"
+
+# Escapes the file so that it can be embedded in a JSON string
+text=$(sed -e 's/\\/\\\\/g' -e 's/"/\\"/g' -e 's/\t/\\t/g' "$file" | awk '{ printf "%s\\n", $0 }')
+
+message() {
+	printf 'Content-Length: %s\r\n\r\n%s' "$(printf '%s' "$1" | wc -c)" "$1"
+}
+
+at() {
+	echo "\"textDocument\":{\"uri\":\"$uri\"},\"position\":{\"line\":$1,\"character\":$2}"
+}
+
+output=$(
+	{
+		message '{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}'
+		message '{"jsonrpc":"2.0","method":"initialized","params":{}}'
+		message "{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/didOpen\",\"params\":{\"textDocument\":{\"uri\":\"$uri\",\"languageId\":\"sea\",\"version\":1,\"text\":\"$text\"}}}"
+		message "{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"textDocument/hover\",\"params\":{$(at 5 13)}}"
+		message "{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"textDocument/hover\",\"params\":{$(at 7 17)}}"
+		message "{\"jsonrpc\":\"2.0\",\"id\":4,\"method\":\"textDocument/definition\",\"params\":{$(at 5 13)}}"
+		message "{\"jsonrpc\":\"2.0\",\"id\":5,\"method\":\"textDocument/definition\",\"params\":{$(at 6 5)}}"
+		message "{\"jsonrpc\":\"2.0\",\"id\":6,\"method\":\"textDocument/completion\",\"params\":{$(at 6 7)}}"
+		message '{"jsonrpc":"2.0","id":7,"method":"shutdown"}'
+		message '{"jsonrpc":"2.0","method":"exit"}'
+	} | "$sea" lsp -s "$std"
+)
+code=$?
+
+expect() {
+	printf '%s' "$output" | grep -qF "$2" || fail "$1, expected to find: $2"
+}
+
+expect "reports diagnostics" '"code":"ImplicitConversion"'
+expect "hovers functions" '"id":2,"jsonrpc":"2.0","result":{"contents":{"kind":"markdown","value":"```sea\nfun add(i32, i32): i32\n```"}'
+expect "hovers variables" '"id":3,"jsonrpc":"2.0","result":{"contents":{"kind":"markdown","value":"```sea\nsum: i32\n```"}'
+expect "finds local definitions" '"id":4,"jsonrpc":"2.0","result":{"range":{"end":{"character":7,"line":2},"start":{"character":4,"line":2}}'
+expect "finds definitions in modules" "\"id\":5,\"jsonrpc\":\"2.0\",\"result\":{\"range\":{\"end\":{\"character\":12,\"line\":15},\"start\":{\"character\":5,\"line\":15}},\"uri\":\"file://$std/std/io/lib.sea\"}"
+expect "completes package names" '"label":"io'"'"'println"'
+
+[ $code -eq 0 ] || fail "sea lsp exited with $code"
+
+finish "sea lsp"
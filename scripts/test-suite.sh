@@ -0,0 +1,8 @@
+#!/usr/bin/env sh
+
+# Runs the compiler's golden-file tests with `sea test-suite`. Extra arguments
+# are passed along, i.e, `--bless`.
+
+. scripts/common.sh
+
+"$sea" test-suite tests/erroneous tests/operators tests/std tests/syntax -s "$std" -c "$cc" "$@"
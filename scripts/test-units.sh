@@ -0,0 +1,20 @@
+#!/usr/bin/env sh
+
+# Compiles tests/units/main.sea, whose modules each have a `#static fun name`,
+# once per module and with `--single-file`, checking that every module calls its
+# own function either way.
+
+. scripts/common.sh
+
+cp -r tests/units/* "$tmp"
+cd "$tmp" || exit 1
+
+for mode in "" --single-file; do
+	"$sea" c main.sea -S -l . -c "$cc" -r $mode >"$tmp/log" 2>&1 || fail "sea c $mode exited with an error: $(cat "$tmp/log")"
+	grep -qx "main first second" "$tmp/log" || fail "sea c $mode called the wrong functions: $(cat "$tmp/log")"
+done
+
+[ -f .sea/build/modules/first.c ] || fail "the modules weren't written to their own units"
+[ -f .sea/build/output.c ] || fail "--single-file didn't write output.c"
+
+finish "translation units"
@@ -0,0 +1,14 @@
+#!/usr/bin/env sh
+
+# Runs every scripts/test-*.sh and fails if any of them did. Run from the
+# repository root after `cargo build`; `SEA`, `SEA_STD`, and `CC` are passed
+# along (see scripts/common.sh).
+
+failed=0
+for script in scripts/test-*.sh; do
+	sh "$script" || {
+		echo "FAILED: $script"
+		failed=1
+	}
+done
+exit $failed
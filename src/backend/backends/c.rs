@@ -1,18 +1,17 @@
 use core::{fmt, format_args, panic};
-use std::{fs, io::Write, path::PathBuf};
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
 
 use crate::{
     backend::backend::Backend,
-    compile::{
-        compiler::Compiler, error::CompilerError, infer::infer_type_of_node, symbol::Symbol,
-        type_::SeaType,
-    },
+    compile::{compiler::Compiler, generics, symbol::Symbol, traits, type_::SeaType},
     hashtags::{DefTags, FunTags, RecTags, TagRecTags, TagTags},
     parse::{
-        ast::{Node, NodeKind},
-        lexer::Lexer,
+        ast::{ContractKind, Node, NodeKind},
         operator::OperatorKind,
-        parser::Parser,
     },
 };
 
@@ -26,41 +25,183 @@ pub struct CBackend<'a, 'b> {
     pub node: Box<Node>, // reference to current node
     pub compiler: &'b mut Compiler<'a>,
     pub block_stack: Vec<CodeBlock>,
+    pub output: File,
+    pub header: bool, // whether to write declarations for a `.h` instead of code
+    pub definitions: bool, // whether to leave out declarations, since the prelude has them
+    pub postconditions: Option<(Node, Vec<Node>)>, // return type and `@ret` contracts of the function being written
 }
 
 impl<'a, 'b> CBackend<'a, 'b> {
     const NAMESPACE_SEP: &'static str = "$";
 
-    pub fn new(compiler: &'b mut Compiler<'a>) -> Self {
+    pub fn new(compiler: &'b mut Compiler<'a>) -> io::Result<Self> {
+        if let Some(dir) = compiler.output_file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let output = File::create(&compiler.output_file)?;
+        Ok(Self::with_output(compiler, output))
+    }
+
+    // Makes a backend that writes a header for the program to `path`, using
+    // the same types and names as the code that `new` would write.
+    pub fn header(compiler: &'b mut Compiler<'a>, path: &Path) -> io::Result<Self> {
+        let mut backend = Self::with_output(compiler, File::create(path)?);
+        backend.header = true;
+        Ok(backend)
+    }
+
+    // Makes a backend that writes one translation unit of a program whose
+    // modules are compiled separately, see `write_units`.
+    pub fn unit(compiler: &'b mut Compiler<'a>, path: &Path) -> io::Result<Self> {
+        Ok(Self::with_output(compiler, File::create(path)?))
+    }
+
+    fn with_output(compiler: &'b mut Compiler<'a>, output: File) -> Self {
         CBackend {
             node: Box::new(Node {
                 line: 0,
                 column: 0,
                 node: NodeKind::Raw(Default::default()),
             }),
+            output,
             compiler,
             block_stack: vec![],
+            header: false,
+            definitions: false,
+            postconditions: None,
+        }
+    }
+
+    // Writes the program as translation units that can be compiled on their own,
+    // into `dir`:
+    //
+    // prelude.h         - declarations for everything in the modules that are used
+    // modules/<name>.c  - the definitions in each module, i.e, `std.io.c` for `std/io`
+    // main.c            - the program itself
+    //
+    // The paths of the `.c` files are returned, with `main.c` first.
+    pub fn write_units(
+        compiler: &'b mut Compiler<'a>,
+        program: Node,
+        dir: &Path,
+    ) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir.join("modules"))?;
+
+        // Writing consumes the analyzed modules, so the units get their own copy
+        let imports = compiler.imports.clone();
+        CBackend::header(compiler, &dir.join("prelude.h"))?.write_prelude(program.clone());
+        compiler.imports = imports;
+
+        let mut modules: Vec<(PathBuf, Vec<(PathBuf, Node)>)> = compiler.imports.drain().collect();
+        modules.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let main = dir.join("main.c");
+        let mut units = vec![main.clone()];
+        for (module, files) in modules {
+            let name = module.to_string_lossy().replace('/', ".");
+            let path = dir.join("modules").join(format!("{name}.c"));
+            let mut backend = CBackend::unit(compiler, &path)?;
+            backend.definitions = true;
+            backend.ws("#include \"../prelude.h\"\n\n");
+            for (file, program) in files {
+                backend.compiler.enter_file(file);
+                backend.write(program);
+                backend.compiler.leave_file();
+            }
+            units.push(path);
+        }
+
+        // Every module was written, so the program's `use`s don't write anything
+        let mut backend = CBackend::unit(compiler, &main)?;
+        backend.ws("#include \"prelude.h\"\n\n");
+        backend.write(program);
+        Ok(units)
+    }
+
+    // Writes declarations for the modules that the program uses.
+    fn write_prelude(&mut self, program: Node) {
+        let NodeKind::Program(nodes) = program.node else {
+            return;
+        };
+        for node in nodes {
+            if let NodeKind::TopUse(_) = node.node {
+                self.write(node);
+            }
         }
     }
 
-    pub fn throw(&self, error: CompilerError, help: Option<&str>) -> ! {
-        self.compiler.throw(error, help, *self.node.clone())
+    // Whether a top level node only declares things, which units of separately
+    // compiled modules get from the prelude instead.
+    fn is_declaration(node: &Node) -> bool {
+        match &node.node {
+            NodeKind::TopInstance(node) => Self::is_declaration(node),
+            node => matches!(
+                node,
+                NodeKind::Raw(_)
+                    | NodeKind::TopRec { .. }
+                    | NodeKind::TopDef { .. }
+                    | NodeKind::TopTagRec { .. }
+                    | NodeKind::TopGeneric { .. }
+            ),
+        }
+    }
+
+    // Writes the program wrapped in an include guard named after the header,
+    // i.e, `MY_LIB_H` for `my-lib.h`.
+    pub fn write_header(&mut self, program: Node, path: &Path) {
+        let stem = path
+            .file_stem()
+            .map(|it| it.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut guard: String = stem
+            .chars()
+            .map(|it| {
+                if it.is_ascii_alphanumeric() {
+                    it.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if guard.starts_with(|it: char| it.is_ascii_digit()) {
+            guard.insert(0, '_');
+        }
+        guard.push_str("_H");
+
+        self.w(format_args!("#ifndef {guard}\n#define {guard}\n\n"));
+        self.write(program);
+        self.w(format_args!("\n#endif // {guard}\n"));
     }
 
     pub fn get_symbol(&self, symbol: String) -> Option<&Symbol> {
         self.compiler.symbols.get_symbol(symbol)
     }
 
+    // The name of a symbol in C, where packages are separated by `$` unless the
+    // symbol is `#extern`. Statics that share their name with statics in other
+    // files are numbered, so that the files can be combined.
+    pub fn c_name(&self, name: &str) -> String {
+        if let Some(it) = self.compiler.externs.get(name) {
+            return it.clone();
+        }
+        if let Some(it) = self.compiler.instances.get(name) {
+            return it.c_name.clone();
+        }
+        let c_name = name.replace('\'', Self::NAMESPACE_SEP);
+        match self.compiler.symbols.static_index(name) {
+            0 => c_name,
+            index => format!("{c_name}{}static{index}", Self::NAMESPACE_SEP),
+        }
+    }
+
     pub fn w(&mut self, fmt: fmt::Arguments<'_>) {
-        self.compiler
-            .output_file
+        self.output
             .write_fmt(fmt)
             .expect("failed to write to output file");
     }
 
     pub fn ws(&mut self, s: &str) {
-        self.compiler
-            .output_file
+        self.output
             .write(s.as_bytes())
             .expect("failed to write to output file");
     }
@@ -94,6 +235,9 @@ impl<'a, 'b> CBackend<'a, 'b> {
         self.w(format_args!("#pragma region \"file: {file_path}\"\n"));
 
         for node in program {
+            if self.definitions && Self::is_declaration(&node) {
+                continue;
+            }
             self.write(node);
         }
 
@@ -133,7 +277,7 @@ impl<'a, 'b> CBackend<'a, 'b> {
         } else {
             self.w(format_args!(
                 "{}{}{}",
-                name.replace('\'', Self::NAMESPACE_SEP),
+                self.c_name(&name),
                 "*".repeat(pointers.into()),
                 CBackend::get_type_array_str(arrays)
             ))
@@ -146,7 +290,7 @@ impl<'a, 'b> CBackend<'a, 'b> {
         } else {
             self.w(format_args!(
                 "{}{}{}",
-                typ.name.replace('\'', Self::NAMESPACE_SEP),
+                self.c_name(&typ.name),
                 "*".repeat(typ.pointers.into()),
                 CBackend::get_type_array_str(typ.arrays)
             ))
@@ -161,6 +305,7 @@ impl<'a, 'b> CBackend<'a, 'b> {
                 arrays,
                 funptr_args,
                 funptr_rets,
+                ..
             } => self.typ(pointers, name, arrays, funptr_args, funptr_rets),
             _ => panic!("named_typ_from_node: node was not of Node::Type"),
         }
@@ -199,7 +344,7 @@ impl<'a, 'b> CBackend<'a, 'b> {
         } else {
             self.w(format_args!(
                 "{} {}{}{}",
-                name.replace('\'', Self::NAMESPACE_SEP),
+                self.c_name(&name),
                 "*".repeat(pointers.into()),
                 id.replace('\'', Self::NAMESPACE_SEP),
                 CBackend::get_type_array_str(arrays)
@@ -232,7 +377,7 @@ impl<'a, 'b> CBackend<'a, 'b> {
         } else {
             self.w(format_args!(
                 "{} {}{}{}",
-                typ.name.replace('\'', Self::NAMESPACE_SEP),
+                self.c_name(&typ.name),
                 "*".repeat(typ.pointers.into()),
                 id.replace('\'', Self::NAMESPACE_SEP),
                 CBackend::get_type_array_str(typ.arrays)
@@ -248,6 +393,7 @@ impl<'a, 'b> CBackend<'a, 'b> {
                 arrays,
                 funptr_args,
                 funptr_rets,
+                ..
             } => self.named_typ(id, pointers, name, arrays, funptr_args, funptr_rets),
             _ => panic!("named_typ_from_node: node was not of Node::Type"),
         }
@@ -291,38 +437,21 @@ impl<'a, 'b> CBackend<'a, 'b> {
 
     // #region: Top level statements
 
+    // The module's files were parsed during analysis, so we just write them.
     pub fn top_use(&mut self, path: PathBuf) {
-        let file_paths = self.compiler.get_use_paths(path.clone());
-        if let Ok(file_paths) = file_paths {
-            for path in file_paths {
-                if !path.exists() {
-                    self.throw(
-                        CompilerError::ImportError(format!("no such module: {path:?}")),
-                        None,
-                    );
-                }
-
-                if self.compiler.uses(&path) {
-                    continue;
-                }
-
-                self.compiler.file_stack.push(path.clone());
-
-                self.compiler.usages.push(path.clone());
-                let code = fs::read_to_string(path.clone()).unwrap();
-                let mut parser = Parser::new(Lexer::new(path, &code));
-                let program = parser.parse(false);
-                self.write(program);
-
-                self.compiler.file_stack.pop();
-            }
-        } else {
-            self.throw(CompilerError::ImportError(file_paths.err().unwrap()), None)
+        let programs = self.compiler.imports.remove(&path).unwrap_or_default();
+        for (path, program) in programs {
+            self.compiler.enter_file(path);
+            self.write(program);
+            self.compiler.leave_file();
         }
     }
 
     pub fn top_pkg(&mut self, namespace: String, statements: Vec<Node>) {
         for node in statements {
+            if self.definitions && Self::is_declaration(&node) {
+                continue;
+            }
             self.pkg_statement(node, &namespace);
         }
     }
@@ -333,112 +462,148 @@ impl<'a, 'b> CBackend<'a, 'b> {
         id: String,
         params: Vec<(String, Node)>,
         rets: Box<Node>,
+        contracts: Vec<Node>,
         expr: Box<Node>,
     ) {
+        // Static functions can't be called from other files anyway, and
+        // extern ones are already declared by C
+        if tags.contains(&FunTags::Extern) || self.header && tags.contains(&FunTags::Static) {
+            return;
+        }
+
         for hashtag in tags.clone() {
             match hashtag {
                 FunTags::NoRet => self.ws("noreturn "), // TODO: is noreturn compiler-specific?
-                FunTags::Extern => unimplemented!(),
-                FunTags::Inline => self.ws("inline "),
+                FunTags::Extern => {}
+                // Plain `inline` never emits a definition in gcc, so calls fail to link
+                FunTags::Inline if self.header => {}
+                FunTags::Inline if tags.contains(&FunTags::Static) => self.ws("inline "),
+                FunTags::Inline => self.ws("extern inline "),
                 FunTags::Static => self.ws("static "),
+                FunTags::Loose => {}
             }
         }
 
         self.typ_from_node((*rets).clone());
-        self.w(format_args!(" {}(", id.replace('\'', Self::NAMESPACE_SEP)));
-        self.compiler.push_scope();
-
-        self.compiler.add_fun(
-            id,
-            tags,
-            params
-                .iter()
-                .map(|(_, it)| SeaType::from_node(it.clone()).unwrap())
-                .collect::<Vec<SeaType>>(),
-            SeaType::from_node(*rets).unwrap(),
-        );
+        self.w(format_args!(" {}(", self.c_name(&id)));
 
         if params.len() > 0 {
             let len = params.len() - 1;
             let mut index = 0;
             for (param_id, param_type) in params {
-                self.named_typ_from_node(param_id.to_string(), param_type.clone());
+                self.named_typ_from_node(param_id.to_string(), param_type);
                 if index != len {
                     self.ws(", ")
                 }
-                self.compiler.symbols.add_scoped_symbol(
-                    param_id.to_string(),
-                    self.compiler.scope,
-                    Symbol::Var {
-                        typ: SeaType::from_node(param_type).unwrap(),
-                        mutable: true,
-                    },
-                );
                 index += 1;
             }
         }
 
+        if self.header {
+            self.ws(");\n\n");
+            return;
+        }
+
         self.ws(")\n");
-        self.write(*expr);
+        let is_kind = |it: &Node, of: ContractKind| {
+            matches!(it.node, NodeKind::Contract { kind, .. } if kind == of)
+        };
+        let preconditions: Vec<&Node> = contracts
+            .iter()
+            .filter(|it| is_kind(it, ContractKind::Arg))
+            .collect();
+        let postconditions: Vec<Node> = contracts
+            .iter()
+            .filter(|it| is_kind(it, ContractKind::Ret))
+            .cloned()
+            .collect();
+        if preconditions.is_empty() && postconditions.is_empty() {
+            self.write(*expr);
+        } else {
+            // The body is a block of its own, so the preconditions go before it
+            self.ws("{\n");
+            for it in preconditions {
+                self.check_contract(it);
+            }
+            self.postconditions = (!postconditions.is_empty()).then_some((*rets, postconditions));
+            self.write(*expr);
+            self.postconditions = None;
+            self.ws("\n}");
+        }
         self.ws("\n\n");
-        self.compiler.pop_scope();
+    }
+
+    // Checks a contract's condition at runtime, exiting with where the contract
+    // was declared if it doesn't hold.
+    fn check_contract(&mut self, contract: &Node) {
+        let NodeKind::Contract { params, text, .. } = &contract.node else {
+            return;
+        };
+        let file = self.compiler.file_stack.last().unwrap().to_string_lossy();
+        let message = format!(
+            "{file}:{}:{}: contract failed: {text}",
+            contract.line, contract.column
+        )
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+        self.ws("if (!(");
+        self.write(params[0].clone());
+        self.w(format_args!(
+            ")) {{\nfprintf(stderr, \"%s\\n\", \"{message}\");\nexit(1);\n}}\n"
+        ));
     }
 
     pub fn top_rec(&mut self, tags: Vec<RecTags>, id: String, fields: Vec<(String, Node)>) {
+        if tags.contains(&RecTags::Extern) {
+            return;
+        }
+
         let mut is_union = false;
         for hashtag in tags.clone() {
             match hashtag {
                 RecTags::Union => is_union = true,
+                RecTags::Extern => {}
                 RecTags::Static => self.ws("static "), //todo: can structs even be static??? is it ever used if so???
             }
         }
 
         // Write a forward declaration
+        let c_name = self.c_name(&id);
         self.ws("typedef ");
         self.ws(if is_union { "union " } else { "struct " });
-        self.ws(id.replace('\'', Self::NAMESPACE_SEP).as_str());
+        self.ws(c_name.as_str());
         self.ws(" ");
-        self.ws(id.replace('\'', Self::NAMESPACE_SEP).as_str());
+        self.ws(c_name.as_str());
         self.ws(";\n");
 
         // Write the struct declaration
         self.ws("typedef ");
         self.ws(if is_union { "union " } else { "struct " });
-        self.ws(id.replace('\'', Self::NAMESPACE_SEP).as_str());
+        self.ws(c_name.as_str());
         self.ws("{\n");
         for (field_name, field_type) in &fields {
             self.ws("\t");
             self.named_typ_from_node(field_name.clone(), field_type.clone());
             self.ws(";\n");
         }
-        self.w(format_args!(
-            "}} {};\n\n",
-            id.replace('\'', Self::NAMESPACE_SEP)
-        ));
-
-        self.compiler.add_rec(
-            id,
-            tags,
-            fields
-                .iter()
-                .map(|(name, typ)| (name.clone(), SeaType::from_node(typ.clone()).unwrap()))
-                .collect::<Vec<(String, SeaType)>>(),
-        );
+        self.w(format_args!("}} {c_name};\n\n"));
     }
 
     pub fn top_def(&mut self, tags: Vec<DefTags>, id: String, typ: Node) {
+        if tags.contains(&DefTags::Extern) {
+            return;
+        }
+
         for hashtag in tags.clone() {
             match hashtag {
+                DefTags::Extern => {}
                 DefTags::Static => self.ws("static "),
             }
         }
 
         self.ws("typedef ");
-        self.named_typ_from_node(id.replace('\'', Self::NAMESPACE_SEP), typ.clone());
+        self.named_typ_from_node(self.c_name(&id), typ);
         self.ws(";\n\n");
-
-        self.compiler
-            .add_def(id, tags, SeaType::from_node(typ).unwrap());
     }
 
     pub fn top_tag(
@@ -447,66 +612,77 @@ impl<'a, 'b> CBackend<'a, 'b> {
         id: String,
         entries: Vec<(String, Option<Box<Node>>)>,
     ) {
+        if tags.contains(&TagTags::Extern) {
+            return;
+        }
+
         let mut skip_helpers = false;
         for hashtag in tags.clone() {
             match hashtag {
+                TagTags::Extern => {}
+                // The prelude already has the enum, only its helpers are written
+                TagTags::Static if self.definitions => {}
                 TagTags::Static => self.ws("static "),
                 TagTags::NoHelpers => skip_helpers = true,
             }
         }
 
         let namespaced_id = id.replace('\'', Self::NAMESPACE_SEP);
-        self.ws("typedef enum {\n");
-        for (entry, value) in &entries {
-            self.w(format_args!(
-                "\t{namespaced_id}{}{entry}",
-                Self::NAMESPACE_SEP
-            ));
-            if value.is_some() {
-                self.ws(" = ");
-                self.write(*value.clone().unwrap());
+        if !self.definitions {
+            self.ws("typedef enum {\n");
+            for (entry, value) in &entries {
+                self.w(format_args!(
+                    "\t{namespaced_id}{}{entry}",
+                    Self::NAMESPACE_SEP
+                ));
+                if value.is_some() {
+                    self.ws(" = ");
+                    self.write(*value.clone().unwrap());
+                }
+                self.ws(",\n");
             }
-            self.ws(",\n");
+            self.w(format_args!("}} {namespaced_id};\n\n"));
         }
-        self.w(format_args!("}} {namespaced_id};\n\n"));
-
-        self.compiler.add_tag(
-            id.clone(),
-            tags,
-            entries
-                .iter()
-                .map(|it| it.0.clone())
-                .collect::<Vec<String>>(),
-        );
 
         if entries.len() == 0 || skip_helpers {
             return;
         }
 
         // Create the `entries` array
-        self.w(format_args!(
-            "const {} {}{}{}[] = {{\n",
-            namespaced_id,
-            namespaced_id,
-            Self::NAMESPACE_SEP,
-            "entries"
-        ));
-        entries.iter().for_each(|it| {
+        if self.header {
             self.w(format_args!(
-                "\t{}{}{},\n",
+                "extern const {namespaced_id} {namespaced_id}{}entries[];\n",
+                Self::NAMESPACE_SEP
+            ));
+            self.w(format_args!(
+                "extern const int {namespaced_id}{}len;\n\n",
+                Self::NAMESPACE_SEP
+            ));
+        } else {
+            self.w(format_args!(
+                "const {} {}{}{}[] = {{\n",
+                namespaced_id,
                 namespaced_id,
                 Self::NAMESPACE_SEP,
-                it.0
+                "entries"
             ));
-        });
-        self.ws("};\n\n");
+            entries.iter().for_each(|it| {
+                self.w(format_args!(
+                    "\t{}{}{},\n",
+                    namespaced_id,
+                    Self::NAMESPACE_SEP,
+                    it.0
+                ));
+            });
+            self.ws("};\n\n");
 
-        self.w(format_args!(
-            "const int {}{}len = {};\n\n",
-            namespaced_id,
-            Self::NAMESPACE_SEP,
-            entries.len()
-        ));
+            self.w(format_args!(
+                "const int {}{}len = {};\n\n",
+                namespaced_id,
+                Self::NAMESPACE_SEP,
+                entries.len()
+            ));
+        }
 
         // to_str and from_str functions. I hope you're ready for some truly horrendous code
         // to_str
@@ -535,6 +711,7 @@ impl<'a, 'b> CBackend<'a, 'b> {
                     pointers: 0,
                     name: id.clone(),
                     arrays: vec![],
+                    generics: vec![],
                     funptr_args: None,
                     funptr_rets: None,
                 }),
@@ -543,9 +720,11 @@ impl<'a, 'b> CBackend<'a, 'b> {
                 pointers: 0,
                 name: "String".to_string(),
                 arrays: vec![],
+                generics: vec![],
                 funptr_args: None,
                 funptr_rets: None,
             })),
+            vec![],
             Box::new(Node::of_kind(NodeKind::ExprBlock(vec![
                 Node::of_kind(NodeKind::StatSwitch {
                     switch: Box::new(Node::of_kind(NodeKind::ExprIdentifier("it".to_string()))),
@@ -557,16 +736,11 @@ impl<'a, 'b> CBackend<'a, 'b> {
             ]))),
         );
 
-        // from_str
-        if !self.compiler.uses(&PathBuf::from("std/str")) {
-            // If `std` is in usage, then we can probably add std/str implicitly.
-            // If it *isn't* in usage then the user is likely using --nostd.
-            if self.compiler.uses(&PathBuf::from("std")) {
-                self.top_use(PathBuf::from("std/str"))
-            } else {
-                return;
-            }
+        // from_str, analysis only registers it if std/str is available
+        if self.get_symbol(format!("{id}'from_str")).is_none() {
+            return;
         }
+        self.top_use(PathBuf::from("std/str"));
         let make_if_cond_for = |entry: String, else_: NodeKind| NodeKind::StatIf {
             cond: Box::new(Node::of_kind(NodeKind::ExprInvoke {
                 left: Box::new(Node::of_kind(NodeKind::ExprIdentifier(
@@ -601,6 +775,7 @@ impl<'a, 'b> CBackend<'a, 'b> {
                     pointers: 0,
                     name: "String".to_string(),
                     arrays: vec![],
+                    generics: vec![],
                     funptr_args: None,
                     funptr_rets: None,
                 }),
@@ -609,9 +784,11 @@ impl<'a, 'b> CBackend<'a, 'b> {
                 pointers: 0,
                 name: id.clone(),
                 arrays: vec![],
+                generics: vec![],
                 funptr_args: None,
                 funptr_rets: None,
             })),
+            vec![],
             Box::new(Node::of_kind(NodeKind::ExprBlock(vec![Node::of_kind(
                 from_str_if_chain,
             )]))),
@@ -625,23 +802,19 @@ impl<'a, 'b> CBackend<'a, 'b> {
         entries: Vec<(String, Vec<(String, Node)>)>,
     ) {
         let is_static = tags.contains(&TagRecTags::Static);
-
-        if is_static {
-            self.ws("static ");
-        }
-        self.ws("typedef enum {\n");
-        let namespaced_id = id.replace('\'', Self::NAMESPACE_SEP);
-        for (entry_id, _entry_fields) in &entries {
-            self.w(format_args!(
-                "\t{}{}{},\n",
-                namespaced_id,
-                Self::NAMESPACE_SEP,
-                entry_id
-            ))
-        }
-        self.w(format_args!("}} _{namespaced_id}_tag;\n\n"));
-
-        let mut mapped_entries: Vec<(String, Vec<(String, SeaType)>)> = vec![];
+        let namespaced_id = self.c_name(&id);
+
+        // Instances share their generic's kinds, see `top_generic`
+        let tag_id = match self.compiler.instances.get(&id) {
+            Some(instance) => instance.generic.replace('\'', Self::NAMESPACE_SEP),
+            None => {
+                if is_static {
+                    self.ws("static ");
+                }
+                self.tag_rec_kinds(&namespaced_id, &entries);
+                namespaced_id.clone()
+            }
+        };
 
         for (entry_id, entry_fields) in &entries {
             self.ws("typedef struct { ");
@@ -651,24 +824,13 @@ impl<'a, 'b> CBackend<'a, 'b> {
                 self.ws("; ");
             }
             self.w(format_args!("}} {name};\n"));
-            let mapped_fields = entry_fields
-                .iter()
-                .map(|(field_name, field_typ)| {
-                    (
-                        field_name.clone(),
-                        SeaType::from_node(field_typ.clone()).unwrap(),
-                    )
-                })
-                .collect::<Vec<(String, SeaType)>>();
-            mapped_entries.push((entry_id.clone(), mapped_fields.clone()));
-            self.compiler.add_rec(name, vec![], mapped_fields);
         }
 
         if is_static {
             self.ws("static ");
         }
         self.ws("typedef struct {\n");
-        self.w(format_args!("\t_{namespaced_id}_tag kind;\n"));
+        self.w(format_args!("\t_{tag_id}_tag kind;\n"));
         self.ws("\tunion {\n");
         for (entry_id, _) in &entries {
             self.w(format_args!(
@@ -677,8 +839,142 @@ impl<'a, 'b> CBackend<'a, 'b> {
         }
         self.ws("\t};\n");
         self.w(format_args!("}} {namespaced_id};\n\n"));
+    }
+
+    // Writes the enum of a tag rec's kinds, i.e, `Shape$Circle`.
+    fn tag_rec_kinds(&mut self, c_name: &str, entries: &[(String, Vec<(String, Node)>)]) {
+        self.ws("typedef enum {\n");
+        for (entry_id, _entry_fields) in entries {
+            self.w(format_args!(
+                "\t{}{}{},\n",
+                c_name,
+                Self::NAMESPACE_SEP,
+                entry_id
+            ))
+        }
+        self.w(format_args!("}} _{c_name}_tag;\n\n"));
+    }
+
+    // Generics are written as their instances, which are added to the program by
+    // analysis. The kinds of a generic tag rec are shared by its instances, so
+    // that `Option'None` is the same for every `Option[T]`.
+    pub fn top_generic(&mut self, id: String, node: Node) {
+        if let NodeKind::TopTagRec { entries, .. } = node.node {
+            let c_name = id.replace('\'', Self::NAMESPACE_SEP);
+            self.tag_rec_kinds(&c_name, &entries);
+        }
+    }
+
+    // Writes the vtable and `dyn` recs that analysis declared for the trait,
+    // then a function for each method that calls it through a `dyn`'s vtable.
+    pub fn top_trait(&mut self, id: String) {
+        let Some(Symbol::Trait { methods }) = self.get_symbol(id.clone()).cloned() else {
+            return;
+        };
+
+        if !self.definitions {
+            for name in [traits::vtable_name(&id), traits::dyn_name(&id)] {
+                let Some(Symbol::Rec { fields, .. }) = self.get_symbol(name.clone()).cloned()
+                else {
+                    continue;
+                };
+                let fields = fields
+                    .iter()
+                    .map(|(field, typ)| (field.clone(), typ.to_node()))
+                    .collect();
+                self.top_rec(vec![], name, fields);
+            }
+        }
+
+        let node = |kind: NodeKind| Node::of_kind(kind);
+        let id_node = |id: &str| node(NodeKind::ExprIdentifier(id.to_string()));
+        let dot = |left: Node, right: &str| {
+            node(NodeKind::ExprBinaryOperator {
+                kind: OperatorKind::Dot,
+                left: Box::new(left),
+                right: Box::new(id_node(right)),
+            })
+        };
+
+        for (method, params, rets) in methods {
+            // `self.vtable^.method(self.self, arg1, ...)`
+            let mut args = vec![dot(id_node("self"), "self")];
+            let mut fun_params = vec![(
+                "self".to_string(),
+                SeaType::named_type(&traits::dyn_name(&id)).to_node(),
+            )];
+            for (index, param) in params.iter().enumerate().skip(1) {
+                let name = format!("arg{index}");
+                args.push(id_node(&name));
+                fun_params.push((name, param.to_node()));
+            }
+            let vtable = node(NodeKind::ExprUnaryOperator {
+                kind: OperatorKind::Deref,
+                value: Box::new(dot(id_node("self"), "vtable")),
+            });
+            let call = node(NodeKind::ExprInvoke {
+                left: Box::new(dot(vtable, &method)),
+                params: args,
+            });
+            let body = if rets == SeaType::named_type("void") {
+                NodeKind::StatExpr(Box::new(call))
+            } else {
+                NodeKind::StatRet(Some(Box::new(call)))
+            };
+
+            self.top_fun(
+                vec![],
+                traits::method_name(&id, &method),
+                fun_params,
+                Box::new(rets.to_node()),
+                vec![],
+                Box::new(node(NodeKind::ExprBlock(vec![node(body)]))),
+            );
+        }
+    }
+
+    // Writes the impl's methods, and its vtable, which `as dyn` points to.
+    pub fn top_impl(&mut self, trait_: String, rec: String, funs: Vec<Node>) {
+        for fun in funs {
+            if let NodeKind::TopFun {
+                tags,
+                id,
+                params,
+                rets,
+                contracts,
+                expr,
+            } = fun.node
+            {
+                let id = traits::impl_method_name(&trait_, &rec, &id);
+                self.top_fun(tags, id, params, rets, contracts, expr);
+            }
+        }
+
+        let vtable_type = traits::vtable_name(&trait_);
+        let Some(Symbol::Rec { fields, .. }) = self.get_symbol(vtable_type.clone()).cloned() else {
+            return;
+        };
+        let vtable = self.c_name(&traits::impl_vtable_name(&trait_, &rec));
+        if self.header {
+            self.w(format_args!(
+                "extern {} {vtable};\n\n",
+                self.c_name(&vtable_type)
+            ));
+            return;
+        }
 
-        self.compiler.add_tag_rec(id, tags, mapped_entries);
+        // The methods take `^Rec` instead of `^void`, so they're cast
+        self.w(format_args!(
+            "{} {vtable} = {{\n",
+            self.c_name(&vtable_type)
+        ));
+        for (method, typ) in fields {
+            self.w(format_args!("\t.{method} = ("));
+            self.named_typ_from_seatype(String::new(), typ);
+            let fun = self.c_name(&traits::impl_method_name(&trait_, &rec, &method));
+            self.w(format_args!("){fun},\n"));
+        }
+        self.ws("};\n\n");
     }
 
     // #endregion: Top level statements
@@ -686,6 +982,22 @@ impl<'a, 'b> CBackend<'a, 'b> {
     // #region: Statements
 
     pub fn stat_ret(&mut self, node: Option<Node>) {
+        // The value is kept in `ret` for the function's `@ret` contracts to check
+        if let (Some(value), Some((rets, contracts))) = (&node, self.postconditions.clone()) {
+            self.ws("{\n");
+            self.named_typ_from_node("ret".to_string(), rets);
+            self.ws(" = ");
+            self.write(value.clone());
+            self.ws(";\n");
+            self.write_deferred(true, false);
+            for it in &contracts {
+                self.check_contract(it);
+            }
+            self.ws("return ret;\n}\n");
+            self.block_stack.last_mut().unwrap().returned = true;
+            return;
+        }
+
         self.write_deferred(true, false);
         self.ws("return ");
         node.map(|it| self.write(it));
@@ -765,7 +1077,7 @@ impl<'a, 'b> CBackend<'a, 'b> {
 
     pub fn stat_for_range(&mut self, var: Option<String>, from: Node, to: Node, expr: Node) {
         self.ws("for (");
-        let v = var.unwrap_or_else(|| "_i".to_string());
+        let v = var.map_or_else(|| "_i".to_string(), |it| self.c_name(&it));
         self.w(format_args!("int {v} = "));
         self.write(from);
         self.ws(" ; ");
@@ -825,7 +1137,8 @@ impl<'a, 'b> CBackend<'a, 'b> {
     }
 
     pub fn expr_id(&mut self, id: String) {
-        self.ws(id.replace('\'', Self::NAMESPACE_SEP).as_str());
+        let c_name = self.c_name(&id);
+        self.ws(&c_name);
     }
 
     pub fn expr_block(&mut self, nodes: Vec<Node>) {
@@ -847,51 +1160,33 @@ impl<'a, 'b> CBackend<'a, 'b> {
     pub fn expr_new(&mut self, id: String, params: Vec<Node>) {
         self.w(format_args!(
             "({}){{",
-            id.replace('\'', Self::NAMESPACE_SEP)
+            self.c_name(&id)
         ));
-        let symbol = self.get_symbol(id.clone());
-
-        if symbol.is_some_and(|it| it.instantiatable()) {
-            // When instantiating tag recs, we want to explicitly specify which union we are instantiating
-            match symbol.unwrap() {
-                Symbol::TagRec {
-                    tags: _,
-                    entries: _,
-                } => {
-                    if params.len() == 0 {
-                        self.throw(CompilerError::TagRecInstantiateWithoutKind, None);
-                    }
-                    let kind = &params[0];
-                    let kind_str = match &kind.node {
-                        NodeKind::ExprIdentifier(id) => id,
-                        _ => self.throw(
-                            CompilerError::TagRecInstantiateWithoutKind,
-                            Some("the first parameter must be an entry in the tag rec (it currently is not)"),
-                        ),
-                    };
-                    self.write(kind.clone());
-                    if params.len() > 1 {
-                        let field = kind_str.split('\'').last().unwrap();
-                        self.w(format_args!(", .{field}={{",));
-                        self.comma_separated(params[1..].to_vec());
-                        self.ws("}");
-                    }
-                }
-                _ => {
-                    self.comma_separated(params);
+        // When instantiating tag recs, we want to explicitly specify which union we are instantiating
+        match self.get_symbol(id) {
+            Some(Symbol::TagRec {
+                tags: _,
+                entries: _,
+            }) => {
+                // Analysis ensures that the first parameter is the kind
+                let kind = &params[0];
+                let kind_str = match &kind.node {
+                    NodeKind::ExprIdentifier(id) => id.clone(),
+                    _ => unreachable!(),
+                };
+                self.write(kind.clone());
+                if params.len() > 1 {
+                    let field = kind_str.split('\'').last().unwrap();
+                    self.w(format_args!(", .{field}={{",));
+                    self.comma_separated(params[1..].to_vec());
+                    self.ws("}");
                 }
             }
-            self.ws("}")
-        } else {
-            if symbol.is_none() {
-                self.throw(CompilerError::UnknownSymbol(id), None);
-            } else {
-                self.throw(
-                    CompilerError::Uninstantiatable(id, symbol.unwrap().clone()),
-                    None,
-                );
+            _ => {
+                self.comma_separated(params);
             }
         }
+        self.ws("}")
     }
 
     pub fn expr_unary_operator(&mut self, kind: OperatorKind, value: Node) {
@@ -995,41 +1290,30 @@ impl<'a, 'b> CBackend<'a, 'b> {
         self.ws("}");
     }
 
+    // Variable types are always known here, analysis fills in inferred ones.
+    // Headers only get declarations, since the values live in the code.
     pub fn expr_var(&mut self, name: String, typ: Option<Node>, value: Node) {
-        let seatyp = match typ {
-            Some(typ) => {
-                self.named_typ_from_node(name.clone(), typ.clone());
-                SeaType::from_node(typ).unwrap()
-            }
-            _ => match infer_type_of_node(&self.compiler, &value) {
-                Ok(it) => {
-                    self.named_typ_from_seatype(name.clone(), it.clone());
-                    it
-                }
-                Err(why) => self.throw(CompilerError::InferenceError(why), None),
-            },
-        };
+        if self.header {
+            self.ws("extern ");
+            self.named_typ_from_node(name, typ.expect("variable type was not inferred"));
+            return;
+        }
+        self.named_typ_from_node(name, typ.expect("variable type was not inferred"));
         self.ws(" = ");
         self.write(value);
-        self.compiler.add_var(name, seatyp, true);
     }
 
     pub fn expr_let(&mut self, name: String, typ: Option<Node>, value: Node) {
+        if self.header {
+            self.ws("extern ");
+        }
         self.ws("const ");
-        let seatyp = match typ {
-            Some(typ) => {
-                self.named_typ_from_node(name.clone(), typ.clone());
-                SeaType::from_node(typ).unwrap()
-            }
-            _ => {
-                let t = infer_type_of_node(&self.compiler, &value).unwrap();
-                self.named_typ_from_seatype(name.clone(), t.clone());
-                t
-            }
-        };
+        self.named_typ_from_node(name, typ.expect("variable type was not inferred"));
+        if self.header {
+            return;
+        }
         self.ws(" = ");
         self.write(value);
-        self.compiler.add_var(name, seatyp, false);
     }
 
     // #endregion Expressions
@@ -1046,12 +1330,14 @@ impl<'a, 'b> CBackend<'a, 'b> {
                 id,
                 params,
                 rets,
+                contracts,
                 expr,
             } => self.top_fun(
                 tags,
                 format_args!("{namespace}'{id}").to_string(),
                 params,
                 rets,
+                contracts,
                 expr,
             ),
             NodeKind::TopRec { tags, id, fields } => {
@@ -1066,6 +1352,17 @@ impl<'a, 'b> CBackend<'a, 'b> {
             NodeKind::TopTagRec { tags, id, entries } => {
                 self.top_tag_rec(tags, format_args!("{namespace}'{id}").to_string(), entries)
             }
+            NodeKind::TopTrait { id, methods: _ } => {
+                self.top_trait(format_args!("{namespace}'{id}").to_string())
+            }
+            NodeKind::TopImpl { trait_, rec, funs } => self.top_impl(trait_, rec, funs),
+            NodeKind::TopGeneric { params: _, node } => {
+                if let Some(id) = generics::declared_id(&node) {
+                    self.top_generic(format_args!("{namespace}'{id}").to_string(), *node)
+                }
+            }
+            // Instances already have their complete name
+            NodeKind::TopInstance(node) => self.write(*node),
             NodeKind::StatExpr(expr) => {
                 self.pkg_statement(*expr, namespace);
                 self.ws(";\n");
@@ -1080,7 +1377,8 @@ impl<'a, 'b> CBackend<'a, 'b> {
                 typ.map(|it| *it),
                 *value,
             ),
-            _ => self.throw(CompilerError::StatementNotAllowedAtTopLevel(), None),
+            // Analysis already reported anything else
+            _ => {}
         }
     }
 }
@@ -1091,12 +1389,17 @@ impl<'a, 'b> Backend for CBackend<'a, 'b> {
         match node.node {
             NodeKind::Program(nodes) => self.program(nodes),
             NodeKind::Raw(text) => self.raw(text),
+            // The parser already reported these, there's nothing to emit.
+            NodeKind::Error => {}
+            // Written by `top_fun`
+            NodeKind::Contract { .. } => {}
             NodeKind::Type {
                 pointers,
                 name,
                 arrays,
                 funptr_args,
                 funptr_rets,
+                ..
             } => self.typ(pointers, name, arrays, funptr_args, funptr_rets),
             NodeKind::TopUse(path_buf) => self.top_use(path_buf),
             NodeKind::TopPkg { name, statements } => self.top_pkg(name, statements),
@@ -1105,13 +1408,22 @@ impl<'a, 'b> Backend for CBackend<'a, 'b> {
                 id,
                 params,
                 rets,
+                contracts,
                 expr,
-            } => self.top_fun(tags, id, params, rets, expr),
+            } => self.top_fun(tags, id, params, rets, contracts, expr),
             NodeKind::TopRec { tags, id, fields } => self.top_rec(tags, id, fields),
             NodeKind::TopDef { tags, id, typ } => self.top_def(tags, id, *typ),
             NodeKind::TopTag { tags, id, entries } => self.top_tag(tags, id, entries),
             NodeKind::TopTagRec { tags, id, entries } => self.top_tag_rec(tags, id, entries),
-            NodeKind::TopPragma { id: _, params: _ } => self.compiler.handle_pragma(node),
+            NodeKind::TopPragma { id: _, params: _ } => {} // handled during analysis
+            NodeKind::TopTrait { id, methods: _ } => self.top_trait(id),
+            NodeKind::TopImpl { trait_, rec, funs } => self.top_impl(trait_, rec, funs),
+            NodeKind::TopGeneric { params: _, node } => {
+                if let Some(id) = generics::declared_id(&node) {
+                    self.top_generic(id, *node)
+                }
+            }
+            NodeKind::TopInstance(node) => self.write(*node),
             NodeKind::StatRet(node) => self.stat_ret(node.map(|it| *it)),
             NodeKind::StatIf { cond, expr, else_ } => {
                 self.stat_if(*cond, *expr, else_.map(|it| *it))
@@ -1130,6 +1442,8 @@ impl<'a, 'b> Backend for CBackend<'a, 'b> {
                 to,
                 expr,
             } => self.stat_for_range(var, *from, *to, *expr),
+            // Lowered to a range loop during analysis
+            NodeKind::StatForOf { .. } => {}
             NodeKind::StatContinue => {
                 self.write_deferred(false, false);
                 self.ws("continue;");
@@ -1181,7 +1495,7 @@ impl<'a, 'b> Backend for CBackend<'a, 'b> {
             NodeKind::ExprFalse => self.expr_false(),
             NodeKind::ExprIdentifier(id) => self.expr_id(id),
             NodeKind::ExprBlock(nodes) => self.expr_block(nodes),
-            NodeKind::ExprNew { id, params } => self.expr_new(id, params),
+            NodeKind::ExprNew { id, params, .. } => self.expr_new(id, params),
             NodeKind::ExprUnaryOperator { kind, value } => self.expr_unary_operator(kind, *value),
             NodeKind::ExprBinaryOperator { kind, left, right } => {
                 self.expr_binary_operator(kind, *left, *right)
@@ -0,0 +1,413 @@
+// `sea bindgen` reads a C header and writes Sea bindings for it: a package of
+// `#extern` declarations, which the C backend writes with their plain C names.
+// Declarations that can't be bound to are written as comments saying why.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::parse::lexer;
+
+use parse::{Base, CType, Decl, DeclParser};
+
+pub mod parse;
+
+enum Binding {
+    Rec {
+        name: String,
+        union: bool,
+        fields: Vec<(String, CType)>,
+    },
+    Tag {
+        name: String,
+        entries: Vec<String>,
+    },
+    Def {
+        name: String,
+        typ: CType,
+    },
+    Fun {
+        name: String,
+        params: Vec<(Option<String>, CType)>,
+        rets: CType,
+    },
+    Const {
+        name: String,
+        value: i64,
+    },
+}
+
+impl Binding {
+    fn name(&self) -> &str {
+        match self {
+            Binding::Rec { name, .. }
+            | Binding::Tag { name, .. }
+            | Binding::Def { name, .. }
+            | Binding::Fun { name, .. }
+            | Binding::Const { name, .. } => name,
+        }
+    }
+}
+
+pub struct Bindgen {
+    pub package: String,
+    pub include: String, // what the bindings `#include`, usually the header's file name
+    pub bindings: usize,
+    pub skipped: usize,
+    typedefs: HashMap<String, String>, // struct/union/enum tags to the typedefs naming them
+    declared: HashSet<String>,         // names of the types in the package
+}
+
+impl Bindgen {
+    pub fn new(package: String, include: String) -> Self {
+        Bindgen {
+            package,
+            include,
+            bindings: 0,
+            skipped: 0,
+            typedefs: HashMap::new(),
+            declared: HashSet::new(),
+        }
+    }
+
+    // Generates the bindings for `source`, which is the header's code.
+    pub fn generate(&mut self, source: &str) -> String {
+        let header = self
+            .include
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.include)
+            .to_string();
+        let decls = DeclParser::new(source, &header).parse();
+
+        // `typedef struct Vector2 { ... } Vector2;` is one record, named by its typedef
+        let mut defined: HashSet<String> = HashSet::new();
+        for decl in &decls {
+            match decl {
+                Decl::Typedef { name, typ } if typ.pointers == 0 && typ.arrays.is_empty() => {
+                    if let Base::Struct { tag, .. } | Base::Enum(tag) = &typ.base {
+                        self.typedefs.entry(tag.clone()).or_insert(name.clone());
+                    }
+                }
+                Decl::Record {
+                    tag,
+                    fields: Some(_),
+                    ..
+                }
+                | Decl::Enum { tag, .. } => {
+                    defined.insert(tag.clone());
+                }
+                _ => {}
+            }
+        }
+
+        let mut bindings: Vec<Result<Binding, String>> = vec![];
+        let mut names: HashSet<String> = HashSet::new();
+        let mut typedefs: Vec<String> = vec![]; // for tags without typedefs, which Sea needs
+        for decl in decls {
+            let binding = match decl {
+                Decl::Record {
+                    tag,
+                    union,
+                    fields: Some(fields),
+                } => {
+                    let name = self.tag_name(&tag);
+                    if name.starts_with('<') {
+                        continue; // only used inline, i.e, `struct { int x; } it;`
+                    }
+                    if !self.typedefs.contains_key(&tag) {
+                        let kind = if union { "union" } else { "struct" };
+                        typedefs.push(format!("typedef {kind} {tag} {tag};"));
+                    }
+                    Ok(Binding::Rec {
+                        name,
+                        union,
+                        fields,
+                    })
+                }
+                // Structs that are only declared, i.e, `struct Brush;`, are opaque
+                Decl::Record {
+                    tag,
+                    union,
+                    fields: None,
+                } => {
+                    if defined.contains(&tag)
+                        || self.typedefs.contains_key(&tag)
+                        || names.contains(&tag)
+                    {
+                        continue;
+                    }
+                    let kind = if union { "union" } else { "struct" };
+                    typedefs.push(format!("typedef {kind} {tag} {tag};"));
+                    Ok(Binding::Rec {
+                        name: tag,
+                        union,
+                        fields: vec![],
+                    })
+                }
+                Decl::Enum { tag, entries } => {
+                    let name = self.tag_name(&tag);
+                    if name.starts_with('<') {
+                        Err(format!(
+                            "anonymous enums aren't supported: {}",
+                            entries.join(", ")
+                        ))
+                    } else {
+                        if !self.typedefs.contains_key(&tag) {
+                            typedefs.push(format!("typedef enum {tag} {tag};"));
+                        }
+                        Ok(Binding::Tag { name, entries })
+                    }
+                }
+                Decl::Typedef { name, typ } => {
+                    let (tag, union) = match &typ.base {
+                        Base::Struct { tag, union } => (Some(tag.clone()), *union),
+                        Base::Enum(tag) => (Some(tag.clone()), false),
+                        _ => (None, false),
+                    };
+                    match tag {
+                        // The record itself is bound where it's defined
+                        Some(tag) if self.typedefs.get(&tag) == Some(&name) => {
+                            if defined.contains(&tag) {
+                                continue;
+                            }
+                            // Opaque types are records without any fields
+                            Ok(Binding::Rec {
+                                name,
+                                union,
+                                fields: vec![],
+                            })
+                        }
+                        _ => Ok(Binding::Def { name, typ }),
+                    }
+                }
+                Decl::Function {
+                    name,
+                    variadic: true,
+                    ..
+                } => Err(format!("`{name}`: variadic functions aren't supported")),
+                Decl::Function {
+                    name, params, rets, ..
+                } => Ok(Binding::Fun { name, params, rets }),
+                Decl::Const { name, value } => Ok(Binding::Const { name, value }),
+                Decl::Skipped(why) => Err(why),
+            };
+
+            // C allows declaring things more than once, Sea doesn't
+            if let Ok(it) = &binding {
+                if !names.insert(it.name().to_string()) {
+                    continue;
+                }
+            }
+            if let Ok(
+                Binding::Rec { name, .. } | Binding::Tag { name, .. } | Binding::Def { name, .. },
+            ) = &binding
+            {
+                self.declared.insert(name.clone());
+            }
+            bindings.push(binding);
+        }
+
+        let mut out = format!(
+            "// Bindings for {}, generated by `sea bindgen`\n\nraw [\n#include \"{}\"\n",
+            header, self.include
+        );
+        for it in typedefs {
+            out.push_str(&format!("{it}\n"));
+        }
+        out.push_str(&format!("]\n\npkg {} {{\n", self.package));
+
+        let mut first = true;
+        for binding in bindings {
+            let text = binding.and_then(|it| self.binding(it));
+            if !first {
+                out.push('\n');
+            }
+            first = false;
+            match text {
+                Ok(it) => {
+                    self.bindings += 1;
+                    for line in it.lines() {
+                        out.push_str(&format!("\t{line}\n"));
+                    }
+                }
+                Err(why) => {
+                    self.skipped += 1;
+                    out.push_str(&format!("\t// skipped {why}\n"));
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn tag_name(&self, tag: &str) -> String {
+        self.typedefs.get(tag).cloned().unwrap_or(tag.to_string())
+    }
+
+    fn binding(&self, binding: Binding) -> Result<String, String> {
+        match binding {
+            Binding::Rec {
+                name,
+                union,
+                fields,
+            } => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, typ)| Ok(format!("{}: {}", id(&name, field)?, self.typ(typ)?)))
+                    .collect::<Result<Vec<_>, String>>()
+                    .map_err(|why| format!("`{name}`: {why}"))?;
+                let tags = if union { "#(extern, union)" } else { "#extern" };
+                Ok(format!(
+                    "{tags}\nrec {}({})",
+                    id(&name, &name)?,
+                    fields.join(", ")
+                ))
+            }
+            Binding::Tag { name, entries } => {
+                for entry in &entries {
+                    id(&name, entry)?;
+                }
+                Ok(format!(
+                    "#extern\ntag {}({})",
+                    id(&name, &name)?,
+                    entries.join(", ")
+                ))
+            }
+            Binding::Def { name, typ } => {
+                let typ = self.typ(&typ).map_err(|why| format!("`{name}`: {why}"))?;
+                Ok(format!("#extern\ndef {} = {typ}", id(&name, &name)?))
+            }
+            Binding::Fun { name, params, rets } => {
+                let params = self
+                    .params(&params)
+                    .map_err(|why| format!("`{name}`: {why}"))?;
+                let rets = self.rets(&rets).map_err(|why| format!("`{name}`: {why}"))?;
+                Ok(format!(
+                    "#extern\nfun {}({params}){rets}",
+                    id(&name, &name)?
+                ))
+            }
+            Binding::Const { name, value } => {
+                let typ = if i32::try_from(value).is_ok() {
+                    "int"
+                } else {
+                    "i64"
+                };
+                Ok(format!("let {}: {typ} = {value}", id(&name, &name)?))
+            }
+        }
+    }
+
+    fn params(&self, params: &[(Option<String>, CType)]) -> Result<String, String> {
+        params
+            .iter()
+            .enumerate()
+            .map(|(index, (name, typ))| {
+                // Array parameters are pointers in C
+                let mut typ = typ.clone();
+                if !typ.arrays.is_empty() {
+                    typ.arrays.remove(0);
+                    typ.pointers += 1;
+                }
+                let typ = self.typ(&typ)?;
+                Ok(match name {
+                    Some(name) if lexer::is_keyword(name) => format!("{name}_: {typ}"),
+                    Some(name) => format!("{name}: {typ}"),
+                    None => format!("arg{index}: {typ}"),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map(|it| it.join(", "))
+    }
+
+    fn rets(&self, rets: &CType) -> Result<String, String> {
+        if rets.is_void() {
+            Ok(String::new())
+        } else {
+            Ok(format!(": {}", self.typ(rets)?))
+        }
+    }
+
+    // The Sea name for a C type, where types from the header are in the package.
+    fn typ(&self, typ: &CType) -> Result<String, String> {
+        let mut pointers = typ.pointers as usize;
+        let name = match &typ.base {
+            Base::Named(name) if name == "void" && pointers > 0 => {
+                pointers -= 1;
+                "Any".to_string()
+            }
+            Base::Named(name) => self.qualify(parse::typedef_name(name)),
+            Base::Struct { tag, .. } | Base::Enum(tag) => {
+                let name = self.tag_name(tag);
+                if name.starts_with('<') {
+                    return Err("anonymous types aren't supported".to_string());
+                }
+                self.qualify(&name)
+            }
+            Base::Function {
+                params,
+                rets,
+                variadic,
+            } => {
+                if *variadic {
+                    return Err("variadic function pointers aren't supported".to_string());
+                }
+                if pointers == 0 || !typ.arrays.is_empty() {
+                    return Err("function types are only supported as pointers".to_string());
+                }
+                pointers -= 1;
+                format!("fun ({}){}", self.param_types(params)?, self.rets(rets)?)
+            }
+        };
+
+        let arrays: String = typ.arrays.iter().map(|it| format!("[{it}]")).collect();
+        Ok(format!("{}{name}{arrays}", "^".repeat(pointers)))
+    }
+
+    fn param_types(&self, params: &[(Option<String>, CType)]) -> Result<String, String> {
+        params
+            .iter()
+            .map(|(_, typ)| self.typ(typ))
+            .collect::<Result<Vec<_>, String>>()
+            .map(|it| it.join(", "))
+    }
+
+    fn qualify(&self, name: &str) -> String {
+        if self.declared.contains(name) {
+            format!("{}'{name}", self.package)
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+// Checks that a name from C can be used in Sea, `of` is what it's a part of.
+fn id(of: &str, name: &str) -> Result<String, String> {
+    if lexer::is_keyword(name) {
+        Err(format!("`{of}`: `{name}` is a keyword in Sea"))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+// Turns a header's file name into a package name, i.e, `raylib-5.h` is `raylib_5`.
+pub fn package_name(header: &str) -> String {
+    let stem = header.rsplit('/').next().unwrap_or(header);
+    let stem = stem.split('.').next().unwrap_or(stem);
+    let mut name: String = stem
+        .chars()
+        .map(|it| {
+            if it.is_alphanumeric() || it == '_' {
+                it
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty()
+        || name.starts_with(|it: char| it.is_ascii_digit())
+        || lexer::is_keyword(&name)
+    {
+        name.insert(0, '_');
+    }
+    name
+}
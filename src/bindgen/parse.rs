@@ -0,0 +1,878 @@
+// A forgiving parser for the declarations in C headers. It understands enough
+// of C to read prototypes, structs, unions, enums, typedefs, and `#define`d
+// integers, and skips over anything else (function bodies, `#if`s, attributes).
+// Headers can be preprocessed, in which case only the declarations from the
+// header itself are kept, not the ones from the headers that it includes.
+
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tok {
+    Ident(String),
+    Number(String),
+    Str(String),
+    Punct(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    main: bool, // whether it's from the header itself, not an included one
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Base {
+    Named(String), // builtin types are already converted to their Sea names
+    Struct {
+        tag: String,
+        union: bool,
+    },
+    Enum(String),
+    Function {
+        params: Vec<(Option<String>, CType)>,
+        rets: Box<CType>,
+        variadic: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CType {
+    pub base: Base,
+    pub pointers: u8,
+    pub arrays: Vec<String>, // the size of each dimension, empty if it's unsized
+}
+
+impl CType {
+    pub fn is_void(&self) -> bool {
+        self.base == Base::Named("void".to_string()) && self.pointers == 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Decl {
+    // `fields` is `None` for forward declarations
+    Record {
+        tag: String,
+        union: bool,
+        fields: Option<Vec<(String, CType)>>,
+    },
+    Enum {
+        tag: String,
+        entries: Vec<String>,
+    },
+    Typedef {
+        name: String,
+        typ: CType,
+    },
+    Function {
+        name: String,
+        params: Vec<(Option<String>, CType)>,
+        rets: CType,
+        variadic: bool,
+    },
+    Const {
+        name: String,
+        value: i64,
+    },
+    Skipped(String), // why a declaration couldn't be read
+}
+
+// Words that don't change what a declaration means to Sea.
+const QUALIFIERS: [&str; 17] = [
+    "const",
+    "volatile",
+    "restrict",
+    "static",
+    "inline",
+    "extern",
+    "register",
+    "auto",
+    "__restrict",
+    "__restrict__",
+    "__inline",
+    "__inline__",
+    "__extension__",
+    "__const",
+    "_Noreturn",
+    "__cdecl",
+    "__stdcall",
+];
+
+// Words that take parenthesized arguments, which are skipped along with them.
+const ATTRIBUTES: [&str; 5] = [
+    "__attribute__",
+    "__attribute",
+    "__declspec",
+    "__asm__",
+    "__asm",
+];
+
+const BUILTINS: [&str; 10] = [
+    "void", "char", "short", "int", "long", "float", "double", "signed", "unsigned", "_Bool",
+];
+
+// #region: Tokens
+
+// Splits the header into tokens, handling preprocessor lines as it goes.
+fn tokenize(
+    source: &str,
+    header: &str,
+    decls: &mut Vec<Decl>,
+    empty: &mut HashSet<String>,
+) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<Token> = vec![];
+    let mut main = true;
+    let mut line_start = true;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+
+        if ch == '\n' {
+            line_start = true;
+            index += 1;
+            continue;
+        }
+        if ch.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        // Preprocessor lines, which can continue onto the next line with `\`
+        if ch == '#' && line_start {
+            let mut directive = String::new();
+            index += 1;
+            while index < chars.len() && chars[index] != '\n' {
+                if chars[index] == '\\' && chars.get(index + 1) == Some(&'\n') {
+                    index += 2;
+                    continue;
+                }
+                directive.push(chars[index]);
+                index += 1;
+            }
+            let in_main = main;
+            directive_of(&directive, header, &mut main, in_main, decls, empty);
+            continue;
+        }
+        line_start = false;
+
+        // Comments
+        if ch == '/' && chars.get(index + 1) == Some(&'/') {
+            while index < chars.len() && chars[index] != '\n' {
+                index += 1;
+            }
+            continue;
+        }
+        if ch == '/' && chars.get(index + 1) == Some(&'*') {
+            index += 2;
+            while index < chars.len()
+                && !(chars[index] == '*' && chars.get(index + 1) == Some(&'/'))
+            {
+                index += 1;
+            }
+            index += 2;
+            continue;
+        }
+
+        let start = index;
+        let tok = if ch.is_alphabetic() || ch == '_' {
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                index += 1;
+            }
+            Tok::Ident(chars[start..index].iter().collect())
+        } else if ch.is_ascii_digit() {
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '.') {
+                index += 1;
+            }
+            Tok::Number(chars[start..index].iter().collect())
+        } else if ch == '"' || ch == '\'' {
+            index += 1;
+            while index < chars.len() && chars[index] != ch {
+                if chars[index] == '\\' {
+                    index += 1;
+                }
+                index += 1;
+            }
+            index += 1;
+            Tok::Str(
+                chars[start + 1..(index - 1).min(chars.len())]
+                    .iter()
+                    .collect(),
+            )
+        } else if chars[index..].starts_with(&['.', '.', '.']) {
+            index += 3;
+            Tok::Punct("...".to_string())
+        } else {
+            index += 1;
+            Tok::Punct(ch.to_string())
+        };
+        tokens.push(Token { tok, main });
+    }
+
+    tokens
+}
+
+// Handles the text after a `#`. Line markers from the preprocessor tell us which
+// file we're in, and object-like `#define`s are either integer constants or
+// empty macros like `#define API`, which get ignored wherever they're used. Any
+// other macro in the header is skipped.
+fn directive_of(
+    directive: &str,
+    header: &str,
+    main: &mut bool,
+    in_main: bool,
+    decls: &mut Vec<Decl>,
+    empty: &mut HashSet<String>,
+) {
+    let directive = match directive.find("//") {
+        Some(it) => &directive[..it],
+        None => directive,
+    };
+    let directive = match (directive.find("/*"), directive.find("*/")) {
+        (Some(start), Some(end)) if start < end => {
+            format!("{}{}", &directive[..start], &directive[end + 2..])
+        }
+        _ => directive.to_string(),
+    };
+    let directive = directive.trim();
+
+    // `# 1 "raylib.h" 1` or `#line 1 "raylib.h"`
+    let marker = directive
+        .strip_prefix("line")
+        .unwrap_or(directive)
+        .trim_start();
+    if marker.starts_with(|it: char| it.is_ascii_digit()) {
+        if let Some(file) = marker.split('"').nth(1) {
+            *main = file == header || file.ends_with(&format!("/{header}"));
+        }
+        return;
+    }
+
+    let Some(define) = directive.strip_prefix("define") else {
+        return;
+    };
+    if !define.starts_with(char::is_whitespace) {
+        return;
+    }
+    let define = define.trim_start();
+    let name_len = define
+        .find(|it: char| !(it.is_alphanumeric() || it == '_'))
+        .unwrap_or(define.len());
+    let (name, value) = define.split_at(name_len);
+    if name.is_empty() {
+        return;
+    }
+    // Function-like macros can't be bound to
+    if value.starts_with('(') {
+        if in_main {
+            decls.push(Decl::Skipped(format!(
+                "`{name}`: function-like macros aren't supported"
+            )));
+        }
+        return;
+    }
+
+    let value = value.trim();
+    if value.is_empty() {
+        empty.insert(name.to_string());
+    } else if in_main {
+        decls.push(match integer(value) {
+            Some(value) => Decl::Const {
+                name: name.to_string(),
+                value,
+            },
+            None => Decl::Skipped(format!(
+                "`{name}`: only macros that are integer literals are supported"
+            )),
+        });
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|it: char| it.is_alphabetic() || it == '_')
+        && text.chars().all(|it| it.is_alphanumeric() || it == '_')
+}
+
+// Reads an integer literal like `42`, `0x2A`, `(-1)`, or `42u`.
+fn integer(text: &str) -> Option<i64> {
+    let mut text = text.trim();
+    while let Some(inner) = text.strip_prefix('(').and_then(|it| it.strip_suffix(')')) {
+        text = inner.trim();
+    }
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(it) => (true, it.trim_start()),
+        None => (false, text),
+    };
+    let text = text.trim_end_matches(['u', 'U', 'l', 'L']);
+
+    let value = if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if text.len() > 1 && text.starts_with('0') {
+        i64::from_str_radix(&text[1..], 8).ok()?
+    } else {
+        text.parse::<i64>().ok()?
+    };
+    Some(if negative { -value } else { value })
+}
+
+// #endregion: Tokens
+
+// #region: Declarations
+
+pub struct DeclParser {
+    tokens: Vec<Token>,
+    pos: usize,
+    empty: HashSet<String>, // empty macros, i.e, `RLAPI` in raylib
+    anonymous: usize,       // how many anonymous structs, unions, and enums have been named
+    pub decls: Vec<Decl>,
+}
+
+type ParseResult<T> = Result<T, String>;
+type Params = Vec<(Option<String>, CType)>;
+
+impl DeclParser {
+    // `header` is the file name of the header, used to tell its declarations
+    // apart from the ones in the headers that it includes.
+    pub fn new(source: &str, header: &str) -> Self {
+        let mut decls: Vec<Decl> = vec![];
+        let mut empty: HashSet<String> = HashSet::new();
+        let tokens = tokenize(source, header, &mut decls, &mut empty);
+        DeclParser {
+            tokens,
+            pos: 0,
+            empty,
+            anonymous: 0,
+            decls,
+        }
+    }
+
+    // Parses every declaration, returning the ones from the header itself.
+    pub fn parse(mut self) -> Vec<Decl> {
+        // Constants were found while tokenizing, so they come first
+        let mut decls: Vec<Decl> = std::mem::take(&mut self.decls);
+
+        while self.pos < self.tokens.len() {
+            // `extern "C" {` and the `}` that closes it
+            if self.is_ident("extern") && matches!(self.peek_at(1), Some(Tok::Str(_))) {
+                self.pos += 2;
+                self.accept("{");
+                continue;
+            }
+            if self.accept(";") || self.accept("}") {
+                continue;
+            }
+
+            let start = self.pos;
+            let main = self.tokens[start].main;
+            if let Err(why) = self.declaration() {
+                self.recover(start);
+                let why = match self.name_in(start) {
+                    Some(name) => format!("`{name}`: {why}"),
+                    None => why,
+                };
+                self.decls.push(Decl::Skipped(why));
+            }
+
+            let parsed = std::mem::take(&mut self.decls);
+            if main {
+                decls.extend(parsed);
+            }
+        }
+
+        decls
+    }
+
+    // #region: Helpers
+
+    fn peek_at(&self, offset: usize) -> Option<&Tok> {
+        self.tokens.get(self.pos + offset).map(|it| &it.tok)
+    }
+
+    fn peek(&self) -> Option<&Tok> {
+        self.peek_at(0)
+    }
+
+    fn is(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Punct(it)) if it == punct)
+    }
+
+    fn is_ident(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Ident(it)) if it == word)
+    }
+
+    fn accept(&mut self, punct: &str) -> bool {
+        if self.is(punct) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> ParseResult<()> {
+        if self.accept(punct) {
+            return Ok(());
+        }
+        Err(match self.peek() {
+            Some(Tok::Ident(it) | Tok::Number(it) | Tok::Punct(it)) => {
+                format!("expected `{punct}` but got `{it}`")
+            }
+            Some(Tok::Str(it)) => format!("expected `{punct}` but got `\"{it}\"`"),
+            None => format!("expected `{punct}` but the header ended"),
+        })
+    }
+
+    // Skips past the `)` or `}` that matches the one that was just passed.
+    fn skip_balanced(&mut self, open: &str, close: &str) {
+        let mut depth = 1;
+        while depth > 0 && self.pos < self.tokens.len() {
+            if self.is(open) {
+                depth += 1;
+            } else if self.is(close) {
+                depth -= 1;
+            }
+            self.pos += 1;
+        }
+    }
+
+    // Skips qualifiers, empty macros, and attributes like `__attribute__((x))`.
+    fn skip_qualifiers(&mut self) {
+        while let Some(Tok::Ident(word)) = self.peek() {
+            if QUALIFIERS.contains(&word.as_str()) || self.empty.contains(word) {
+                self.pos += 1;
+            } else if ATTRIBUTES.contains(&word.as_str()) {
+                self.pos += 1;
+                if self.accept("(") {
+                    self.skip_balanced("(", ")");
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    // Moves past a declaration that couldn't be parsed. That's either at the
+    // next `;`, or at the end of a function's body.
+    fn recover(&mut self, start: usize) {
+        self.pos = start;
+        let mut depth = 0;
+        let mut is_function = false;
+        while self.pos < self.tokens.len() {
+            if depth == 0 && self.is(";") {
+                self.pos += 1;
+                return;
+            }
+            if self.is("{") {
+                if depth == 0
+                    && self.pos > 0
+                    && self.tokens[self.pos - 1].tok == Tok::Punct(")".to_string())
+                {
+                    is_function = true;
+                }
+                depth += 1;
+            } else if self.is("}") {
+                depth -= 1;
+                if depth == 0 && is_function {
+                    self.pos += 1;
+                    return;
+                }
+            }
+            self.pos += 1;
+        }
+    }
+
+    // Guesses the name of the declaration between `start` and here, for
+    // reporting why it was skipped. Functions are named before their `(`, and
+    // typedefs after the `}` of their struct.
+    fn name_in(&self, start: usize) -> Option<String> {
+        let mut last: Option<&String> = None;
+        let mut depth = 0;
+        for token in &self.tokens[start..self.pos] {
+            match &token.tok {
+                Tok::Punct(it) if it == "{" => depth += 1,
+                Tok::Punct(it) if it == "}" => depth -= 1,
+                Tok::Punct(it) if it == "(" && depth == 0 && last.is_some() => break,
+                Tok::Ident(it)
+                    if depth == 0
+                        && !QUALIFIERS.contains(&it.as_str())
+                        && !["typedef", "struct", "union", "enum"].contains(&it.as_str()) =>
+                {
+                    last = Some(it)
+                }
+                _ => {}
+            }
+        }
+        last.cloned()
+    }
+
+    // #endregion: Helpers
+
+    fn declaration(&mut self) -> ParseResult<()> {
+        let is_typedef = self.is_ident("typedef");
+        if is_typedef {
+            self.pos += 1;
+        }
+
+        let typ = self.specifiers()?;
+        if self.accept(";") {
+            return Ok(()); // just a struct, union, or enum
+        }
+
+        loop {
+            let (name, typ) = self.declarator(typ.clone())?;
+            let Some(name) = name else {
+                return Err("declaration is missing a name".to_string());
+            };
+
+            if is_typedef {
+                self.decls.push(Decl::Typedef { name, typ });
+            } else if let (
+                Base::Function {
+                    params,
+                    rets,
+                    variadic,
+                },
+                0,
+            ) = (typ.base, typ.pointers)
+            {
+                self.decls.push(Decl::Function {
+                    name,
+                    params,
+                    rets: *rets,
+                    variadic,
+                });
+
+                // Functions defined in headers are usually `static inline`
+                if self.accept("{") {
+                    self.skip_balanced("{", "}");
+                    return Ok(());
+                }
+            } else {
+                self.decls.push(Decl::Skipped(format!(
+                    "`{name}`: global variables aren't supported"
+                )));
+            }
+
+            if !self.accept(",") {
+                break;
+            }
+        }
+
+        self.expect(";")
+    }
+
+    // Reads the type at the start of a declaration, i.e, `const unsigned int`
+    // or `struct Vector2 { ... }`.
+    fn specifiers(&mut self) -> ParseResult<CType> {
+        let mut words: Vec<String> = vec![];
+        let mut base: Option<Base> = None;
+
+        loop {
+            self.skip_qualifiers();
+            let Some(Tok::Ident(word)) = self.peek().cloned() else {
+                break;
+            };
+
+            if BUILTINS.contains(&word.as_str()) {
+                words.push(word);
+                self.pos += 1;
+            } else if (word == "struct" || word == "union") && base.is_none() {
+                self.pos += 1;
+                base = Some(self.record(word == "union")?);
+            } else if word == "enum" && base.is_none() {
+                self.pos += 1;
+                base = Some(self.enumeration()?);
+            } else if base.is_none() && words.is_empty() {
+                base = Some(Base::Named(word));
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        if !words.is_empty() {
+            base = Some(Base::Named(builtin(&words)?));
+        }
+        match base {
+            Some(base) => Ok(CType {
+                base,
+                pointers: 0,
+                arrays: vec![],
+            }),
+            None => Err(match self.peek() {
+                Some(Tok::Ident(it) | Tok::Number(it) | Tok::Punct(it)) => {
+                    format!("expected a type but got `{it}`")
+                }
+                _ => "expected a type".to_string(),
+            }),
+        }
+    }
+
+    fn tag(&mut self, kind: &str) -> String {
+        if let Some(Tok::Ident(tag)) = self.peek().cloned() {
+            self.pos += 1;
+            return tag;
+        }
+        self.anonymous += 1;
+        format!("<anonymous {kind} {}>", self.anonymous)
+    }
+
+    fn record(&mut self, union: bool) -> ParseResult<Base> {
+        self.skip_qualifiers();
+        let tag = self.tag(if union { "union" } else { "struct" });
+        self.skip_qualifiers();
+
+        if !self.accept("{") {
+            self.decls.push(Decl::Record {
+                tag: tag.clone(),
+                union,
+                fields: None,
+            });
+            return Ok(Base::Struct { tag, union });
+        }
+
+        let mut fields: Vec<(String, CType)> = vec![];
+        while !self.accept("}") {
+            let typ = self.specifiers()?;
+            if self.accept(";") {
+                return Err("anonymous members aren't supported".to_string());
+            }
+            loop {
+                let (name, typ) = self.declarator(typ.clone())?;
+                if self.is(":") {
+                    return Err("bitfields aren't supported".to_string());
+                }
+                let Some(name) = name else {
+                    return Err("a field is missing its name".to_string());
+                };
+                fields.push((name, typ));
+                if !self.accept(",") {
+                    break;
+                }
+            }
+            self.expect(";")?;
+        }
+
+        self.decls.push(Decl::Record {
+            tag: tag.clone(),
+            union,
+            fields: Some(fields),
+        });
+        Ok(Base::Struct { tag, union })
+    }
+
+    fn enumeration(&mut self) -> ParseResult<Base> {
+        self.skip_qualifiers();
+        let tag = self.tag("enum");
+        if !self.accept("{") {
+            return Ok(Base::Enum(tag));
+        }
+
+        let mut entries: Vec<String> = vec![];
+        while !self.accept("}") {
+            match self.peek().cloned() {
+                Some(Tok::Ident(entry)) => {
+                    entries.push(entry);
+                    self.pos += 1;
+                }
+                _ => return Err("expected an enum entry".to_string()),
+            }
+
+            // Skip the value, the C compiler knows it
+            let mut depth = 0;
+            while self.pos < self.tokens.len() {
+                if self.is("(") {
+                    depth += 1;
+                } else if self.is(")") {
+                    depth -= 1;
+                } else if depth == 0 && (self.is(",") || self.is("}")) {
+                    break;
+                }
+                self.pos += 1;
+            }
+            self.accept(",");
+        }
+
+        self.decls.push(Decl::Enum {
+            tag: tag.clone(),
+            entries,
+        });
+        Ok(Base::Enum(tag))
+    }
+
+    // Reads the name and the rest of the type, i.e, `*name[4]` or `(*name)(int)`.
+    fn declarator(&mut self, mut typ: CType) -> ParseResult<(Option<String>, CType)> {
+        loop {
+            self.skip_qualifiers();
+            if !self.accept("*") {
+                break;
+            }
+            typ.pointers += 1;
+        }
+
+        // `(*name)(int)`, where the suffixes apply before the pointer, or just `(name)(int)`
+        let grouped = match (self.peek_at(1), self.peek_at(2), self.peek_at(3)) {
+            (Some(Tok::Punct(it)), _, _) => it == "*",
+            (Some(Tok::Ident(_)), Some(Tok::Punct(close)), Some(Tok::Punct(open))) => {
+                close == ")" && open == "("
+            }
+            _ => false,
+        };
+        if self.is("(") && grouped {
+            self.pos += 1;
+            let inner = self.pos;
+            self.skip_balanced("(", ")");
+            let typ = self.suffixes(typ)?;
+            let end = self.pos;
+
+            self.pos = inner;
+            let (name, typ) = self.declarator(typ)?;
+            self.expect(")")?;
+            self.pos = end;
+            return Ok((name, typ));
+        }
+
+        let name = match self.peek().cloned() {
+            Some(Tok::Ident(name)) => {
+                self.pos += 1;
+                Some(name)
+            }
+            _ => None,
+        };
+        let typ = self.suffixes(typ)?;
+        self.skip_qualifiers();
+        Ok((name, typ))
+    }
+
+    // Reads parameter lists and array sizes after a name.
+    fn suffixes(&mut self, mut typ: CType) -> ParseResult<CType> {
+        if self.accept("(") {
+            let (params, variadic) = self.params()?;
+            typ = CType {
+                base: Base::Function {
+                    params,
+                    rets: Box::new(typ),
+                    variadic,
+                },
+                pointers: 0,
+                arrays: vec![],
+            };
+        }
+
+        while self.accept("[") {
+            // Sizes from macros tend to be in parentheses, i.e, `[(16)]`
+            let mut text = String::new();
+            while self.pos < self.tokens.len() && !self.is("]") {
+                match &self.tokens[self.pos].tok {
+                    Tok::Ident(it) | Tok::Number(it) | Tok::Punct(it) => text.push_str(it),
+                    Tok::Str(_) => break,
+                }
+                self.pos += 1;
+            }
+            let size = if text.is_empty() || is_identifier(&text) {
+                text
+            } else {
+                match integer(&text) {
+                    Some(it) => it.to_string(),
+                    None => return Err(format!("unsupported array size: `{text}`")),
+                }
+            };
+            self.expect("]")?;
+            typ.arrays.push(size);
+        }
+
+        Ok(typ)
+    }
+
+    fn params(&mut self) -> ParseResult<(Params, bool)> {
+        let mut params: Params = vec![];
+        let mut variadic = false;
+        if self.accept(")") {
+            return Ok((params, variadic));
+        }
+
+        loop {
+            if self.accept("...") {
+                variadic = true;
+                self.expect(")")?;
+                break;
+            }
+            let typ = self.specifiers()?;
+            params.push(self.declarator(typ)?);
+            if self.accept(")") {
+                break;
+            }
+            self.expect(",")?;
+        }
+
+        // `f(void)` has no parameters
+        if let [(None, typ)] = params.as_slice() {
+            if typ.is_void() {
+                params.clear();
+            }
+        }
+        Ok((params, variadic))
+    }
+}
+
+// Converts builtin C types to Sea's names for them, i.e, `unsigned char` is `u8`.
+fn builtin(words: &[String]) -> ParseResult<String> {
+    let has = |word: &str| words.iter().any(|it| it == word);
+    let longs = words.iter().filter(|it| *it == "long").count();
+    let unsigned = has("unsigned");
+
+    let name = if has("void") {
+        "void"
+    } else if has("_Bool") {
+        "bool"
+    } else if has("float") {
+        "f32"
+    } else if has("double") && longs == 0 {
+        "f64"
+    } else if has("double") {
+        return Err("`long double` isn't supported".to_string());
+    } else if has("char") {
+        match (unsigned, has("signed")) {
+            (true, _) => "u8",
+            (_, true) => "i8",
+            _ => "char",
+        }
+    } else if has("short") {
+        if unsigned {
+            "u16"
+        } else {
+            "i16"
+        }
+    } else if longs > 0 {
+        if unsigned {
+            "u64"
+        } else {
+            "i64"
+        }
+    } else if unsigned {
+        "u32"
+    } else {
+        "int"
+    };
+    Ok(name.to_string())
+}
+
+// Sea names for the fixed-width types from `stdint.h` and friends.
+pub fn typedef_name(name: &str) -> &str {
+    match name {
+        "int8_t" => "i8",
+        "int16_t" => "i16",
+        "int32_t" => "i32",
+        "int64_t" => "i64",
+        "uint8_t" => "u8",
+        "uint16_t" => "u16",
+        "uint32_t" => "u32",
+        "uint64_t" => "u64",
+        "size_t" => "usize",
+        "bool" => "bool",
+        _ => name,
+    }
+}
+
+// #endregion: Declarations
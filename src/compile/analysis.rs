@@ -0,0 +1,2244 @@
+// Semantic analysis. This walks the whole program before any backend runs,
+// resolving imports, registering symbols, and inferring the type of every
+// expression, so that all errors get reported before any code is written.
+// Backends consume the annotated program that this produces.
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{
+    hashtags::{DefTags, FunTags, RecTags, TagRecTags, TagTags},
+    parse::{
+        ast::{ContractKind, FunSignature, Node, NodeKind},
+        lexer::Lexer,
+        operator::OperatorKind,
+        parser::Parser,
+    },
+};
+
+use super::{
+    compiler::Compiler,
+    contracts::{self, Value},
+    error::CompilerError,
+    flow::{flow_of_node, Flow},
+    generics::{self, Instance, Template},
+    infer::{infer_type_of_node, number_literal, NumberLiteral},
+    symbol::{Location, Symbol},
+    traits,
+    type_::{NumberKind, SeaType},
+};
+
+// How a value of one type can become another type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversion {
+    // Nothing can be lost, so no cast is needed.
+    Implicit,
+    // Possible, but only with an `as` cast.
+    Explicit,
+    // The types have nothing to do with each other.
+    Invalid,
+}
+
+pub struct Analyzer<'a, 'b> {
+    pub node: Box<Node>, // reference to current node
+    pub compiler: &'b mut Compiler<'a>,
+    pub loose: bool, // allows implicit conversions, set by `--loose` or `#loose`
+    pub prod: bool,  // leaves out the runtime checks of contracts, set by `--prod`
+    pub rets: Option<SeaType>, // return type of the current function
+    pub mutates: Option<Vec<String>>, // globals in the `@mut` contracts of the current function
+    pub instances: Vec<Node>, // analyzed instances of generics, which are yet to be added
+    pub queued: Vec<(Node, PathBuf, String)>, // generic functions' instances to analyze, see `instantiate`
+}
+
+impl<'a, 'b> Analyzer<'a, 'b> {
+    pub fn new(compiler: &'b mut Compiler<'a>) -> Self {
+        Analyzer {
+            node: Box::new(Node::of_kind(NodeKind::Raw(Default::default()))),
+            compiler,
+            loose: false,
+            prod: false,
+            rets: None,
+            mutates: None,
+            instances: vec![],
+            queued: vec![],
+        }
+    }
+
+    pub fn throw(&self, error: CompilerError, help: Option<&str>) {
+        self.compiler.throw(error, help, *self.node.clone())
+    }
+
+    fn analyze_all(&mut self, nodes: Vec<Node>) -> Vec<Node> {
+        nodes.into_iter().map(|it| self.analyze(it)).collect()
+    }
+
+    fn analyze_boxed(&mut self, node: Node) -> Box<Node> {
+        Box::new(self.analyze(node))
+    }
+
+    pub fn throw_at(&self, error: CompilerError, help: Option<&str>, node: &Node) {
+        self.compiler.throw(error, help, node.clone())
+    }
+
+    // Remembers where `id` was declared, or reports it if it couldn't be added
+    // because the scope already has it.
+    fn declare(&mut self, added: bool, id: &str, node: &Node) {
+        if added {
+            let location = self.location_of(node);
+            self.compiler
+                .symbols
+                .set_definition(id.to_string(), location);
+        } else {
+            self.throw_at(CompilerError::DuplicateSymbol(id.to_string()), None, node);
+        }
+    }
+
+    fn location_of(&self, node: &Node) -> Location {
+        Location {
+            file: self.compiler.file_stack.last().unwrap().clone(),
+            line: node.line,
+            column: node.column,
+        }
+    }
+
+    // #region: Types
+
+    // Follows `def`s until we reach a type that isn't an alias.
+    pub fn resolve_type(&self, typ: &SeaType) -> SeaType {
+        let mut resolved = typ.clone();
+        // The limit protects us from cyclic aliases
+        for _ in 0..32 {
+            if resolved.funptr_rets.is_some() {
+                break;
+            }
+            match self.compiler.symbols.get_symbol(resolved.name.clone()) {
+                Some(Symbol::Def { tags: _, typ }) => {
+                    let mut arrays = typ.arrays.clone();
+                    arrays.extend(resolved.arrays);
+                    resolved = SeaType {
+                        pointers: typ.pointers + resolved.pointers,
+                        arrays,
+                        ..typ.clone()
+                    };
+                }
+                _ => break,
+            }
+        }
+        resolved
+    }
+
+    // How a value of type `from` can be used where `to` is wanted. Numbers only
+    // convert implicitly when nothing can be lost, and `^void` converts to and
+    // from any other pointer.
+    pub fn conversion(&self, from: &SeaType, to: &SeaType) -> Conversion {
+        let from = self.resolve_type(from);
+        let to = self.resolve_type(to);
+
+        // Types from C headers (i.e, `time_t`) are unknown to us, so we leave
+        // those to the C compiler
+        if from == to || self.is_opaque(&from) || self.is_opaque(&to) {
+            return Conversion::Implicit;
+        }
+
+        if let (Some(from), Some(to)) = (from.number_kind(), to.number_kind()) {
+            return if from.converts_implicitly_to(to) {
+                Conversion::Implicit
+            } else {
+                Conversion::Explicit
+            };
+        }
+
+        if from.funptr_rets.is_some() || to.funptr_rets.is_some() {
+            return Conversion::Invalid;
+        }
+
+        // Arrays decay into pointers
+        let from_depth = from.pointers as usize + from.arrays.len();
+        let to_depth = to.pointers as usize + to.arrays.len();
+        let is_integer = |typ: &SeaType| typ.number_kind().is_some_and(|it| it.is_integer());
+        match (from_depth > 0, to_depth > 0) {
+            (true, true) => {
+                if from.name == "void"
+                    || to.name == "void"
+                    || (from_depth == to_depth && from.name == to.name)
+                {
+                    Conversion::Implicit
+                } else {
+                    Conversion::Explicit
+                }
+            }
+            (true, false) if is_integer(&to) => Conversion::Explicit,
+            (false, true) if is_integer(&from) => Conversion::Explicit,
+            _ => Conversion::Invalid,
+        }
+    }
+
+    fn is_opaque(&self, typ: &SeaType) -> bool {
+        typ.funptr_rets.is_none()
+            && typ.name != "void"
+            && SeaType::named_type(&typ.name).number_kind().is_none()
+            && self.compiler.symbols.get_symbol(typ.name.clone()).is_none()
+    }
+
+    // Number literals take on the type they're used as, as long as they fit.
+    fn literal_converts_to(literal: NumberLiteral, kind: NumberKind) -> bool {
+        match literal {
+            NumberLiteral::Integer => kind != NumberKind::Bool,
+            NumberLiteral::Float => matches!(kind, NumberKind::Float(_)),
+        }
+    }
+
+    // Checks that `value` can be used where `expected` is wanted. `help` is
+    // shown when the types don't match at all. Values that we can't infer the
+    // type of are left to the C compiler.
+    pub fn check_value(&self, expected: &SeaType, value: &Node, help: Option<&str>) {
+        let got = match infer_type_of_node(self.compiler, value) {
+            Ok(it) => it,
+            Err(_) => return,
+        };
+
+        let conversion = match (
+            number_literal(value),
+            self.resolve_type(expected).number_kind(),
+        ) {
+            (Some(literal), Some(kind)) if Analyzer::literal_converts_to(literal, kind) => {
+                Conversion::Implicit
+            }
+            _ => self.conversion(&got, expected),
+        };
+
+        // Pointers to recs only become `dyn`s with a cast, since that needs a vtable
+        let dyn_help = traits::dyn_trait_of(expected)
+            .filter(|_| got.pointers > 0)
+            .map(|it| Analyzer::with_help(format!("add a cast: `as dyn {it}`"), help));
+
+        match conversion {
+            Conversion::Implicit => {}
+            Conversion::Explicit => self.throw_implicit_conversion(&got, expected, value, help),
+            Conversion::Invalid => self.throw_at(
+                CompilerError::MismatchedTypes(expected.to_string(), got.to_string()),
+                dyn_help.as_deref().or(help),
+                value,
+            ),
+        }
+    }
+
+    // Adds the caller's help, like a call's expected signature, after a more
+    // specific one.
+    fn with_help(specific: String, help: Option<&str>) -> String {
+        match help {
+            Some(help) => format!("{specific}; {help}"),
+            None => specific,
+        }
+    }
+
+    fn throw_implicit_conversion(
+        &self,
+        from: &SeaType,
+        to: &SeaType,
+        at: &Node,
+        help: Option<&str>,
+    ) {
+        if self.loose {
+            return;
+        }
+        self.throw_at(
+            CompilerError::ImplicitConversion(from.to_string(), to.to_string()),
+            Some(&Analyzer::with_help(
+                format!("add an explicit cast: `as {to}`"),
+                help,
+            )),
+            at,
+        );
+    }
+
+    pub fn is_void(&self, typ: &SeaType) -> bool {
+        let typ = self.resolve_type(typ);
+        typ.name == "void" && typ.pointers == 0 && typ.funptr_rets.is_none()
+    }
+
+    // #endregion: Types
+
+    // #region: Top level statements
+
+    // Parses and analyzes each file in the module, then stores them in the
+    // compiler so that backends don't need to parse them again.
+    pub fn top_use(&mut self, path: PathBuf) {
+        let file_paths = match self.compiler.get_use_paths(path.clone()) {
+            Ok(it) => it,
+            Err(why) => return self.throw(CompilerError::ImportError(why), None),
+        };
+
+        let mut programs: Vec<(PathBuf, Node)> = vec![];
+        for file_path in file_paths {
+            if !file_path.exists() {
+                self.throw(
+                    CompilerError::ImportError(format!("no such module: {file_path:?}")),
+                    None,
+                );
+                continue;
+            }
+
+            if self.compiler.uses(&file_path) {
+                continue;
+            }
+
+            self.compiler.enter_file(file_path.clone());
+
+            self.compiler.usages.push(file_path.clone());
+            let code = fs::read_to_string(file_path.clone()).unwrap();
+            let mut parser = Parser::new(Lexer::new(
+                file_path.clone(),
+                &code,
+                self.compiler.diagnostics.clone(),
+            ));
+            let program = parser.parse(false);
+            let program = self.analyze(program);
+            programs.push((file_path, program));
+
+            self.compiler.leave_file();
+        }
+
+        self.compiler
+            .imports
+            .entry(path)
+            .or_default()
+            .extend(programs);
+    }
+
+    pub fn top_pkg(&mut self, namespace: String, statements: Vec<Node>) -> Vec<Node> {
+        self.analyze_statements(statements, Some(&namespace))
+    }
+
+    pub fn top_fun(
+        &mut self,
+        tags: Vec<FunTags>,
+        id: String,
+        params: &[(String, Node)],
+        rets: &Node,
+        contracts: Vec<Node>,
+        expr: Node,
+    ) -> (Vec<Node>, Box<Node>) {
+        let fun_node = self.node.clone();
+        let rets = SeaType::from_node(rets.clone()).unwrap();
+        let is_extern = tags.contains(&FunTags::Extern);
+        let loose = self.loose;
+        self.loose |= tags.contains(&FunTags::Loose);
+        let added = self.compiler.add_fun(
+            id.clone(),
+            tags,
+            params
+                .iter()
+                .map(|(_, it)| SeaType::from_node(it.clone()).unwrap())
+                .collect::<Vec<SeaType>>(),
+            rets.clone(),
+        );
+        self.declare(added, &id, &fun_node);
+        if is_extern {
+            self.compiler.add_extern(&id);
+        }
+
+        self.compiler.push_scope();
+        for (param_id, param_type) in params {
+            let added = self.compiler.add_var(
+                param_id.to_string(),
+                SeaType::from_node(param_type.clone()).unwrap(),
+                true,
+            );
+            self.declare(added, param_id, param_type);
+        }
+        let contracts = self.contracts(&id, params, &rets, contracts);
+
+        // There's no body to check, C has it
+        if is_extern {
+            self.compiler.pop_scope();
+            self.mutates = None;
+            self.loose = loose;
+            return (contracts, Box::new(expr));
+        }
+
+        // The body shares its scope with the parameters, like in C
+        self.rets = Some(rets.clone());
+        let expr = match expr.node {
+            NodeKind::ExprBlock(nodes) => Box::new(Node {
+                node: NodeKind::ExprBlock(self.analyze_all(nodes)),
+                ..expr
+            }),
+            _ => self.analyze_boxed(expr),
+        };
+        self.rets = None;
+        self.mutates = None;
+
+        // Calls are looked up in the body's scope, where parameters can shadow
+        // `#noret` functions. C lets `main` fall off the end, so we do too.
+        let flow = flow_of_node(self.compiler, &expr);
+        self.compiler.pop_scope();
+        if flow == Flow::Continues && !self.is_void(&rets) && id != "main" {
+            self.throw_at(
+                CompilerError::MissingReturn(id),
+                Some(&format!("add a `ret` with a value of type `{rets}`")),
+                &fun_node,
+            );
+        }
+
+        self.loose = loose;
+        (contracts, expr)
+    }
+
+    // Checks the function's contracts, in the scope of its parameters. Its
+    // `@arg`s are kept for checking calls with constant arguments, even when
+    // `--prod` leaves out the runtime checks.
+    fn contracts(
+        &mut self,
+        id: &str,
+        params: &[(String, Node)],
+        rets: &SeaType,
+        contracts: Vec<Node>,
+    ) -> Vec<Node> {
+        // Only functions that declare their mutations are held to them
+        let mut mutates: Option<Vec<String>> = None;
+        let mut analyzed: Vec<Node> = vec![];
+        for contract in contracts {
+            *self.node = contract.clone();
+            let Node { line, column, node } = contract;
+            let NodeKind::Contract { kind, params, text } = node else {
+                continue;
+            };
+            let params = match kind {
+                ContractKind::Arg => params.into_iter().map(|it| self.condition(it)).collect(),
+                ContractKind::Ret if self.is_void(rets) => {
+                    self.throw(CompilerError::RetContractOnVoid(id.to_string()), None);
+                    continue;
+                }
+                // The return value is only visible to the contract
+                ContractKind::Ret => {
+                    self.compiler.push_scope();
+                    self.compiler
+                        .add_var("ret".to_string(), rets.clone(), false);
+                    let params = params.into_iter().map(|it| self.condition(it)).collect();
+                    self.compiler.pop_scope();
+                    params
+                }
+                ContractKind::Mut => {
+                    let mutates = mutates.get_or_insert_with(Vec::new);
+                    for param in &params {
+                        match &param.node {
+                            NodeKind::ExprIdentifier(name)
+                                if self.compiler.symbols.is_global_var(name) =>
+                            {
+                                mutates.push(name.clone())
+                            }
+                            _ => self.throw_at(CompilerError::NotAGlobal, None, param),
+                        }
+                    }
+                    params
+                }
+            };
+            analyzed.push(Node {
+                line,
+                column,
+                node: NodeKind::Contract { kind, params, text },
+            });
+        }
+
+        self.mutates = mutates;
+        self.compiler.preconditions.insert(
+            id.to_string(),
+            (
+                params.iter().map(|(name, _)| name.clone()).collect(),
+                analyzed.clone(),
+            ),
+        );
+        if self.prod {
+            analyzed.retain(|it| {
+                matches!(
+                    it.node,
+                    NodeKind::Contract {
+                        kind: ContractKind::Mut,
+                        ..
+                    }
+                )
+            });
+        }
+        analyzed
+    }
+
+    fn condition(&mut self, cond: Node) -> Node {
+        let cond = self.analyze(cond);
+        self.check_value(&SeaType::named_type("bool"), &cond, None);
+        cond
+    }
+
+    pub fn top_rec(&mut self, tags: Vec<RecTags>, id: String, fields: &[(String, Node)]) {
+        if tags.contains(&RecTags::Extern) {
+            self.compiler.add_extern(&id);
+        }
+        let added = self.compiler.add_rec(
+            id.clone(),
+            tags,
+            fields
+                .iter()
+                .map(|(name, typ)| (name.clone(), SeaType::from_node(typ.clone()).unwrap()))
+                .collect::<Vec<(String, SeaType)>>(),
+        );
+        self.declare(added, &id, &self.node.clone());
+    }
+
+    pub fn top_def(&mut self, tags: Vec<DefTags>, id: String, typ: &Node) {
+        if tags.contains(&DefTags::Extern) {
+            self.compiler.add_extern(&id);
+        }
+        let added =
+            self.compiler
+                .add_def(id.clone(), tags, SeaType::from_node(typ.clone()).unwrap());
+        self.declare(added, &id, &self.node.clone());
+    }
+
+    pub fn top_tag(
+        &mut self,
+        tags: Vec<TagTags>,
+        id: String,
+        entries: Vec<(String, Option<Box<Node>>)>,
+    ) -> Vec<(String, Option<Box<Node>>)> {
+        let tag_node = self.node.clone();
+        let entries = entries
+            .into_iter()
+            .map(|(entry, value)| (entry, value.map(|it| self.analyze_boxed(*it))))
+            .collect::<Vec<(String, Option<Box<Node>>)>>();
+
+        let added = self.compiler.add_tag(
+            id.clone(),
+            tags.clone(),
+            entries
+                .iter()
+                .map(|it| it.0.clone())
+                .collect::<Vec<String>>(),
+        );
+        self.declare(added, &id, &tag_node);
+
+        // C enums are just their entries, which aren't namespaced either
+        if tags.contains(&TagTags::Extern) {
+            self.compiler.add_extern(&id);
+            for (entry, _) in &entries {
+                self.compiler.add_extern(&format!("{id}'{entry}"));
+            }
+            return entries;
+        }
+
+        if entries.is_empty() || tags.contains(&TagTags::NoHelpers) {
+            return entries;
+        }
+
+        // The backend generates `to_str` and `from_str` helpers for tags, so
+        // we register them here.
+        self.compiler.add_fun(
+            format!("{id}'to_str"),
+            vec![],
+            vec![SeaType::named_type(&id)],
+            SeaType::STRING.clone(),
+        );
+
+        // `from_str` needs std/str. If `std` is in usage, then we can probably
+        // add std/str implicitly. If it *isn't* in usage then the user is
+        // likely using --nostd.
+        if !self.compiler.uses(&PathBuf::from("std/str")) {
+            if self.compiler.uses(&PathBuf::from("std")) {
+                self.top_use(PathBuf::from("std/str"))
+            } else {
+                return entries;
+            }
+        }
+        self.compiler.add_fun(
+            format!("{id}'from_str"),
+            vec![],
+            vec![SeaType::STRING.clone()],
+            SeaType::named_type(&id),
+        );
+
+        entries
+    }
+
+    pub fn top_tag_rec(
+        &mut self,
+        tags: Vec<TagRecTags>,
+        id: String,
+        entries: &[(String, Vec<(String, Node)>)],
+    ) {
+        // Entry structs are registered under their C names, matching the backend.
+        let mut mapped_entries: Vec<(String, Vec<(String, SeaType)>)> = vec![];
+
+        for (entry_id, entry_fields) in entries {
+            let mapped_fields = entry_fields
+                .iter()
+                .map(|(field_name, field_typ)| {
+                    (
+                        field_name.clone(),
+                        SeaType::from_node(field_typ.clone()).unwrap(),
+                    )
+                })
+                .collect::<Vec<(String, SeaType)>>();
+            mapped_entries.push((entry_id.clone(), mapped_fields.clone()));
+            self.compiler.add_rec(
+                self.compiler.tag_rec_entry(&id, entry_id),
+                vec![],
+                mapped_fields,
+            );
+        }
+
+        let added = self.compiler.add_tag_rec(id.clone(), tags, mapped_entries);
+        self.declare(added, &id, &self.node.clone());
+    }
+
+    // Declares the trait along with the symbols in `traits`: its vtable, its
+    // `dyn` rec, and a function for each method that calls it through a `dyn`.
+    pub fn top_trait(&mut self, id: String, methods: &[FunSignature]) {
+        let trait_node = self.node.clone();
+        let receiver = SeaType::named_type(traits::SELF).pointer();
+        let void_pointer = SeaType::named_type("void").pointer();
+
+        let mut signatures: Vec<(String, Vec<SeaType>, SeaType)> = vec![];
+        for (method, params, rets) in methods {
+            if traits::is_generated(&id, method) {
+                self.throw(
+                    CompilerError::GeneratedTraitMethod(id.clone(), method.clone()),
+                    Some("rename the method"),
+                );
+                continue;
+            }
+            let param_types: Vec<SeaType> = params
+                .iter()
+                .map(|(_, it)| SeaType::from_node(it.clone()).unwrap())
+                .collect();
+            let rets = SeaType::from_node(rets.clone()).unwrap();
+
+            // `Self` is only known through the receiver, since a `dyn` hides it
+            let uses_self = param_types
+                .iter()
+                .skip(1)
+                .chain([&rets])
+                .any(|it| it.name == traits::SELF);
+            if param_types.first() != Some(&receiver) || uses_self {
+                let at = params
+                    .first()
+                    .map_or(*trait_node.clone(), |it| it.1.clone());
+                self.throw_at(
+                    CompilerError::TraitReceiver(method.clone()),
+                    Some("`Self` can only be used as the type of the first parameter"),
+                    &at,
+                );
+                continue;
+            }
+            signatures.push((method.clone(), param_types, rets));
+        }
+
+        let added = self.compiler.add_trait(id.clone(), signatures.clone());
+        self.declare(added, &id, &trait_node);
+        if !added {
+            return;
+        }
+
+        let mut vtable_fields: Vec<(String, SeaType)> = vec![];
+        for (method, params, rets) in signatures {
+            let mut args = params.clone();
+            args[0] = void_pointer.clone();
+            vtable_fields.push((
+                method.clone(),
+                SeaType {
+                    pointers: 0,
+                    name: "fun".to_string(),
+                    arrays: vec![],
+                    funptr_args: Some(args),
+                    funptr_rets: Some(Box::new(rets.clone())),
+                },
+            ));
+
+            let mut params = params;
+            params[0] = SeaType::named_type(&traits::dyn_name(&id));
+            let name = traits::method_name(&id, &method);
+            let added = self.compiler.add_fun(name.clone(), vec![], params, rets);
+            self.declare(added, &name, &trait_node);
+        }
+        let vtable = traits::vtable_name(&id);
+        let added = self.compiler.add_rec(vtable.clone(), vec![], vtable_fields);
+        self.declare(added, &vtable, &trait_node);
+        let dyn_ = traits::dyn_name(&id);
+        let added = self.compiler.add_rec(
+            dyn_.clone(),
+            vec![],
+            vec![
+                ("self".to_string(), void_pointer),
+                ("vtable".to_string(), SeaType::named_type(&vtable).pointer()),
+            ],
+        );
+        self.declare(added, &dyn_, &trait_node);
+    }
+
+    // Checks that the impl has every method of the trait with the right
+    // signature, where `Self` is the rec, then analyzes them like functions.
+    pub fn top_impl(&mut self, trait_: &str, rec: &str, funs: Vec<Node>) -> Vec<Node> {
+        let impl_node = self.node.clone();
+        let methods = match self.compiler.symbols.get_symbol(trait_.to_string()) {
+            Some(Symbol::Trait { methods }) => methods.clone(),
+            Some(_) => {
+                self.throw(CompilerError::NotATrait(trait_.to_string()), None);
+                return funs;
+            }
+            None => {
+                self.throw(CompilerError::UnknownSymbol(trait_.to_string()), None);
+                return funs;
+            }
+        };
+        match self.compiler.symbols.get_symbol(rec.to_string()) {
+            Some(Symbol::Rec { .. } | Symbol::TagRec { .. }) => {}
+            Some(_) => {
+                self.throw(CompilerError::NotImplementable(rec.to_string()), None);
+                return funs;
+            }
+            None => {
+                self.throw(CompilerError::UnknownSymbol(rec.to_string()), None);
+                return funs;
+            }
+        }
+        if !self
+            .compiler
+            .impls
+            .insert((trait_.to_string(), rec.to_string()))
+        {
+            self.throw(
+                CompilerError::DuplicateImpl(trait_.to_string(), rec.to_string()),
+                None,
+            );
+            return funs;
+        }
+        self.compiler.add_var(
+            traits::impl_vtable_name(trait_, rec),
+            SeaType::named_type(&traits::vtable_name(trait_)),
+            false,
+        );
+
+        let mut implemented: Vec<String> = vec![];
+        let mut analyzed: Vec<Node> = vec![];
+        for fun in funs {
+            *self.node = fun.clone();
+            let Node { line, column, node } = fun;
+            let NodeKind::TopFun {
+                tags,
+                id,
+                params,
+                rets,
+                contracts,
+                expr,
+            } = node
+            else {
+                analyzed.push(Node { line, column, node });
+                continue;
+            };
+            let params: Vec<(String, Node)> = params
+                .into_iter()
+                .map(|(name, typ)| (name, Analyzer::replace_self(typ, rec)))
+                .collect();
+            let rets = Box::new(Analyzer::replace_self(*rets, rec));
+
+            match methods.iter().find(|it| it.0 == id) {
+                Some((_, expected_params, expected_rets)) => {
+                    let mut expected_params = expected_params.clone();
+                    expected_params[0] = SeaType::named_type(rec).pointer();
+                    let got_params: Vec<SeaType> = params
+                        .iter()
+                        .map(|(_, it)| SeaType::from_node(it.clone()).unwrap())
+                        .collect();
+                    let got_rets = SeaType::from_node(*rets.clone()).unwrap();
+                    let same =
+                        |a: &SeaType, b: &SeaType| self.resolve_type(a) == self.resolve_type(b);
+                    let matches = got_params.len() == expected_params.len()
+                        && got_params
+                            .iter()
+                            .zip(&expected_params)
+                            .all(|(a, b)| same(a, b))
+                        && same(&got_rets, expected_rets);
+                    if !matches {
+                        let expected = Symbol::Fun {
+                            tags: vec![],
+                            params: expected_params,
+                            rets: expected_rets.clone(),
+                        };
+                        self.throw(
+                            CompilerError::TraitMethodMismatch(id.clone(), expected.signature(&id)),
+                            None,
+                        );
+                    }
+                    implemented.push(id.clone());
+                }
+                None => self.throw(
+                    CompilerError::UnknownTraitMethod(trait_.to_string(), id.clone()),
+                    None,
+                ),
+            }
+
+            let (contracts, expr) = self.top_fun(
+                tags.clone(),
+                traits::impl_method_name(trait_, rec, &id),
+                &params,
+                &rets,
+                contracts,
+                *expr,
+            );
+            analyzed.push(Node {
+                line,
+                column,
+                node: NodeKind::TopFun {
+                    tags,
+                    id,
+                    params,
+                    rets,
+                    contracts,
+                    expr,
+                },
+            });
+        }
+
+        for (method, params, rets) in methods {
+            if implemented.contains(&method) {
+                continue;
+            }
+            let mut params = params;
+            params[0] = SeaType::named_type(rec).pointer();
+            let signature = Symbol::Fun {
+                tags: vec![],
+                params,
+                rets,
+            }
+            .signature(&method);
+            self.throw_at(
+                CompilerError::MissingTraitMethod(trait_.to_string(), rec.to_string(), method),
+                Some(&format!("add `{signature}`")),
+                &impl_node,
+            );
+        }
+        analyzed
+    }
+
+    // Impls can use `Self` for the rec that they're for.
+    fn replace_self(typ: Node, rec: &str) -> Node {
+        match typ.node {
+            NodeKind::Type {
+                pointers,
+                name,
+                arrays,
+                generics,
+                funptr_args,
+                funptr_rets,
+            } if name == traits::SELF => Node {
+                node: NodeKind::Type {
+                    pointers,
+                    name: rec.to_string(),
+                    arrays,
+                    generics,
+                    funptr_args,
+                    funptr_rets,
+                },
+                ..typ
+            },
+            _ => typ,
+        }
+    }
+
+    // #endregion: Top level statements
+
+    // #region: Generics
+
+    // Analyzes the statements of a program or package. Instances of generics that
+    // a statement uses are added before it, so that C declares them first.
+    fn analyze_statements(&mut self, nodes: Vec<Node>, namespace: Option<&str>) -> Vec<Node> {
+        let mut analyzed: Vec<Node> = vec![];
+        for node in nodes {
+            let pending = self.instances.len();
+            let node = self.resolve_types(node);
+            let node = match namespace {
+                Some(namespace) => self.pkg_statement(node, namespace),
+                None => self.analyze(node),
+            };
+            self.analyze_queued();
+            analyzed.extend(self.instances.drain(pending..).map(|it| Node {
+                line: it.line,
+                column: it.column,
+                node: NodeKind::TopInstance(Box::new(it)),
+            }));
+            analyzed.push(node);
+        }
+        analyzed
+    }
+
+    // Declares the generic with its parameters as types. Its body isn't checked
+    // until it's instantiated, since the parameters could be anything until then.
+    pub fn top_generic(&mut self, id: String, params: Vec<String>, node: &Node) {
+        let generic_node = self.node.clone();
+        let typ = |it: &Node| SeaType::from_node(it.clone()).unwrap();
+        let fields = |fields: &Vec<(String, Node)>| {
+            fields
+                .iter()
+                .map(|(name, it)| (name.clone(), typ(it)))
+                .collect::<Vec<(String, SeaType)>>()
+        };
+        let symbol = match &node.node {
+            NodeKind::TopFun {
+                tags, params, rets, ..
+            } => Symbol::Fun {
+                tags: tags.clone(),
+                params: params.iter().map(|(_, it)| typ(it)).collect(),
+                rets: typ(rets),
+            },
+            NodeKind::TopRec {
+                tags, fields: f, ..
+            } => Symbol::Rec {
+                tags: tags.clone(),
+                fields: fields(f),
+            },
+            NodeKind::TopDef { tags, typ: t, .. } => Symbol::Def {
+                tags: tags.clone(),
+                typ: typ(t),
+            },
+            NodeKind::TopTagRec { tags, entries, .. } => Symbol::TagRec {
+                tags: tags.clone(),
+                entries: entries
+                    .iter()
+                    .map(|(entry, f)| (entry.clone(), fields(f)))
+                    .collect(),
+            },
+            _ => return,
+        };
+
+        let added = self
+            .compiler
+            .add_generic(id.clone(), params.clone(), symbol);
+        self.declare(added, &id, &generic_node);
+        if added {
+            let file = self.compiler.file_stack.last().unwrap().clone();
+            self.compiler.templates.insert(
+                id,
+                Template {
+                    params,
+                    node: node.clone(),
+                    file,
+                },
+            );
+        }
+    }
+
+    // Instantiates the generics that the node gives type arguments to, i.e,
+    // `List[i32]` or `new List[i32](...)`, and refers to their instances instead.
+    fn resolve_types(&mut self, node: Node) -> Node {
+        if let NodeKind::TopPkg { .. } | NodeKind::TopGeneric { .. } = node.node {
+            return node;
+        }
+        generics::transform(node, &mut |it| self.resolve_generic(it))
+    }
+
+    fn resolve_generic(&mut self, node: Node) -> Node {
+        match &node.node {
+            NodeKind::Type { name, generics, .. } if generics.is_empty() => {
+                if let Some(template) = self.compiler.templates.get(name) {
+                    let example = format!("{name}[{}]", template.params.join(", "));
+                    self.throw_at(
+                        CompilerError::MissingTypeArguments(name.clone(), example),
+                        None,
+                        &node,
+                    );
+                }
+                node
+            }
+            NodeKind::Type {
+                pointers,
+                name,
+                arrays,
+                generics,
+                funptr_args,
+                funptr_rets,
+            } => {
+                let mut arrays = arrays.clone();
+                let name = if self.compiler.templates.contains_key(name) {
+                    let args = generics
+                        .iter()
+                        .map(|it| SeaType::from_node(it.clone()).unwrap())
+                        .collect();
+                    self.instantiate(name, args, &node)
+                        .unwrap_or_else(|| name.clone())
+                } else {
+                    // The parser can't tell `i32[size]` apart from type arguments
+                    match self.array_size(generics) {
+                        Some(size) => arrays.insert(0, (None, Some(size))),
+                        None => self.throw_at(CompilerError::NotGeneric(name.clone()), None, &node),
+                    }
+                    name.clone()
+                };
+                Node {
+                    node: NodeKind::Type {
+                        pointers: *pointers,
+                        name,
+                        arrays,
+                        generics: vec![],
+                        funptr_args: funptr_args.clone(),
+                        funptr_rets: funptr_rets.clone(),
+                    },
+                    ..node
+                }
+            }
+            NodeKind::ExprNew {
+                id,
+                generics,
+                params,
+            } if !generics.is_empty() => {
+                let id = if self.compiler.templates.contains_key(id) {
+                    let args = generics
+                        .iter()
+                        .map(|it| SeaType::from_node(it.clone()).unwrap())
+                        .collect();
+                    self.instantiate(id, args, &node)
+                        .unwrap_or_else(|| id.clone())
+                } else {
+                    self.throw_at(CompilerError::NotGeneric(id.clone()), None, &node);
+                    id.clone()
+                };
+                Node {
+                    node: NodeKind::ExprNew {
+                        id,
+                        generics: vec![],
+                        params: params.clone(),
+                    },
+                    ..node
+                }
+            }
+            // `max[i32]`, for generic functions whose arguments can't be inferred
+            NodeKind::ExprBinaryOperator {
+                kind: OperatorKind::Index,
+                left,
+                right,
+            } => {
+                let NodeKind::ExprIdentifier(id) = &left.node else {
+                    return node;
+                };
+                if !self.compiler.templates.contains_key(id) {
+                    return node;
+                }
+                let args = match &right.node {
+                    NodeKind::ExprList(items) => items.iter().map(Analyzer::type_arg).collect(),
+                    _ => Analyzer::type_arg(right).map(|it| vec![it]),
+                };
+                match args.and_then(|it| self.instantiate(id, it, &node)) {
+                    Some(name) => Node {
+                        node: NodeKind::ExprIdentifier(name),
+                        ..node
+                    },
+                    None => node,
+                }
+            }
+            _ => node,
+        }
+    }
+
+    // A type argument written as an expression, i.e, `i32` in `max[i32]`.
+    fn type_arg(node: &Node) -> Option<SeaType> {
+        match &node.node {
+            NodeKind::ExprIdentifier(name) => Some(SeaType::named_type(name)),
+            NodeKind::Type { .. } => SeaType::from_node(node.clone()),
+            _ => None,
+        }
+    }
+
+    // The size of an array given as a name, i.e, `size` in `i32[size]`.
+    fn array_size(&self, generics: &[Node]) -> Option<String> {
+        let [arg] = generics else {
+            return None;
+        };
+        let typ = SeaType::from_node(arg.clone())?;
+        let is_type = typ.is_numeric()
+            || typ.name == "void"
+            || matches!(
+                self.compiler.symbols.get_symbol(typ.name.clone()),
+                Some(it) if !matches!(it, Symbol::Var { .. } | Symbol::Fun { .. })
+            );
+        if typ != SeaType::named_type(&typ.name) || is_type {
+            return None;
+        }
+        Some(typ.name)
+    }
+
+    // Gets the instance of a generic for the type arguments, analyzing it first
+    // if it's new. Functions are only declared, since they can be instantiated
+    // inside of other functions, so their bodies are queued until the statement
+    // is done. Returns the name of the instance, or None if there was an error.
+    fn instantiate(&mut self, generic: &str, args: Vec<SeaType>, at: &Node) -> Option<String> {
+        let template = self.compiler.templates.get(generic)?.clone();
+        if args.len() != template.params.len() {
+            self.throw_at(
+                CompilerError::WrongTypeArgumentCount(
+                    generic.to_string(),
+                    template.params.len(),
+                    args.len(),
+                ),
+                Some(&format!(
+                    "expected `{generic}[{}]`",
+                    template.params.join(", ")
+                )),
+                at,
+            );
+            return None;
+        }
+
+        let name = generics::instance_name(generic, &args);
+        if self.compiler.instances.contains_key(&name) {
+            return Some(name);
+        }
+
+        // C names can't have brackets, so instances in the arguments use theirs
+        let mangled = args
+            .iter()
+            .map(|it| {
+                let name = match self.compiler.instances.get(&it.name) {
+                    Some(instance) => instance.c_name.clone(),
+                    None => it.name.replace('\'', "$"),
+                };
+                SeaType { name, ..it.clone() }.mangle()
+            })
+            .collect::<Vec<String>>();
+        let c_name = format!("{}${}", generic.replace('\'', "$"), mangled.join("$"));
+        // Inserted before analysis, so that recursive generics find themselves
+        self.compiler.instances.insert(
+            name.clone(),
+            Instance {
+                generic: generic.to_string(),
+                args: args.clone(),
+                c_name,
+            },
+        );
+
+        let note = format!(
+            "in `{name}`, instantiated at {}:{}:{}",
+            self.compiler.file_stack.last().unwrap().to_string_lossy(),
+            at.line,
+            at.column
+        );
+        let node = generics::substitute(template.node, &template.params, &args);
+        let node = Analyzer::rename(node, &name);
+
+        self.compiler.enter_file(template.file.clone());
+        self.compiler.instantiating.push(note.clone());
+        let node = self.resolve_types(node);
+        if let NodeKind::TopFun {
+            tags,
+            params,
+            rets,
+            contracts,
+            ..
+        } = &node.node
+        {
+            self.compiler.add_fun(
+                name.clone(),
+                tags.clone(),
+                params
+                    .iter()
+                    .map(|(_, it)| SeaType::from_node(it.clone()).unwrap())
+                    .collect(),
+                SeaType::from_node(*rets.clone()).unwrap(),
+            );
+            // The body is analyzed later, but calls can be checked right away
+            self.compiler.preconditions.insert(
+                name.clone(),
+                (
+                    params.iter().map(|(name, _)| name.clone()).collect(),
+                    contracts.clone(),
+                ),
+            );
+            self.queued.push((node, template.file, note));
+        } else {
+            let current = self.node.clone();
+            let node = self.analyze(node);
+            self.node = current;
+            self.instances.push(node);
+        }
+        self.compiler.instantiating.pop();
+        self.compiler.leave_file();
+
+        Some(name)
+    }
+
+    // Instances are declared under their own name, i.e, `List[i32]`.
+    fn rename(node: Node, name: &str) -> Node {
+        let id = name.to_string();
+        let Node { line, column, node } = node;
+        let node = match node {
+            NodeKind::TopFun {
+                tags,
+                params,
+                rets,
+                contracts,
+                expr,
+                ..
+            } => NodeKind::TopFun {
+                tags,
+                id,
+                params,
+                rets,
+                contracts,
+                expr,
+            },
+            NodeKind::TopRec { tags, fields, .. } => NodeKind::TopRec { tags, id, fields },
+            NodeKind::TopDef { tags, typ, .. } => NodeKind::TopDef { tags, id, typ },
+            NodeKind::TopTagRec { tags, entries, .. } => NodeKind::TopTagRec { tags, id, entries },
+            node => node,
+        };
+        Node { line, column, node }
+    }
+
+    // Analyzes the queued instances of generic functions. Instances that their
+    // bodies use come first, so that C has them before they're called.
+    fn analyze_queued(&mut self) {
+        for (node, file, note) in std::mem::take(&mut self.queued) {
+            self.compiler.enter_file(file);
+            self.compiler.instantiating.push(note);
+            if let NodeKind::TopFun { id, .. } = &node.node {
+                // `instantiate` declared it, and analysis declares it again
+                self.compiler.symbols.remove_symbol(id.clone());
+            }
+            let node = self.analyze(node);
+            self.compiler.instantiating.pop();
+            self.compiler.leave_file();
+
+            self.analyze_queued();
+            self.instances.push(node);
+        }
+    }
+
+    // Calls to generic functions call the instance for their arguments' types,
+    // i.e, `ds'push(ref list, 1)` calls `ds'push[i32]` for a `ds'List[i32]`.
+    fn generic_call(&mut self, left: Node, params: &[Node]) -> Node {
+        let NodeKind::ExprIdentifier(id) = &left.node else {
+            return left;
+        };
+        let Some(template) = self.compiler.templates.get(id).cloned() else {
+            return left;
+        };
+        let NodeKind::TopFun {
+            params: fun_params, ..
+        } = template.node.node
+        else {
+            return left;
+        };
+        let type_params = template.params;
+
+        let patterns: Vec<Node> = fun_params.into_iter().map(|(_, it)| it).collect();
+        let example = format!("{id}[{}](...)", type_params.join(", "));
+        let Some(args) = self.infer_type_args(id, &type_params, &patterns, params, &example) else {
+            return left;
+        };
+        match self.instantiate(id, args, &left) {
+            Some(name) => Node {
+                node: NodeKind::ExprIdentifier(name),
+                ..left
+            },
+            None => left,
+        }
+    }
+
+    // Like `generic_call`, but for the fields of generic recs and tag recs, where
+    // the first parameter of a tag rec is the entry.
+    fn generic_new(&mut self, id: String, params: &[Node]) -> String {
+        let Some(template) = self.compiler.templates.get(&id).cloned() else {
+            return id;
+        };
+        let (patterns, values): (Vec<Node>, &[Node]) = match template.node.node {
+            NodeKind::TopRec { fields, .. } => {
+                (fields.into_iter().map(|(_, it)| it).collect(), params)
+            }
+            NodeKind::TopTagRec { entries, .. } => {
+                let entry = match params.first().map(|it| &it.node) {
+                    Some(NodeKind::ExprIdentifier(kind)) => kind.rsplit('\'').next().unwrap(),
+                    _ => return id,
+                };
+                let fields = entries
+                    .into_iter()
+                    .find(|(it, _)| it == entry)
+                    .map(|(_, it)| it)
+                    .unwrap_or_default();
+                (fields.into_iter().map(|(_, it)| it).collect(), &params[1..])
+            }
+            _ => return id,
+        };
+
+        let example = format!("new {id}[{}](...)", template.params.join(", "));
+        let at = self.node.clone();
+        self.infer_type_args(&id, &template.params, &patterns, values, &example)
+            .and_then(|args| self.instantiate(&id, args, &at))
+            .unwrap_or(id)
+    }
+
+    // Infers the type arguments of a generic from the values given for the
+    // `patterns`, which are types that use the type parameters. Number literals
+    // are looked at last, since they could be any kind of number.
+    fn infer_type_args(
+        &self,
+        generic: &str,
+        type_params: &[String],
+        patterns: &[Node],
+        values: &[Node],
+        example: &str,
+    ) -> Option<Vec<SeaType>> {
+        let mut pairs: Vec<(&Node, &Node)> = patterns.iter().zip(values).collect();
+        pairs.sort_by_key(|(_, value)| number_literal(value).is_some());
+
+        let mut bindings: HashMap<String, SeaType> = HashMap::new();
+        for (pattern, value) in pairs {
+            if let Ok(typ) = infer_type_of_node(self.compiler, value) {
+                self.unify(pattern, &typ, type_params, &mut bindings);
+            }
+        }
+
+        let mut args: Vec<SeaType> = vec![];
+        for param in type_params {
+            match bindings.remove(param) {
+                Some(it) => args.push(it),
+                None => {
+                    self.throw(
+                        CompilerError::CannotInferTypeArgument(generic.to_string(), param.clone()),
+                        Some(&format!("give the type arguments, i.e, `{example}`")),
+                    );
+                    return None;
+                }
+            }
+        }
+        Some(args)
+    }
+
+    // Binds the type parameters in `pattern` to the parts of `got` that are in
+    // their place, i.e, `T` to `i32` for `^List[T]` and `^List[i32]`. The first
+    // binding of each parameter is kept.
+    fn unify(
+        &self,
+        pattern: &Node,
+        got: &SeaType,
+        type_params: &[String],
+        bindings: &mut HashMap<String, SeaType>,
+    ) {
+        let NodeKind::Type {
+            pointers,
+            name,
+            arrays,
+            generics,
+            funptr_args,
+            funptr_rets,
+        } = &pattern.node
+        else {
+            return;
+        };
+
+        if let (Some(args), Some(rets)) = (funptr_args, funptr_rets) {
+            if let (Some(got_args), Some(got_rets)) = (&got.funptr_args, &got.funptr_rets) {
+                for (arg, got_arg) in args.iter().zip(got_args) {
+                    self.unify(arg, got_arg, type_params, bindings);
+                }
+                self.unify(rets, got_rets, type_params, bindings);
+            }
+            return;
+        }
+
+        if got.pointers < *pointers || got.arrays.len() < arrays.len() {
+            return;
+        }
+        let inner = SeaType {
+            pointers: got.pointers - pointers,
+            arrays: got.arrays[..got.arrays.len() - arrays.len()].to_vec(),
+            ..got.clone()
+        };
+
+        if generics.is_empty() && type_params.contains(name) {
+            bindings.entry(name.clone()).or_insert(inner);
+        } else if let Some(instance) = self.compiler.instances.get(&self.resolve_type(&inner).name)
+        {
+            if instance.generic == *name {
+                for (pattern, arg) in generics.iter().zip(&instance.args) {
+                    self.unify(pattern, arg, type_params, bindings);
+                }
+            }
+        }
+    }
+
+    // #endregion: Generics
+
+    // #region: Statements
+
+    // Lowers `for i, item of items` to a range loop over the indices of `items`.
+    // Arrays need a known size, anything else needs the functions from
+    // `iterator_functions`.
+    fn for_of(
+        &mut self,
+        for_node: &Node,
+        index: Option<String>,
+        var: String,
+        iter: Node,
+        expr: Node,
+    ) -> Node {
+        let n = |node| Node {
+            line: for_node.line,
+            column: for_node.column,
+            node,
+        };
+        let (name, typ) = match infer_type_of_node(self.compiler, &iter) {
+            Ok(it) => (it.to_string(), self.resolve_type(&it)),
+            Err(why) => {
+                self.throw(CompilerError::InferenceError(why), None);
+                return n(NodeKind::Error);
+            }
+        };
+        let index = index.unwrap_or_else(|| "for'index".to_string());
+        let index_node = n(NodeKind::ExprIdentifier(index.clone()));
+
+        let mut collection = None;
+        let (len, item) = if typ.pointers == 0 && !typ.arrays.is_empty() {
+            let len = match typ.arrays.last().unwrap() {
+                (Some(size), _) => NodeKind::ExprNumber(size.to_string()),
+                (None, Some(size)) => NodeKind::ExprIdentifier(size.clone()),
+                (None, None) => {
+                    self.throw(
+                        CompilerError::NotIterable(name.clone()),
+                        Some("only arrays with a size, like `i32[4]`, have a known length"),
+                    );
+                    return n(NodeKind::Error);
+                }
+            };
+            (n(len), Node::join(OperatorKind::Index, iter, index_node))
+        } else {
+            let Some((len, at, index_type)) = self.iterator_functions(&typ) else {
+                let help =
+                    "declare `len(collection)` and `at(collection, index)` functions next to it";
+                self.throw(
+                    CompilerError::NotIterable(name.clone()),
+                    (!typ.is_numeric()).then_some(help),
+                );
+                return n(NodeKind::Error);
+            };
+            // The collection is only evaluated once
+            let of = n(NodeKind::ExprIdentifier("for'of".to_string()));
+            collection = Some(n(NodeKind::StatExpr(Box::new(n(NodeKind::ExprVar {
+                name: "for'of".to_string(),
+                typ: None,
+                value: Box::new(iter),
+            })))));
+            let index_arg = match index_type {
+                Some(typ) => Node::join(OperatorKind::As, index_node, typ.to_node()),
+                None => index_node,
+            };
+            (
+                n(NodeKind::ExprInvoke {
+                    left: Box::new(n(NodeKind::ExprIdentifier(len))),
+                    params: vec![of.clone()],
+                }),
+                n(NodeKind::ExprInvoke {
+                    left: Box::new(n(NodeKind::ExprIdentifier(at))),
+                    params: vec![of, index_arg],
+                }),
+            )
+        };
+
+        // The element is declared at the start of the loop's body
+        let item = n(NodeKind::StatExpr(Box::new(n(NodeKind::ExprLet {
+            name: var,
+            typ: None,
+            value: Box::new(item),
+        }))));
+        let body = match expr.node {
+            NodeKind::ExprBlock(mut nodes) => {
+                nodes.insert(0, item);
+                Node {
+                    node: NodeKind::ExprBlock(nodes),
+                    ..expr
+                }
+            }
+            _ => n(NodeKind::ExprBlock(vec![item, expr])),
+        };
+        let for_range = n(NodeKind::StatForRange {
+            var: Some(index),
+            from: Box::new(n(NodeKind::ExprNumber("0".to_string()))),
+            to: Box::new(len),
+            expr: Box::new(body),
+        });
+        match collection {
+            Some(collection) => n(NodeKind::StatExpr(Box::new(n(NodeKind::ExprBlock(vec![
+                collection, for_range,
+            ]))))),
+            None => for_range,
+        }
+    }
+
+    // The functions that `for of` loops call on collections that aren't arrays,
+    // which are declared next to the collection's type: for `list'List`, they're
+    // `list'len(list)` and `list'at(list, index)`. Also returns the type of
+    // `at`'s index, which the loop's index is cast to. Both have to take the
+    // collection (or a pointer to it) first.
+    fn iterator_functions(&self, typ: &SeaType) -> Option<(String, String, Option<SeaType>)> {
+        if typ.is_numeric() || typ.funptr_rets.is_some() {
+            return None;
+        }
+        // `List[i32]` is declared next to `List`
+        let name = typ.name.split('[').next().unwrap();
+        let prefix = match name.rsplit_once('\'') {
+            Some((pkg, _)) => format!("{pkg}'"),
+            None => String::new(),
+        };
+        let params_of = |id: &str| match self.compiler.symbols.get_symbol(id.to_string())? {
+            Symbol::Fun { params, .. } => Some(params.clone()),
+            Symbol::Generic { symbol, .. } => match symbol.as_ref() {
+                Symbol::Fun { params, .. } => Some(params.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let takes_collection = |params: &[SeaType]| {
+            params.first().is_some_and(|it| {
+                let param = self.resolve_type(it);
+                param.name.split('[').next() == Some(name)
+                    && param.pointers <= 1
+                    && param.arrays.is_empty()
+            })
+        };
+        let (len, at) = (format!("{prefix}len"), format!("{prefix}at"));
+        let (len_params, at_params) = (params_of(&len)?, params_of(&at)?);
+        if !takes_collection(&len_params) || !takes_collection(&at_params) {
+            return None;
+        }
+        Some((len, at, at_params.get(1).cloned()))
+    }
+
+    // #endregion: Statements
+
+    // #region: Expressions
+
+    pub fn stat_ret(&mut self, value: &Node) {
+        if let Some(rets) = self.rets.clone() {
+            self.check_value(&rets, value, None);
+        }
+    }
+
+    pub fn expr_new(&mut self, id: &str, params: &[Node]) {
+        let symbol = match self.compiler.symbols.get_symbol(id.to_string()) {
+            Some(it) => it.clone(),
+            None => return self.throw(CompilerError::UnknownSymbol(id.to_string()), None),
+        };
+
+        // `generic_new` already reported why it couldn't be instantiated
+        if let Symbol::Generic { .. } = symbol {
+            return;
+        }
+        if !symbol.instantiatable() {
+            return self.throw(
+                CompilerError::Uninstantiatable(id.to_string(), symbol),
+                None,
+            );
+        }
+
+        // When instantiating tag recs, the first parameter is the entry we are instantiating
+        if let Symbol::TagRec { .. } = symbol {
+            match params.first().map(|it| &it.node) {
+                None => self.throw(CompilerError::TagRecInstantiateWithoutKind, None),
+                Some(NodeKind::ExprIdentifier(_)) => {}
+                Some(_) => self.throw(
+                    CompilerError::TagRecInstantiateWithoutKind,
+                    Some(
+                        "the first parameter must be an entry in the tag rec (it currently is not)",
+                    ),
+                ),
+            }
+        }
+    }
+
+    // Returns the variable's type, inferring it from `value` if it wasn't given.
+    pub fn var_type(&mut self, typ: Option<Box<Node>>, value: &Node) -> Option<SeaType> {
+        match typ {
+            Some(typ) => {
+                let typ = SeaType::from_node(*typ)?;
+                // Generics that are still named after their template couldn't be
+                // instantiated, which `resolve_generic` already reported
+                if !self.compiler.templates.contains_key(&typ.name) {
+                    self.check_value(&typ, value, None);
+                }
+                Some(typ)
+            }
+            None => match infer_type_of_node(self.compiler, value) {
+                Ok(it) => Some(it),
+                Err(why) => {
+                    self.throw(CompilerError::InferenceError(why), None);
+                    None
+                }
+            },
+        }
+    }
+
+    // Registers the variable under `symbol` and returns its type as a node, so
+    // that backends don't need to infer it. `var_node` is the declaration.
+    pub fn add_var(
+        &mut self,
+        symbol: String,
+        typ: Option<SeaType>,
+        mutable: bool,
+        var_node: &Node,
+    ) -> Option<Box<Node>> {
+        let typ = typ?;
+        let added = self.compiler.add_var(symbol.clone(), typ.clone(), mutable);
+        self.declare(added, &symbol, var_node);
+        Some(Box::new(typ.to_node()))
+    }
+
+    // Calls to a trait's methods go straight to the implementation when the
+    // receiver is a pointer to a rec, i.e, `Shape'area(ref circle)` calls
+    // `Circle'Shape'area`. Otherwise they go through the receiver's vtable.
+    fn trait_method(&self, left: Node, params: &[Node]) -> Node {
+        let NodeKind::ExprIdentifier(id) = &left.node else {
+            return left;
+        };
+        let Some((trait_, method)) = id.rsplit_once('\'') else {
+            return left;
+        };
+        match self.compiler.symbols.get_symbol(trait_.to_string()) {
+            Some(Symbol::Trait { methods }) if methods.iter().any(|it| it.0 == method) => {}
+            _ => return left,
+        }
+        let Some(Ok(typ)) = params
+            .first()
+            .map(|it| infer_type_of_node(self.compiler, it))
+        else {
+            return left;
+        };
+
+        let typ = self.resolve_type(&typ);
+        if typ.pointers != 1
+            || !typ.arrays.is_empty()
+            || typ.funptr_rets.is_some()
+            || traits::dyn_trait(&typ.name).is_some()
+        {
+            return left;
+        }
+        if !self
+            .compiler
+            .impls
+            .contains(&(trait_.to_string(), typ.name.clone()))
+        {
+            self.throw_at(
+                CompilerError::NotImplemented(trait_.to_string(), typ.name.clone()),
+                None,
+                &params[0],
+            );
+        }
+        Node {
+            node: NodeKind::ExprIdentifier(traits::impl_method_name(trait_, &typ.name, method)),
+            ..left
+        }
+    }
+
+    // `value as dyn Shape` turns a pointer to a rec that implements `Shape` into
+    // a `dyn Shape`, which points to the rec and the impl's vtable.
+    fn as_dyn(&mut self, value: Node, typ: Node) -> NodeKind {
+        let as_node = self.node.clone();
+        let target = SeaType::from_node(typ.clone()).unwrap();
+        let trait_ = traits::dyn_trait(&target.name).unwrap().to_string();
+        let value = self.analyze(value);
+        let unchanged = |value: Node| NodeKind::ExprBinaryOperator {
+            kind: OperatorKind::As,
+            left: Box::new(value),
+            right: Box::new(typ.clone()),
+        };
+
+        match self.compiler.symbols.get_symbol(trait_.clone()) {
+            Some(Symbol::Trait { .. }) => {}
+            Some(_) => {
+                self.throw_at(CompilerError::NotATrait(trait_), None, &as_node);
+                return unchanged(value);
+            }
+            None => {
+                self.throw_at(CompilerError::UnknownSymbol(trait_), None, &as_node);
+                return unchanged(value);
+            }
+        }
+        let got = match infer_type_of_node(self.compiler, &value) {
+            Ok(it) => self.resolve_type(&it),
+            Err(_) => return unchanged(value),
+        };
+        if got == target {
+            return value.node;
+        }
+
+        let help = format!(
+            "only pointers to recs that implement `{trait_}` can be used as `dyn {trait_}`"
+        );
+        if got.pointers != 1 || !got.arrays.is_empty() || got.funptr_rets.is_some() {
+            self.throw_at(
+                CompilerError::MismatchedTypes(target.to_string(), got.to_string()),
+                Some(&help),
+                &value,
+            );
+            return unchanged(value);
+        }
+        if !self
+            .compiler
+            .impls
+            .contains(&(trait_.clone(), got.name.clone()))
+        {
+            self.throw_at(
+                CompilerError::NotImplemented(trait_.clone(), got.name.clone()),
+                Some(&help),
+                &value,
+            );
+            return unchanged(value);
+        }
+
+        let vtable = Node {
+            line: value.line,
+            column: value.column,
+            node: NodeKind::ExprUnaryOperator {
+                kind: OperatorKind::Ref,
+                value: Box::new(Node {
+                    line: value.line,
+                    column: value.column,
+                    node: NodeKind::ExprIdentifier(traits::impl_vtable_name(&trait_, &got.name)),
+                }),
+            },
+        };
+        NodeKind::ExprNew {
+            id: traits::dyn_name(&trait_),
+            generics: vec![],
+            params: vec![value, vtable],
+        }
+    }
+
+    // Checks the arguments of calls to Sea functions and function pointers.
+    pub fn expr_invoke(&mut self, left: &Node, params: &[Node]) {
+        let (name, signature, param_types) = match &left.node {
+            NodeKind::ExprIdentifier(id) => match self.compiler.symbols.get_symbol(id.clone()) {
+                Some(
+                    symbol @ Symbol::Fun {
+                        tags: _,
+                        params,
+                        rets: _,
+                    },
+                ) => (id.clone(), symbol.signature(id), params.clone()),
+                Some(Symbol::Var { typ, mutable: _ }) if typ.funptr_args.is_some() => (
+                    id.clone(),
+                    format!("{id}: {typ}"),
+                    typ.funptr_args.clone().unwrap(),
+                ),
+                _ => return,
+            },
+            _ => match infer_type_of_node(self.compiler, left) {
+                Ok(typ) if typ.funptr_args.is_some() => (
+                    "function pointer".to_string(),
+                    format!("{typ}"),
+                    typ.funptr_args.clone().unwrap(),
+                ),
+                _ => return,
+            },
+        };
+        let help = format!("expected signature: {signature}");
+
+        if params.len() != param_types.len() {
+            // Point at the first extra argument, or the call itself if there are too few
+            let at = params.get(param_types.len()).unwrap_or(&self.node).clone();
+            return self.throw_at(
+                CompilerError::WrongArgumentCount(name, param_types.len(), params.len()),
+                Some(&help),
+                &at,
+            );
+        }
+
+        for (param, expected) in params.iter().zip(param_types.iter()) {
+            self.check_value(expected, param, Some(&help));
+        }
+    }
+
+    // Calls whose arguments are constants can be checked against the function's
+    // `@arg` contracts before the program runs.
+    fn check_preconditions(&self, left: &Node, params: &[Node]) {
+        let NodeKind::ExprIdentifier(id) = &left.node else {
+            return;
+        };
+        let Some((names, contracts)) = self.compiler.preconditions.get(id) else {
+            return;
+        };
+        let args: HashMap<String, Node> =
+            names.iter().cloned().zip(params.iter().cloned()).collect();
+        for (cond, text) in contracts::conditions(contracts, ContractKind::Arg) {
+            if contracts::evaluate(&cond, &args) == Some(Value::Bool(false)) {
+                self.throw(CompilerError::ContractViolation(id.clone(), text), None);
+            }
+        }
+    }
+
+    // Functions can only write to the globals in their `@mut` contracts.
+    fn check_mutation(&self, target: &Node) {
+        let (Some(mutates), Some(name)) = (&self.mutates, contracts::written_var(target)) else {
+            return;
+        };
+        if mutates.contains(&name) || !self.compiler.symbols.is_global_var(&name) {
+            return;
+        }
+        self.throw_at(
+            CompilerError::UndeclaredMutation(name.clone()),
+            Some(&format!(
+                "add `@mut({name})` after the function's signature"
+            )),
+            target,
+        );
+    }
+
+    // Assignments can't convert implicitly, and both sides of arithmetic and
+    // comparisons must be the same kind of number.
+    pub fn expr_binary_operator(&mut self, kind: OperatorKind, left: &Node, right: &Node) {
+        match kind {
+            OperatorKind::Assign => {
+                self.check_mutation(left);
+                if let Ok(typ) = infer_type_of_node(self.compiler, left) {
+                    self.check_value(&typ, right, None);
+                }
+            }
+            OperatorKind::Add
+            | OperatorKind::Sub
+            | OperatorKind::Mul
+            | OperatorKind::Div
+            | OperatorKind::Mod
+            | OperatorKind::Eq
+            | OperatorKind::Neq
+            | OperatorKind::Gt
+            | OperatorKind::GtEq
+            | OperatorKind::Lt
+            | OperatorKind::LtEq => self.check_operands(left, right),
+            _ => {}
+        }
+    }
+
+    fn check_operands(&self, left: &Node, right: &Node) {
+        let (left_type, right_type) = match (
+            infer_type_of_node(self.compiler, left),
+            infer_type_of_node(self.compiler, right),
+        ) {
+            (Ok(left_type), Ok(right_type)) => (left_type, right_type),
+            _ => return,
+        };
+
+        // Pointer arithmetic and such is left alone
+        let (left_kind, right_kind) = match (
+            self.resolve_type(&left_type).number_kind(),
+            self.resolve_type(&right_type).number_kind(),
+        ) {
+            (Some(left_kind), Some(right_kind)) => (left_kind, right_kind),
+            _ => return,
+        };
+
+        let compatible = match (number_literal(left), number_literal(right)) {
+            (Some(_), Some(_)) => true,
+            (Some(literal), None) => Analyzer::literal_converts_to(literal, right_kind),
+            (None, Some(literal)) => Analyzer::literal_converts_to(literal, left_kind),
+            (None, None) => {
+                left_kind.converts_implicitly_to(right_kind)
+                    || right_kind.converts_implicitly_to(left_kind)
+            }
+        };
+
+        // Suggest casting the right side, unless it's a literal
+        if !compatible {
+            if number_literal(right).is_some() {
+                self.throw_implicit_conversion(&left_type, &right_type, left, None);
+            } else {
+                self.throw_implicit_conversion(&right_type, &left_type, right, None);
+            }
+        }
+    }
+
+    // #endregion: Expressions
+
+    pub fn pkg_statement(&mut self, node: Node, namespace: &str) -> Node {
+        *self.node = node.clone();
+        let Node { line, column, node } = node;
+        let node = match node {
+            NodeKind::Raw(_) | NodeKind::Error => node,
+            NodeKind::TopPkg { name, statements } => NodeKind::TopPkg {
+                statements: self.top_pkg(format!("{namespace}'{name}"), statements),
+                name,
+            },
+            NodeKind::TopFun {
+                tags,
+                id,
+                params,
+                rets,
+                contracts,
+                expr,
+            } => {
+                let (contracts, expr) = self.top_fun(
+                    tags.clone(),
+                    format!("{namespace}'{id}"),
+                    &params,
+                    &rets,
+                    contracts,
+                    *expr,
+                );
+                NodeKind::TopFun {
+                    tags,
+                    id,
+                    params,
+                    rets,
+                    contracts,
+                    expr,
+                }
+            }
+            NodeKind::TopRec { tags, id, fields } => {
+                self.top_rec(tags.clone(), format!("{namespace}'{id}"), &fields);
+                NodeKind::TopRec { tags, id, fields }
+            }
+            NodeKind::TopDef { tags, id, typ } => {
+                self.top_def(tags.clone(), format!("{namespace}'{id}"), &typ);
+                NodeKind::TopDef { tags, id, typ }
+            }
+            NodeKind::TopTag { tags, id, entries } => NodeKind::TopTag {
+                entries: self.top_tag(tags.clone(), format!("{namespace}'{id}"), entries),
+                tags,
+                id,
+            },
+            NodeKind::TopTagRec { tags, id, entries } => {
+                self.top_tag_rec(tags.clone(), format!("{namespace}'{id}"), &entries);
+                NodeKind::TopTagRec { tags, id, entries }
+            }
+            NodeKind::TopTrait { id, methods } => {
+                self.top_trait(format!("{namespace}'{id}"), &methods);
+                NodeKind::TopTrait { id, methods }
+            }
+            // The trait and rec are written out in full, so there's nothing to namespace
+            NodeKind::TopImpl { trait_, rec, funs } => NodeKind::TopImpl {
+                funs: self.top_impl(&trait_, &rec, funs),
+                trait_,
+                rec,
+            },
+            NodeKind::TopGeneric { params, node } => {
+                if let Some(id) = generics::declared_id(&node) {
+                    self.top_generic(format!("{namespace}'{id}"), params.clone(), &node);
+                }
+                NodeKind::TopGeneric { params, node }
+            }
+            NodeKind::StatExpr(expr) => {
+                NodeKind::StatExpr(Box::new(self.pkg_statement(*expr, namespace)))
+            }
+            NodeKind::ExprVar { name, typ, value } => {
+                let var_node = self.node.clone();
+                // Analysis can change the value, i.e, to a generic's instance
+                let value = self.analyze_boxed(*value);
+                self.node = var_node.clone();
+                let typ = self.var_type(typ, &value);
+                NodeKind::ExprVar {
+                    typ: self.add_var(format!("{namespace}'{name}"), typ, true, &var_node),
+                    name,
+                    value,
+                }
+            }
+            NodeKind::ExprLet { name, typ, value } => {
+                let var_node = self.node.clone();
+                let value = self.analyze_boxed(*value);
+                self.node = var_node.clone();
+                let typ = self.var_type(typ, &value);
+                NodeKind::ExprLet {
+                    typ: self.add_var(format!("{namespace}'{name}"), typ, false, &var_node),
+                    name,
+                    value,
+                }
+            }
+            node => {
+                self.throw(CompilerError::StatementNotAllowedAtTopLevel(), None);
+                node
+            }
+        };
+        Node { line, column, node }
+    }
+
+    // Analyzes the node and its children, returning the annotated node.
+    pub fn analyze(&mut self, node: Node) -> Node {
+        *self.node = node.clone();
+        let Node { line, column, node } = node;
+        let node = match node {
+            NodeKind::Program(nodes) => NodeKind::Program(self.analyze_statements(nodes, None)),
+            NodeKind::Raw(_) | NodeKind::Error | NodeKind::Type { .. } => node,
+            // Contracts are analyzed along with their function, see `contracts`
+            NodeKind::Contract { .. } => node,
+            NodeKind::TopUse(path) => {
+                self.top_use(path.clone());
+                NodeKind::TopUse(path)
+            }
+            NodeKind::TopPkg { name, statements } => NodeKind::TopPkg {
+                statements: self.top_pkg(name.clone(), statements),
+                name,
+            },
+            NodeKind::TopFun {
+                tags,
+                id,
+                params,
+                rets,
+                contracts,
+                expr,
+            } => {
+                let (contracts, expr) =
+                    self.top_fun(tags.clone(), id.clone(), &params, &rets, contracts, *expr);
+                NodeKind::TopFun {
+                    tags,
+                    id,
+                    params,
+                    rets,
+                    contracts,
+                    expr,
+                }
+            }
+            NodeKind::TopRec { tags, id, fields } => {
+                self.top_rec(tags.clone(), id.clone(), &fields);
+                NodeKind::TopRec { tags, id, fields }
+            }
+            NodeKind::TopDef { tags, id, typ } => {
+                self.top_def(tags.clone(), id.clone(), &typ);
+                NodeKind::TopDef { tags, id, typ }
+            }
+            NodeKind::TopTag { tags, id, entries } => NodeKind::TopTag {
+                entries: self.top_tag(tags.clone(), id.clone(), entries),
+                tags,
+                id,
+            },
+            NodeKind::TopTagRec { tags, id, entries } => {
+                self.top_tag_rec(tags.clone(), id.clone(), &entries);
+                NodeKind::TopTagRec { tags, id, entries }
+            }
+            NodeKind::TopPragma { .. } => {
+                self.compiler.handle_pragma(*self.node.clone());
+                node
+            }
+            NodeKind::TopTrait { id, methods } => {
+                self.top_trait(id.clone(), &methods);
+                NodeKind::TopTrait { id, methods }
+            }
+            NodeKind::TopImpl { trait_, rec, funs } => NodeKind::TopImpl {
+                funs: self.top_impl(&trait_, &rec, funs),
+                trait_,
+                rec,
+            },
+            NodeKind::TopGeneric { params, node } => {
+                if let Some(id) = generics::declared_id(&node) {
+                    self.top_generic(id, params.clone(), &node);
+                }
+                NodeKind::TopGeneric { params, node }
+            }
+            // Instances are only added to programs after they're analyzed
+            NodeKind::TopInstance(_) => node,
+            NodeKind::StatRet(value) => NodeKind::StatRet(value.map(|it| {
+                let value = self.analyze_boxed(*it);
+                self.stat_ret(&value);
+                value
+            })),
+            NodeKind::StatIf { cond, expr, else_ } => NodeKind::StatIf {
+                cond: self.analyze_boxed(*cond),
+                expr: self.analyze_boxed(*expr),
+                else_: else_.map(|it| self.analyze_boxed(*it)),
+            },
+            NodeKind::StatSwitch { switch, cases } => NodeKind::StatSwitch {
+                switch: self.analyze_boxed(*switch),
+                cases: cases
+                    .into_iter()
+                    .map(|(case, fall, expr)| {
+                        (
+                            case.map(|it| self.analyze_boxed(*it)),
+                            fall,
+                            self.analyze_boxed(*expr),
+                        )
+                    })
+                    .collect(),
+            },
+            NodeKind::StatForCStyle {
+                def,
+                cond,
+                inc,
+                expr,
+            } => {
+                // Variables declared in the loop only exist inside of it
+                self.compiler.push_scope();
+                let node = NodeKind::StatForCStyle {
+                    def: self.analyze_boxed(*def),
+                    cond: self.analyze_boxed(*cond),
+                    inc: self.analyze_boxed(*inc),
+                    expr: self.analyze_boxed(*expr),
+                };
+                self.compiler.pop_scope();
+                node
+            }
+            NodeKind::StatForSingleExpr { cond, expr } => NodeKind::StatForSingleExpr {
+                cond: self.analyze_boxed(*cond),
+                expr: self.analyze_boxed(*expr),
+            },
+            NodeKind::StatForRange {
+                var,
+                from,
+                to,
+                expr,
+            } => {
+                let for_node = self.node.clone();
+                let from = self.analyze_boxed(*from);
+                let to = self.analyze_boxed(*to);
+                self.compiler.push_scope();
+                if let Some(var) = &var {
+                    let added = self
+                        .compiler
+                        .add_var(var.clone(), SeaType::I32.clone(), true);
+                    self.declare(added, var, &for_node);
+                }
+                let node = NodeKind::StatForRange {
+                    expr: self.analyze_boxed(*expr),
+                    var,
+                    from,
+                    to,
+                };
+                self.compiler.pop_scope();
+                node
+            }
+            NodeKind::StatForOf {
+                index,
+                var,
+                iter,
+                expr,
+            } => {
+                let for_node = self.node.clone();
+                let lowered = self.for_of(&for_node, index, var, *iter, *expr);
+                self.analyze(lowered).node
+            }
+            NodeKind::StatContinue | NodeKind::StatBreak => node,
+            NodeKind::StatDefer(expr) => NodeKind::StatDefer(self.analyze_boxed(*expr)),
+            NodeKind::StatExpr(expr) => NodeKind::StatExpr(self.analyze_boxed(*expr)),
+            NodeKind::ExprGroup(expr) => NodeKind::ExprGroup(self.analyze_boxed(*expr)),
+            NodeKind::ExprNumber(_)
+            | NodeKind::ExprString(_)
+            | NodeKind::ExprCString(_)
+            | NodeKind::ExprChar(_)
+            | NodeKind::ExprTrue
+            | NodeKind::ExprFalse => node,
+            NodeKind::ExprIdentifier(ref id) => {
+                // Remember what the identifier refers to, for tooling
+                if let Some(definition) = self.compiler.symbols.get_definition(id).cloned() {
+                    let at = self.location_of(&self.node);
+                    self.compiler.symbols.set_reference(at, definition);
+                }
+                node
+            }
+            NodeKind::ExprBlock(nodes) => {
+                self.compiler.push_scope();
+                let nodes = self.analyze_all(nodes);
+                self.compiler.pop_scope();
+                NodeKind::ExprBlock(nodes)
+            }
+            NodeKind::ExprNew { id, params, .. } => {
+                let id = self.generic_new(id, &params);
+                self.expr_new(&id, &params);
+                NodeKind::ExprNew {
+                    params: self.analyze_all(params),
+                    generics: vec![],
+                    id,
+                }
+            }
+            NodeKind::ExprUnaryOperator { kind, value } => {
+                if let OperatorKind::Inc | OperatorKind::Dec = kind {
+                    self.check_mutation(&value);
+                }
+                NodeKind::ExprUnaryOperator {
+                    kind,
+                    value: self.analyze_boxed(*value),
+                }
+            }
+            NodeKind::ExprBinaryOperator {
+                kind: OperatorKind::As,
+                left,
+                right,
+            } if Analyzer::is_dyn_type(&right) => self.as_dyn(*left, *right),
+            NodeKind::ExprBinaryOperator { kind, left, right } => {
+                self.expr_binary_operator(kind, &left, &right);
+                NodeKind::ExprBinaryOperator {
+                    kind,
+                    left: self.analyze_boxed(*left),
+                    // The right side of a dot is a field, not a symbol
+                    right: if kind == OperatorKind::Dot {
+                        right
+                    } else {
+                        self.analyze_boxed(*right)
+                    },
+                }
+            }
+            NodeKind::ExprInvoke { left, params } => {
+                // The arguments come first, since generic functions are
+                // instantiated for their types
+                let invoke_node = self.node.clone();
+                let params = self.analyze_all(params);
+                self.node = invoke_node;
+                let left = self.generic_call(*left, &params);
+                let left = Box::new(self.trait_method(left, &params));
+                self.expr_invoke(&left, &params);
+                self.check_preconditions(&left, &params);
+                NodeKind::ExprInvoke {
+                    left: self.analyze_boxed(*left),
+                    params,
+                }
+            }
+            NodeKind::ExprList(nodes) => NodeKind::ExprList(self.analyze_all(nodes)),
+            NodeKind::ExprVar { name, typ, value } => {
+                let var_node = self.node.clone();
+                let value = self.analyze_boxed(*value);
+                self.node = var_node.clone();
+                let typ = self.var_type(typ, &value);
+                NodeKind::ExprVar {
+                    typ: self.add_var(name.clone(), typ, true, &var_node),
+                    name,
+                    value,
+                }
+            }
+            NodeKind::ExprLet { name, typ, value } => {
+                let var_node = self.node.clone();
+                let value = self.analyze_boxed(*value);
+                self.node = var_node.clone();
+                let typ = self.var_type(typ, &value);
+                NodeKind::ExprLet {
+                    typ: self.add_var(name.clone(), typ, false, &var_node),
+                    name,
+                    value,
+                }
+            }
+        };
+        let node = Node { line, column, node };
+
+        // Remember the type of each expression so that backends and tooling
+        // don't need to infer it again.
+        if Analyzer::is_typed_expression(&node.node) {
+            if let Ok(typ) = infer_type_of_node(self.compiler, &node) {
+                let file = self.compiler.file_stack.last().unwrap().clone();
+                self.compiler.symbols.set_type_of(file, line, column, typ);
+            }
+        }
+
+        node
+    }
+
+    fn is_dyn_type(node: &Node) -> bool {
+        SeaType::from_node(node.clone()).is_some_and(|it| traits::dyn_trait_of(&it).is_some())
+    }
+
+    // Blocks and variable declarations don't have a type of their own.
+    fn is_typed_expression(node: &NodeKind) -> bool {
+        matches!(
+            node,
+            NodeKind::ExprGroup(_)
+                | NodeKind::ExprNumber(_)
+                | NodeKind::ExprString(_)
+                | NodeKind::ExprCString(_)
+                | NodeKind::ExprChar(_)
+                | NodeKind::ExprTrue
+                | NodeKind::ExprFalse
+                | NodeKind::ExprIdentifier(_)
+                | NodeKind::ExprNew { .. }
+                | NodeKind::ExprUnaryOperator { .. }
+                | NodeKind::ExprBinaryOperator { .. }
+                | NodeKind::ExprInvoke { .. }
+                | NodeKind::ExprList(_)
+        )
+    }
+}
@@ -0,0 +1,147 @@
+// Each translation unit from `CBackend::write_units` is compiled to an object
+// file in `.sea/cache`, named after a hash of its code, the prelude, the C
+// compiler and its flags, and this version of Sea. Unchanged modules are reused
+// on later builds. The prelude declares everything in every module, so changing
+// what a module declares rebuilds all of them, but changing the body of a
+// function only rebuilds its module. Units that need compiling are compiled in
+// parallel.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+};
+
+use crate::deps::sha256::Sha256;
+
+pub const DIR: &str = ".sea/cache";
+
+pub struct Cache {
+    pub dir: PathBuf,
+    pub cc: String,
+    pub cc_flags: Vec<String>,
+    pub rebuild: bool, // compile every unit again, even if it's cached
+    pub reused: usize,
+}
+
+impl Cache {
+    // Flags for the linker are left out, since units are compiled with `-c`.
+    pub fn new(dir: PathBuf, cc: String, cc_flags: &[String]) -> Self {
+        let cc_flags = cc_flags
+            .iter()
+            .filter(|it| !is_link_flag(it))
+            .cloned()
+            .collect();
+        Cache {
+            dir,
+            cc,
+            cc_flags,
+            rebuild: false,
+            reused: 0,
+        }
+    }
+
+    // Returns the object files for the units, compiling the ones that aren't
+    // cached in parallel.
+    pub fn objects(&mut self, units: &[PathBuf], prelude: &Path) -> Result<Vec<PathBuf>, String> {
+        let mut objects: Vec<PathBuf> = vec![];
+        let mut queue: Vec<(PathBuf, PathBuf)> = vec![]; // units to compile, and their objects
+        for unit in units {
+            let object = self.object_of(unit, prelude)?;
+            if !self.rebuild && object.is_file() {
+                self.reused += 1;
+            } else {
+                queue.push((unit.clone(), object.clone()));
+            }
+            objects.push(object);
+        }
+        if queue.is_empty() {
+            return Ok(objects);
+        }
+        fs::create_dir_all(&self.dir)
+            .map_err(|why| format!("failed to create {:?}: {why}", self.dir))?;
+
+        let jobs = thread::available_parallelism()
+            .map_or(1, |it| it.get())
+            .min(queue.len());
+        let queue = Mutex::new(queue);
+        let this = &*self;
+        let errors: Vec<String> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs)
+                .map(|_| {
+                    scope.spawn(|| loop {
+                        let Some((unit, object)) = queue.lock().unwrap().pop() else {
+                            return Ok(());
+                        };
+                        if let Err(why) = this.compile(&unit, &object) {
+                            // There's no use in compiling the rest
+                            queue.lock().unwrap().clear();
+                            return Err(why);
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .filter_map(|it| it.join().unwrap().err())
+                .collect()
+        });
+
+        match errors.into_iter().next() {
+            Some(why) => Err(why),
+            None => Ok(objects),
+        }
+    }
+
+    // Where a unit's object file is cached.
+    fn object_of(&self, unit: &Path, prelude: &Path) -> Result<PathBuf, String> {
+        let read =
+            |path: &Path| fs::read(path).map_err(|why| format!("failed to read {path:?}: {why}"));
+
+        let mut hasher = Sha256::new();
+        for part in [env!("CARGO_PKG_VERSION").as_bytes(), self.cc.as_bytes()] {
+            hasher.update(part);
+            hasher.update(&[0]);
+        }
+        for flag in &self.cc_flags {
+            hasher.update(flag.as_bytes());
+            hasher.update(&[0]);
+        }
+        for code in [read(prelude)?, read(unit)?] {
+            hasher.update(&(code.len() as u64).to_be_bytes());
+            hasher.update(&code);
+        }
+        Ok(self.dir.join(format!("{}.o", hasher.finish())))
+    }
+
+    // A failed or interrupted compile shouldn't leave a broken object behind, so
+    // the object is only moved into place once it's done.
+    fn compile(&self, unit: &Path, object: &Path) -> Result<(), String> {
+        let partial = object.with_extension("o.partial");
+        super::run_object_cmds(
+            unit.to_path_buf(),
+            partial.clone(),
+            self.cc.clone(),
+            self.cc_flags.clone(),
+        )?;
+        fs::rename(&partial, object).map_err(|why| format!("failed to write {object:?}: {why}"))
+    }
+}
+
+// Whether a C compiler flag is only for linking, which includes files like
+// `shapes.c` or `libfoo.a` that are compiled and linked with the program. Other
+// arguments, like the directory after a separate `-I`, are kept.
+pub fn is_link_flag(flag: &str) -> bool {
+    is_link_input(flag)
+        || flag.starts_with("-l")
+        || flag.starts_with("-L")
+        || flag.starts_with("-Wl,")
+}
+
+fn is_link_input(flag: &str) -> bool {
+    !flag.starts_with('-')
+        && Path::new(flag)
+            .extension()
+            .is_some_and(|it| ["c", "o", "a", "so"].contains(&it.to_string_lossy().as_ref()))
+}
@@ -1,13 +1,19 @@
-use std::{fs::File, path::PathBuf, process::exit};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
 use crate::{
+    deps,
+    diagnostics::{Diagnostic, Diagnostics, Severity},
     hashtags::{DefTags, FunTags, RecTags, TagRecTags, TagTags},
-    parse::{ast::Node, parser::Parser},
+    parse::{ast::Node, lexer::Lexer, parser::Parser, token::TokenKind},
     util,
 };
 
 use super::{
     error::CompilerError,
+    generics::{Instance, Template},
     pragmas::Pragma,
     symbol::{Symbol, SymbolTable},
     type_::SeaType,
@@ -15,14 +21,22 @@ use super::{
 
 pub struct Compiler<'a> {
     pub output_path: PathBuf,
-    pub output_file: File,
+    pub output_file: PathBuf, // created by the backend, after analysis succeeds
     pub libpaths: Vec<PathBuf>,
     pub scope: usize,
     pub symbols: SymbolTable,
     pub parser: Parser<'a>,
     pub usages: Vec<PathBuf>,
+    pub imports: HashMap<PathBuf, Vec<(PathBuf, Node)>>, // analyzed files for each `use`d module
+    pub externs: HashMap<String, String>, // C names of `#extern` symbols, which aren't namespaced
+    pub impls: HashSet<(String, String)>, // the trait and rec of each `impl`
+    pub templates: HashMap<String, Template>, // the declaration of each generic
+    pub instances: HashMap<String, Instance>, // each generic's instances, by their name
+    pub instantiating: Vec<String>,       // notes for the instances being analyzed, innermost last
+    pub preconditions: HashMap<String, (Vec<String>, Vec<Node>)>, // parameter names and `@arg` contracts of each function
     pub file_stack: Vec<PathBuf>,
     pub cc_flags: Vec<String>,
+    pub diagnostics: Diagnostics,
 }
 
 impl<'a> Compiler<'a> {
@@ -33,74 +47,108 @@ impl<'a> Compiler<'a> {
         parser: Parser<'a>,
     ) -> Self {
         let p = parser.lexer.file.clone();
+        let diagnostics = parser.lexer.diagnostics.clone();
+        let mut symbols = SymbolTable::new();
+        symbols.set_file(p.clone());
         Compiler {
             output_path,
-            output_file: File::create(output_file).unwrap(),
+            output_file,
             libpaths,
             scope: 0,
-            symbols: SymbolTable::new(),
+            symbols,
             parser,
             usages: vec![],
+            imports: HashMap::new(),
+            externs: HashMap::new(),
+            impls: HashSet::new(),
+            templates: HashMap::new(),
+            instances: HashMap::new(),
+            instantiating: vec![],
+            preconditions: HashMap::new(),
             file_stack: vec![p],
             cc_flags: vec![],
+            diagnostics,
         }
     }
 
-    pub fn throw(&self, error: CompilerError, help: Option<&str>, node: Node) -> ! {
-        let source_file = self.file_stack.last().unwrap().to_str().unwrap();
+    // Reports an error at the given node in the file currently being compiled.
+    pub fn throw(&self, error: CompilerError, help: Option<&str>, node: Node) {
+        self.report(Severity::Error, error, help, node);
+    }
 
-        println!(
-            "\x1b[31;1m{source_file}:{}:{}:\x1b[0;1m {error}\x1b[0m",
-            node.line, node.column
-        );
+    pub fn report(&self, severity: Severity, error: CompilerError, help: Option<&str>, node: Node) {
+        let source_file = self.file_stack.last().unwrap();
 
-        let lines = util::get_lines_from_file(&source_file, node.line);
-        if lines.len() == 0 {
-            println!("No line information available :(");
-            println!("This error shouldn't happen, please report it.");
-            println!("Debug: source_file={source_file}, node={}", node.clone())
+        // The main file might not exist on disk (i.e, in the sandbox), so we
+        // use the parser's copy of its source code.
+        let lines = if *source_file == self.parser.lexer.file {
+            self.parser.lexer.get_lines(node.line)
         } else {
-            // Determine the longest integer by digit so that we can make our error prettier.
-            let longest_length = lines.iter().map(|it| it.0).max().unwrap().to_string().len();
-
-            for (line_index, line_str) in lines {
-                // We replace `\t` with ` ` so that no matter the terminal indentation, the underline will be aligned
-                let indents = line_str.chars().filter(|it| *it == '\t').count();
-                let sanitized = line_str.replace('\t', "    ");
-
-                println!("\x1b[1;34m{line_index:>longest_length$} | \x1b[0m{sanitized}");
-                if line_index == node.line {
-                    // Determine the column that the node is on to highlight it
-                    println!(
-                        "\x1b[1;34m{} | {}\x1b[31m^\x1b[0m",
-                        " ".repeat(longest_length),
-                        " ".repeat(node.column - 1 - indents + (indents * 4)),
-                    );
-                }
-            }
-        }
+            util::get_lines_from_file(source_file.to_str().unwrap(), node.line)
+        };
+
+        self.diagnostics.report(Diagnostic {
+            severity,
+            error: error.into(),
+            help: help.map(|it| it.to_string()),
+            // Errors in a generic's body are reported in the generic, so we
+            // mention where it was instantiated
+            note: self.instantiating.last().cloned(),
+            file: source_file.clone(),
+            line: node.line,
+            column: node.column,
+            len: Compiler::token_len(&lines, &node),
+            lines,
+        });
+    }
 
-        if let Some(help) = help {
-            println!("\x1b[1;32mhelp:\x1b[0m {}", help);
+    // Nodes only remember the column just past their first token, so the token
+    // is found by lexing its line again.
+    fn token_len(lines: &[(usize, String)], node: &Node) -> usize {
+        let Some((_, line)) = lines.iter().find(|(it, _)| *it == node.line) else {
+            return 1;
+        };
+        let mut lexer = Lexer::new(PathBuf::new(), line, Diagnostics::new());
+        loop {
+            let token = lexer.next_token();
+            if token.kind == TokenKind::Eof || token.column > node.column {
+                return 1;
+            }
+            if token.column == node.column {
+                return token.len.max(1);
+            }
         }
-
-        exit(1)
     }
 
     pub fn push_scope(&mut self) {
+        self.symbols.push_scope();
         self.scope += 1;
     }
 
     pub fn pop_scope(&mut self) {
-        self.symbols.remove_symbols_from_scopes(self.scope);
+        self.symbols.pop_scope();
         self.scope -= 1;
     }
 
+    // Starts compiling another file, i.e, one from a `use`d module.
+    pub fn enter_file(&mut self, path: PathBuf) {
+        self.symbols.set_file(path.clone());
+        self.file_stack.push(path);
+    }
+
+    pub fn leave_file(&mut self) {
+        self.file_stack.pop();
+        if let Some(it) = self.file_stack.last() {
+            self.symbols.set_file(it.clone());
+        }
+    }
+
     pub fn uses(&self, path: &PathBuf) -> bool {
         self.usages.contains(path)
     }
 
-    // Get the paths to each file in a given module
+    // Get the paths to each file in a given module, the project's dependencies
+    // are searched after the libpaths
     pub fn get_use_paths(&mut self, path: PathBuf) -> Result<Vec<PathBuf>, String> {
         if self.uses(&path) {
             return Ok(vec![]);
@@ -108,7 +156,8 @@ impl<'a> Compiler<'a> {
 
         let mut paths: Vec<PathBuf> = vec![];
 
-        for libpath in &self.libpaths {
+        let deps = Path::new(deps::DIR).to_path_buf();
+        for libpath in self.libpaths.iter().chain([&deps]) {
             let p = libpath.join(&path);
 
             if !p.exists() || p.is_file() {
@@ -143,27 +192,56 @@ impl<'a> Compiler<'a> {
         Err(format!("no such module: {path:?}"))
     }
 
+    // Remembers that `name` was declared in C, where it's called by its name
+    // without any packages.
+    pub fn add_extern(&mut self, name: &str) {
+        let c_name = name.rsplit('\'').next().unwrap_or(name);
+        self.externs.insert(name.to_string(), c_name.to_string());
+    }
+
+    // These `add_` functions return false if the symbol was already declared in
+    // the same scope. `#static` functions are only visible in their own file.
     pub fn add_fun(
         &mut self,
         name: String,
         tags: Vec<FunTags>,
         params: Vec<SeaType>,
         rets: SeaType,
-    ) {
+    ) -> bool {
+        if tags.contains(&FunTags::Static) {
+            return self
+                .symbols
+                .add_static_symbol(name, Symbol::Fun { tags, params, rets });
+        }
         self.symbols
-            .add_symbol(name, Symbol::Fun { tags, params, rets });
+            .add_symbol(name, Symbol::Fun { tags, params, rets })
+    }
+
+    pub fn add_rec(
+        &mut self,
+        name: String,
+        tags: Vec<RecTags>,
+        fields: Vec<(String, SeaType)>,
+    ) -> bool {
+        self.symbols.add_symbol(name, Symbol::Rec { tags, fields })
     }
 
-    pub fn add_rec(&mut self, name: String, tags: Vec<RecTags>, fields: Vec<(String, SeaType)>) {
-        self.symbols.add_symbol(name, Symbol::Rec { tags, fields });
+    pub fn add_def(&mut self, name: String, tags: Vec<DefTags>, typ: SeaType) -> bool {
+        self.symbols.add_symbol(name, Symbol::Def { tags, typ })
     }
 
-    pub fn add_def(&mut self, name: String, tags: Vec<DefTags>, typ: SeaType) {
-        self.symbols.add_symbol(name, Symbol::Def { tags, typ });
+    pub fn add_tag(&mut self, name: String, tags: Vec<TagTags>, entries: Vec<String>) -> bool {
+        self.symbols.add_symbol(name, Symbol::Tag { tags, entries })
     }
 
-    pub fn add_tag(&mut self, name: String, tags: Vec<TagTags>, entries: Vec<String>) {
-        self.symbols.add_symbol(name, Symbol::Tag { tags, entries });
+    // The name of the rec with the fields of a `tag rec`'s entry, which is also
+    // its name in C, i.e, `_ds$Shape_Circle` for `ds'Shape`'s `Circle`.
+    pub fn tag_rec_entry(&self, tag_rec: &str, entry: &str) -> String {
+        let c_name = match self.instances.get(tag_rec) {
+            Some(it) => it.c_name.clone(),
+            None => tag_rec.replace('\'', "$"),
+        };
+        format!("_{c_name}_{entry}")
     }
 
     pub fn add_tag_rec(
@@ -171,14 +249,33 @@ impl<'a> Compiler<'a> {
         name: String,
         tags: Vec<TagRecTags>,
         entries: Vec<(String, Vec<(String, SeaType)>)>,
-    ) {
+    ) -> bool {
         self.symbols
-            .add_symbol(name, Symbol::TagRec { tags, entries });
+            .add_symbol(name, Symbol::TagRec { tags, entries })
+    }
+
+    pub fn add_trait(
+        &mut self,
+        name: String,
+        methods: Vec<(String, Vec<SeaType>, SeaType)>,
+    ) -> bool {
+        self.symbols.add_symbol(name, Symbol::Trait { methods })
+    }
+
+    pub fn add_generic(&mut self, name: String, params: Vec<String>, symbol: Symbol) -> bool {
+        self.symbols.add_symbol(
+            name,
+            Symbol::Generic {
+                params,
+                symbol: Box::new(symbol),
+            },
+        )
     }
 
-    pub fn add_var(&mut self, name: String, typ: SeaType, mutable: bool) {
+    // Adds a variable to the current scope.
+    pub fn add_var(&mut self, name: String, typ: SeaType, mutable: bool) -> bool {
         self.symbols
-            .add_scoped_symbol(name, self.scope, Symbol::Var { typ, mutable });
+            .add_scoped_symbol(name, self.scope, Symbol::Var { typ, mutable })
     }
 
     fn format_pragma_string(&self, s: String) -> String {
@@ -195,7 +292,10 @@ impl<'a> Compiler<'a> {
     }
 
     pub fn handle_pragma(&mut self, node: Node) {
-        let pragma = Pragma::from_node(&node).unwrap_or_else(|it| self.throw(it, None, node));
+        let pragma = match Pragma::from_node(&node) {
+            Ok(it) => it,
+            Err(it) => return self.throw(it, None, node),
+        };
         match pragma {
             Pragma::AddCCFlag(it) => self.cc_flags.push(self.format_pragma_string(it)),
             Pragma::AddLibrary(it) => self
@@ -0,0 +1,140 @@
+// Contracts are written between a function's signature and its body:
+//
+// @arg(n > 0)      - checked when the function is called, and at compile time
+//                    for calls whose arguments are constants
+// @ret(ret != nil) - checked when the function returns, `ret` is the value
+// @mut(count)      - the globals that the function writes to, which is checked
+//                    at compile time
+//
+// `@arg` and `@ret` become runtime checks, unless the program is built with
+// `--prod`.
+
+use std::collections::HashMap;
+
+use crate::parse::{
+    ast::{ContractKind, Node, NodeKind},
+    operator::OperatorKind,
+};
+
+// The value of a constant expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl Value {
+    fn as_float(self) -> Option<f64> {
+        match self {
+            Value::Int(it) => Some(it as f64),
+            Value::Float(it) => Some(it),
+            Value::Bool(_) => None,
+        }
+    }
+}
+
+// The conditions of the contracts of the given kind, along with their text.
+pub fn conditions(contracts: &[Node], of: ContractKind) -> Vec<(Node, String)> {
+    contracts
+        .iter()
+        .filter_map(|it| match &it.node {
+            NodeKind::Contract { kind, params, text } if *kind == of => {
+                Some((params[0].clone(), text.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Evaluates the condition when every parameter that it uses is given a
+// constant argument. Returns None if it can't be known until runtime.
+pub fn evaluate(node: &Node, args: &HashMap<String, Node>) -> Option<Value> {
+    match &node.node {
+        NodeKind::ExprNumber(number) => number_value(number),
+        NodeKind::ExprTrue => Some(Value::Bool(true)),
+        NodeKind::ExprFalse => Some(Value::Bool(false)),
+        // Arguments can't refer to the parameters, so they're evaluated alone
+        NodeKind::ExprIdentifier(id) => evaluate(args.get(id)?, &HashMap::new()),
+        NodeKind::ExprGroup(expr) => evaluate(expr, args),
+        NodeKind::ExprUnaryOperator { kind, value } => match (kind, evaluate(value, args)?) {
+            (OperatorKind::Negate, Value::Int(it)) => it.checked_neg().map(Value::Int),
+            (OperatorKind::Negate, Value::Float(it)) => Some(Value::Float(-it)),
+            (OperatorKind::Not, Value::Bool(it)) => Some(Value::Bool(!it)),
+            _ => None,
+        },
+        NodeKind::ExprBinaryOperator { kind, left, right } => {
+            binary(*kind, evaluate(left, args)?, evaluate(right, args)?)
+        }
+        _ => None,
+    }
+}
+
+fn number_value(number: &str) -> Option<Value> {
+    if let Some(hex) = number.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok().map(Value::Int)
+    } else if let Some(binary) = number.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok().map(Value::Int)
+    } else if number.contains('.') {
+        number.parse::<f64>().ok().map(Value::Float)
+    } else {
+        number.parse::<i64>().ok().map(Value::Int)
+    }
+}
+
+fn binary(kind: OperatorKind, left: Value, right: Value) -> Option<Value> {
+    if let (Value::Bool(left), Value::Bool(right)) = (left, right) {
+        return match kind {
+            OperatorKind::And => Some(Value::Bool(left && right)),
+            OperatorKind::Or => Some(Value::Bool(left || right)),
+            OperatorKind::Eq => Some(Value::Bool(left == right)),
+            OperatorKind::Neq => Some(Value::Bool(left != right)),
+            _ => None,
+        };
+    }
+
+    // Integers stay integers, so that division truncates like in C
+    if let (Value::Int(left), Value::Int(right)) = (left, right) {
+        let value = match kind {
+            OperatorKind::Add => left.checked_add(right).map(Value::Int),
+            OperatorKind::Sub => left.checked_sub(right).map(Value::Int),
+            OperatorKind::Mul => left.checked_mul(right).map(Value::Int),
+            OperatorKind::Div => left.checked_div(right).map(Value::Int),
+            OperatorKind::Mod => left.checked_rem(right).map(Value::Int),
+            _ => None,
+        };
+        if value.is_some() {
+            return value;
+        }
+    }
+
+    let (left, right) = (left.as_float()?, right.as_float()?);
+    match kind {
+        OperatorKind::Add => Some(Value::Float(left + right)),
+        OperatorKind::Sub => Some(Value::Float(left - right)),
+        OperatorKind::Mul => Some(Value::Float(left * right)),
+        OperatorKind::Div if right != 0.0 => Some(Value::Float(left / right)),
+        OperatorKind::Eq => Some(Value::Bool(left == right)),
+        OperatorKind::Neq => Some(Value::Bool(left != right)),
+        OperatorKind::Gt => Some(Value::Bool(left > right)),
+        OperatorKind::GtEq => Some(Value::Bool(left >= right)),
+        OperatorKind::Lt => Some(Value::Bool(left < right)),
+        OperatorKind::LtEq => Some(Value::Bool(left <= right)),
+        _ => None,
+    }
+}
+
+// The variable that an assignment writes to, i.e, `world` in `world.tiles[i] = 0`.
+// Writes through pointers change what they point to, not the variable.
+pub fn written_var(target: &Node) -> Option<String> {
+    match &target.node {
+        NodeKind::ExprIdentifier(id) => Some(id.clone()),
+        NodeKind::ExprGroup(expr) => written_var(expr),
+        NodeKind::ExprBinaryOperator {
+            kind: OperatorKind::Dot | OperatorKind::Index,
+            left,
+            ..
+        } => written_var(left),
+        _ => None,
+    }
+}
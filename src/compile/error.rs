@@ -1,8 +1,9 @@
+use strum::IntoStaticStr;
 use thiserror::Error;
 
 use super::symbol::Symbol;
 
-#[derive(Debug, Clone, Error)]
+#[derive(Debug, Clone, Error, IntoStaticStr)]
 pub enum CompilerError {
     #[error("undefined or unbound symbol: `{0}`")]
     UnknownSymbol(String),
@@ -30,4 +31,76 @@ pub enum CompilerError {
 
     #[error("type inference error: {0}")]
     InferenceError(String),
+
+    #[error("`{0}` takes {1} argument(s) but got {2}")]
+    WrongArgumentCount(String, usize, usize),
+
+    #[error("mismatched types: expected `{0}` but got `{1}`")]
+    MismatchedTypes(String, String),
+
+    #[error("not all paths in `{0}` return a value")]
+    MissingReturn(String),
+
+    #[error("unreachable code")]
+    UnreachableCode,
+
+    #[error("`{0}` is not implicitly converted to `{1}`")]
+    ImplicitConversion(String, String),
+
+    #[error("`{0}` is already declared in this scope")]
+    DuplicateSymbol(String),
+
+    #[error("`{0}` is not a trait")]
+    NotATrait(String),
+
+    #[error("traits can only be implemented for `rec`s and `tag rec`s, `{0}` isn't one")]
+    NotImplementable(String),
+
+    #[error("the first parameter of `{0}` must be `^Self`")]
+    TraitReceiver(String),
+
+    #[error("`{1}` doesn't implement `{0}`")]
+    NotImplemented(String, String),
+
+    #[error("`{0}` is already implemented for `{1}`")]
+    DuplicateImpl(String, String),
+
+    #[error("`impl {0} for {1}` is missing `{2}`")]
+    MissingTraitMethod(String, String, String),
+
+    #[error("`{1}` is not a method of `{0}`")]
+    UnknownTraitMethod(String, String),
+
+    #[error("`{0}` doesn't match the trait, expected `{1}`")]
+    TraitMethodMismatch(String, String),
+
+    #[error("`{0}` can't have a method named `{1}`, since `{0}'{1}` is generated for the trait")]
+    GeneratedTraitMethod(String, String),
+
+    #[error("`{0}` doesn't take type arguments")]
+    NotGeneric(String),
+
+    #[error("`{0}` needs type arguments, i.e, `{1}`")]
+    MissingTypeArguments(String, String),
+
+    #[error("`{0}` takes {1} type argument(s) but got {2}")]
+    WrongTypeArgumentCount(String, usize, usize),
+
+    #[error("cannot infer `{1}` for `{0}`")]
+    CannotInferTypeArgument(String, String),
+
+    #[error("this call to `{0}` violates `{1}`")]
+    ContractViolation(String, String),
+
+    #[error("`{0}` is a global, so writing to it needs `@mut({0})`")]
+    UndeclaredMutation(String),
+
+    #[error("`@mut` only takes global variables")]
+    NotAGlobal,
+
+    #[error("`{0}` returns `void`, so it can't have `@ret`")]
+    RetContractOnVoid(String),
+
+    #[error("`{0}` can't be iterated with `for of`")]
+    NotIterable(String),
 }
@@ -0,0 +1,197 @@
+// Control flow analysis, used to find functions that don't always return and
+// code that can never run.
+
+use crate::{
+    diagnostics::Severity,
+    hashtags::FunTags,
+    parse::ast::{Node, NodeKind},
+};
+
+use super::{compiler::Compiler, error::CompilerError, symbol::Symbol};
+
+// How control leaves a statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    // Control can reach the end of the statement.
+    Continues,
+    // Control never reaches the end of the statement (`ret`, `break`, `continue`, or a `#noret` call).
+    Stops,
+    // We can't tell, i.e, raw C code which might `return`.
+    Unknown,
+}
+
+impl Flow {
+    // The flow of a statement that takes one of two paths.
+    fn join(self, other: Flow) -> Flow {
+        match (self, other) {
+            (Flow::Stops, Flow::Stops) => Flow::Stops,
+            (Flow::Continues, _) | (_, Flow::Continues) => Flow::Continues,
+            _ => Flow::Unknown,
+        }
+    }
+}
+
+// Returns how control leaves the node. Statements that can never run get
+// reported as warnings along the way.
+pub fn flow_of_node(compiler: &Compiler, node: &Node) -> Flow {
+    match &node.node {
+        NodeKind::StatRet(_) | NodeKind::StatBreak | NodeKind::StatContinue => Flow::Stops,
+        NodeKind::Raw(_) | NodeKind::Error => Flow::Unknown,
+        NodeKind::StatExpr(expr) => flow_of_node(compiler, expr),
+        NodeKind::ExprBlock(nodes) => flow_of_block(compiler, nodes),
+        NodeKind::ExprInvoke { left, params: _ } => {
+            if is_noret(compiler, left) {
+                Flow::Stops
+            } else {
+                Flow::Continues
+            }
+        }
+        NodeKind::StatIf {
+            cond: _,
+            expr,
+            else_,
+        } => {
+            let then = flow_of_node(compiler, expr);
+            match else_ {
+                Some(else_) => then.join(flow_of_node(compiler, else_)),
+                None => Flow::Continues,
+            }
+        }
+        NodeKind::StatSwitch { switch: _, cases } => {
+            let mut has_default = false;
+            let mut flow = Flow::Stops;
+            for (index, (case, fall, expr)) in cases.iter().enumerate() {
+                has_default |= case.is_none();
+                let case_flow = flow_of_node(compiler, expr);
+
+                // `break` leaves the switch, not the loop it's in
+                if contains_break(expr) {
+                    flow = Flow::Continues;
+                }
+
+                // Fall cases continue into the next case
+                if *fall && case_flow == Flow::Continues && index + 1 < cases.len() {
+                    continue;
+                }
+                flow = flow.join(case_flow);
+            }
+
+            if has_default {
+                flow
+            } else {
+                Flow::Continues
+            }
+        }
+        NodeKind::StatForCStyle {
+            def: _,
+            cond: _,
+            inc: _,
+            expr,
+        }
+        | NodeKind::StatForRange {
+            var: _,
+            from: _,
+            to: _,
+            expr,
+        }
+        | NodeKind::StatForOf {
+            index: _,
+            var: _,
+            iter: _,
+            expr,
+        } => {
+            flow_of_node(compiler, expr);
+            Flow::Continues
+        }
+        NodeKind::StatForSingleExpr { cond, expr } => {
+            flow_of_node(compiler, expr);
+            // `for true { ... }` only ends if we break out of it
+            if matches!(cond.node, NodeKind::ExprTrue) && !contains_break(expr) {
+                Flow::Stops
+            } else {
+                Flow::Continues
+            }
+        }
+        _ => Flow::Continues,
+    }
+}
+
+fn flow_of_block(compiler: &Compiler, nodes: &[Node]) -> Flow {
+    let mut flow = Flow::Continues;
+    let mut stopped_by: Option<&Node> = None;
+    for node in nodes {
+        if let Some(stopped_by) = stopped_by {
+            compiler.report(
+                Severity::Warning,
+                CompilerError::UnreachableCode,
+                Some(&format!(
+                    "this statement comes after {}",
+                    describe_stop(stopped_by)
+                )),
+                node.clone(),
+            );
+            break;
+        }
+
+        match flow_of_node(compiler, node) {
+            Flow::Continues => {}
+            Flow::Stops => {
+                flow = Flow::Stops;
+                stopped_by = Some(node);
+            }
+            Flow::Unknown => flow = Flow::Unknown,
+        }
+    }
+    flow
+}
+
+// Names the statement that control never gets past, for the help text.
+fn describe_stop(node: &Node) -> String {
+    match &node.node {
+        NodeKind::StatRet(_) => "a `ret`".to_string(),
+        NodeKind::StatBreak => "a `break`".to_string(),
+        NodeKind::StatContinue => "a `continue`".to_string(),
+        NodeKind::StatExpr(expr) => describe_stop(expr),
+        NodeKind::ExprInvoke { left, params: _ } => match &left.node {
+            NodeKind::ExprIdentifier(id) => format!("a call to `{id}`, which never returns"),
+            _ => "a call that never returns".to_string(),
+        },
+        NodeKind::StatForSingleExpr { .. } => {
+            "a `for true` loop that never finishes, since nothing `break`s out of it".to_string()
+        }
+        NodeKind::StatIf { .. } => "an `if` where every branch stops".to_string(),
+        NodeKind::StatSwitch { .. } => "a `switch` where every case stops".to_string(),
+        NodeKind::ExprBlock(_) => "a block that never finishes".to_string(),
+        _ => "a statement that never finishes".to_string(),
+    }
+}
+
+fn is_noret(compiler: &Compiler, left: &Node) -> bool {
+    match &left.node {
+        NodeKind::ExprIdentifier(id) => matches!(
+            compiler.symbols.get_symbol(id.clone()),
+            Some(Symbol::Fun {
+                tags,
+                params: _,
+                rets: _,
+            }) if tags.contains(&FunTags::NoRet)
+        ),
+        _ => false,
+    }
+}
+
+// Whether the node has a `break` that would leave the enclosing loop or switch.
+fn contains_break(node: &Node) -> bool {
+    match &node.node {
+        NodeKind::StatBreak => true,
+        NodeKind::StatExpr(expr) => contains_break(expr),
+        NodeKind::ExprBlock(nodes) => nodes.iter().any(contains_break),
+        NodeKind::StatIf {
+            cond: _,
+            expr,
+            else_,
+        } => contains_break(expr) || else_.as_ref().is_some_and(|it| contains_break(it)),
+        // Loops and switches have their own breaks
+        _ => false,
+    }
+}
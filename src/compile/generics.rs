@@ -0,0 +1,311 @@
+// Generics are templates: a `fun`, `rec`, `def`, or `tag rec` with type
+// parameters isn't analyzed or written by itself. Instead, each distinct set of
+// type arguments that it's used with gets a copy of it where the parameters are
+// replaced by the arguments. That copy is an instance, which is analyzed and
+// written like any other declaration. For a `rec List[T]` in `pkg ds`:
+//
+// ds'List[i32]     - the name of the instance for `i32`, as it's written in Sea
+// ds$List$_i32_    - its name in C, using `SeaType::mangle` for each argument
+
+use std::path::PathBuf;
+
+use crate::parse::ast::{Node, NodeKind};
+
+use super::type_::SeaType;
+
+// A generic declaration, along with the file that declared it.
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub params: Vec<String>,
+    pub node: Node,
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub generic: String,
+    pub args: Vec<SeaType>,
+    pub c_name: String,
+}
+
+pub fn instance_name(generic: &str, args: &[SeaType]) -> String {
+    let args = args
+        .iter()
+        .map(|it| it.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("{generic}[{args}]")
+}
+
+// The name that a generic declares, i.e, `List` for `rec List[T](...)`.
+pub fn declared_id(node: &Node) -> Option<String> {
+    match &node.node {
+        NodeKind::TopFun { id, .. }
+        | NodeKind::TopRec { id, .. }
+        | NodeKind::TopDef { id, .. }
+        | NodeKind::TopTagRec { id, .. } => Some(id.clone()),
+        _ => None,
+    }
+}
+
+// Applies `f` to every node in the tree, children first. Types are included,
+// as are the type arguments inside of them.
+pub fn transform(node: Node, f: &mut dyn FnMut(Node) -> Node) -> Node {
+    let Node { line, column, node } = node;
+    let node = match node {
+        NodeKind::Program(nodes) => NodeKind::Program(transform_all(nodes, f)),
+        NodeKind::Raw(_)
+        | NodeKind::Error
+        | NodeKind::TopUse(_)
+        | NodeKind::StatContinue
+        | NodeKind::StatBreak
+        | NodeKind::ExprNumber(_)
+        | NodeKind::ExprString(_)
+        | NodeKind::ExprCString(_)
+        | NodeKind::ExprChar(_)
+        | NodeKind::ExprTrue
+        | NodeKind::ExprFalse
+        | NodeKind::ExprIdentifier(_) => node,
+        NodeKind::Contract { kind, params, text } => NodeKind::Contract {
+            kind,
+            params: transform_all(params, f),
+            text,
+        },
+        NodeKind::Type {
+            pointers,
+            name,
+            arrays,
+            generics,
+            funptr_args,
+            funptr_rets,
+        } => NodeKind::Type {
+            pointers,
+            name,
+            arrays,
+            generics: transform_all(generics, f),
+            funptr_args: funptr_args.map(|it| transform_all(it, f)),
+            funptr_rets: funptr_rets.map(|it| transform_boxed(*it, f)),
+        },
+        NodeKind::TopPkg { name, statements } => NodeKind::TopPkg {
+            name,
+            statements: transform_all(statements, f),
+        },
+        NodeKind::TopFun {
+            tags,
+            id,
+            params,
+            rets,
+            contracts,
+            expr,
+        } => NodeKind::TopFun {
+            tags,
+            id,
+            params: transform_fields(params, f),
+            rets: transform_boxed(*rets, f),
+            contracts: transform_all(contracts, f),
+            expr: transform_boxed(*expr, f),
+        },
+        NodeKind::TopRec { tags, id, fields } => NodeKind::TopRec {
+            tags,
+            id,
+            fields: transform_fields(fields, f),
+        },
+        NodeKind::TopDef { tags, id, typ } => NodeKind::TopDef {
+            tags,
+            id,
+            typ: transform_boxed(*typ, f),
+        },
+        NodeKind::TopTag { tags, id, entries } => NodeKind::TopTag {
+            tags,
+            id,
+            entries: entries
+                .into_iter()
+                .map(|(entry, value)| (entry, value.map(|it| transform_boxed(*it, f))))
+                .collect(),
+        },
+        NodeKind::TopTagRec { tags, id, entries } => NodeKind::TopTagRec {
+            tags,
+            id,
+            entries: entries
+                .into_iter()
+                .map(|(entry, fields)| (entry, transform_fields(fields, f)))
+                .collect(),
+        },
+        NodeKind::TopPragma { id, params } => NodeKind::TopPragma {
+            id,
+            params: transform_all(params, f),
+        },
+        NodeKind::TopTrait { id, methods } => NodeKind::TopTrait {
+            id,
+            methods: methods
+                .into_iter()
+                .map(|(method, params, rets)| {
+                    (method, transform_fields(params, f), transform(rets, f))
+                })
+                .collect(),
+        },
+        NodeKind::TopImpl { trait_, rec, funs } => NodeKind::TopImpl {
+            trait_,
+            rec,
+            funs: transform_all(funs, f),
+        },
+        // Generics are transformed when they're instantiated
+        NodeKind::TopGeneric { .. } => node,
+        NodeKind::TopInstance(node) => NodeKind::TopInstance(transform_boxed(*node, f)),
+        NodeKind::StatRet(value) => NodeKind::StatRet(value.map(|it| transform_boxed(*it, f))),
+        NodeKind::StatIf { cond, expr, else_ } => NodeKind::StatIf {
+            cond: transform_boxed(*cond, f),
+            expr: transform_boxed(*expr, f),
+            else_: else_.map(|it| transform_boxed(*it, f)),
+        },
+        NodeKind::StatSwitch { switch, cases } => NodeKind::StatSwitch {
+            switch: transform_boxed(*switch, f),
+            cases: cases
+                .into_iter()
+                .map(|(case, fall, expr)| {
+                    (
+                        case.map(|it| transform_boxed(*it, f)),
+                        fall,
+                        transform_boxed(*expr, f),
+                    )
+                })
+                .collect(),
+        },
+        NodeKind::StatForCStyle {
+            def,
+            cond,
+            inc,
+            expr,
+        } => NodeKind::StatForCStyle {
+            def: transform_boxed(*def, f),
+            cond: transform_boxed(*cond, f),
+            inc: transform_boxed(*inc, f),
+            expr: transform_boxed(*expr, f),
+        },
+        NodeKind::StatForSingleExpr { cond, expr } => NodeKind::StatForSingleExpr {
+            cond: transform_boxed(*cond, f),
+            expr: transform_boxed(*expr, f),
+        },
+        NodeKind::StatForRange {
+            var,
+            from,
+            to,
+            expr,
+        } => NodeKind::StatForRange {
+            var,
+            from: transform_boxed(*from, f),
+            to: transform_boxed(*to, f),
+            expr: transform_boxed(*expr, f),
+        },
+        NodeKind::StatForOf {
+            index,
+            var,
+            iter,
+            expr,
+        } => NodeKind::StatForOf {
+            index,
+            var,
+            iter: transform_boxed(*iter, f),
+            expr: transform_boxed(*expr, f),
+        },
+        NodeKind::StatDefer(expr) => NodeKind::StatDefer(transform_boxed(*expr, f)),
+        NodeKind::StatExpr(expr) => NodeKind::StatExpr(transform_boxed(*expr, f)),
+        NodeKind::ExprGroup(expr) => NodeKind::ExprGroup(transform_boxed(*expr, f)),
+        NodeKind::ExprBlock(nodes) => NodeKind::ExprBlock(transform_all(nodes, f)),
+        NodeKind::ExprNew {
+            id,
+            generics,
+            params,
+        } => NodeKind::ExprNew {
+            id,
+            generics: transform_all(generics, f),
+            params: transform_all(params, f),
+        },
+        NodeKind::ExprUnaryOperator { kind, value } => NodeKind::ExprUnaryOperator {
+            kind,
+            value: transform_boxed(*value, f),
+        },
+        NodeKind::ExprBinaryOperator { kind, left, right } => NodeKind::ExprBinaryOperator {
+            kind,
+            left: transform_boxed(*left, f),
+            right: transform_boxed(*right, f),
+        },
+        NodeKind::ExprInvoke { left, params } => NodeKind::ExprInvoke {
+            left: transform_boxed(*left, f),
+            params: transform_all(params, f),
+        },
+        NodeKind::ExprList(nodes) => NodeKind::ExprList(transform_all(nodes, f)),
+        NodeKind::ExprVar { name, typ, value } => NodeKind::ExprVar {
+            name,
+            typ: typ.map(|it| transform_boxed(*it, f)),
+            value: transform_boxed(*value, f),
+        },
+        NodeKind::ExprLet { name, typ, value } => NodeKind::ExprLet {
+            name,
+            typ: typ.map(|it| transform_boxed(*it, f)),
+            value: transform_boxed(*value, f),
+        },
+    };
+    f(Node { line, column, node })
+}
+
+fn transform_all(nodes: Vec<Node>, f: &mut dyn FnMut(Node) -> Node) -> Vec<Node> {
+    nodes.into_iter().map(|it| transform(it, f)).collect()
+}
+
+fn transform_boxed(node: Node, f: &mut dyn FnMut(Node) -> Node) -> Box<Node> {
+    Box::new(transform(node, f))
+}
+
+fn transform_fields(
+    fields: Vec<(String, Node)>,
+    f: &mut dyn FnMut(Node) -> Node,
+) -> Vec<(String, Node)> {
+    fields
+        .into_iter()
+        .map(|(name, typ)| (name, transform(typ, f)))
+        .collect()
+}
+
+// Replaces each type parameter in the node with its argument. Parameters used
+// as values, i.e, in `sizeof(T)`, become types too.
+pub fn substitute(node: Node, params: &[String], args: &[SeaType]) -> Node {
+    let arg_of = |name: &str| {
+        params
+            .iter()
+            .position(|it| it == name)
+            .map(|it| args[it].clone())
+    };
+    transform(node, &mut |node| match &node.node {
+        NodeKind::Type {
+            pointers,
+            name,
+            arrays,
+            generics,
+            funptr_rets: None,
+            ..
+        } if generics.is_empty() => match arg_of(name) {
+            // `^T[4]` with `T = ^i32` is `^^i32[4]`
+            Some(arg) => {
+                let mut typ = SeaType {
+                    pointers: arg.pointers + pointers,
+                    ..arg
+                };
+                typ.arrays.extend(arrays.iter().cloned());
+                Node {
+                    node: typ.to_node().node,
+                    ..node
+                }
+            }
+            None => node,
+        },
+        NodeKind::ExprIdentifier(name) => match arg_of(name) {
+            Some(arg) => Node {
+                node: arg.to_node().node,
+                ..node
+            },
+            None => node,
+        },
+        _ => node,
+    })
+}
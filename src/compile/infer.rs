@@ -35,13 +35,17 @@ pub fn infer_type_of_node(compiler: &Compiler, node: &Node) -> Result<SeaType, S
             _ => return Err(format!("symbol undefined or unbound: {id}")),
         },
         NodeKind::ExprBlock(_) => return Err("cannot infer type for block expressions".to_string()),
-        NodeKind::ExprNew {
-            id,
-            params: _params,
-        } => SeaType::named_type(&id),
+        NodeKind::ExprNew { id, .. } => SeaType::named_type(&id),
         NodeKind::ExprUnaryOperator { kind, value } => match *kind {
+            OperatorKind::Not => SeaType::BOOL.clone(),
             OperatorKind::Ref => infer_type_of_node(compiler, value)?.pointer(),
-            OperatorKind::Deref => infer_type_of_node(compiler, value)?.unpointer(),
+            OperatorKind::Deref => {
+                let typ = infer_type_of_node(compiler, value)?;
+                if typ.pointers == 0 {
+                    return Err("cannot dereference a non-pointer type".to_string());
+                }
+                typ.unpointer()
+            }
             _ => infer_type_of_node(compiler, value)?,
         },
         NodeKind::ExprBinaryOperator { kind, left, right } => match kind {
@@ -49,7 +53,11 @@ pub fn infer_type_of_node(compiler: &Compiler, node: &Node) -> Result<SeaType, S
                 // The right side of a dot operator will always be an identifier
                 let id = match &right.node {
                     NodeKind::ExprIdentifier(id) => id,
-                    _ => unreachable!(),
+                    _ => {
+                        return Err(
+                            "right side of dot (`.`) operator must be an identifier".to_string()
+                        )
+                    }
                 };
 
                 let typ = infer_type_of_node(compiler, left)?;
@@ -73,7 +81,7 @@ pub fn infer_type_of_node(compiler: &Compiler, node: &Node) -> Result<SeaType, S
                         entries: _,
                     } => SeaType {
                         pointers: 0,
-                        name: format!("_{name}_{id}"),
+                        name: compiler.tag_rec_entry(&name, id),
                         arrays: vec![],
                         funptr_args: None,
                         funptr_rets: None,
@@ -87,6 +95,29 @@ pub fn infer_type_of_node(compiler: &Compiler, node: &Node) -> Result<SeaType, S
             }
             OperatorKind::As => SeaType::from_node(right.as_ref().clone()).unwrap(),
             OperatorKind::Assign => infer_type_of_node(compiler, right)?,
+            OperatorKind::Eq
+            | OperatorKind::Neq
+            | OperatorKind::Gt
+            | OperatorKind::GtEq
+            | OperatorKind::Lt
+            | OperatorKind::LtEq
+            | OperatorKind::And
+            | OperatorKind::Or => SeaType::BOOL.clone(),
+            OperatorKind::Add
+            | OperatorKind::Sub
+            | OperatorKind::Mul
+            | OperatorKind::Div
+            | OperatorKind::Mod => infer_arithmetic_type(compiler, left, right)?,
+            OperatorKind::Index => {
+                let mut typ = infer_type_of_node(compiler, left)?;
+                if typ.arrays.pop().is_none() {
+                    if typ.pointers == 0 {
+                        return Err("cannot index a non-array type".to_string());
+                    }
+                    typ.pointers -= 1;
+                }
+                typ
+            }
             _ => infer_type_of_node(compiler, left)?,
         },
         NodeKind::ExprInvoke { left, params: _ } => match &left.node {
@@ -117,7 +148,7 @@ pub fn infer_type_of_node(compiler: &Compiler, node: &Node) -> Result<SeaType, S
                     _ => return Err(format!("no such function: {id}")),
                 }
             }
-            _ => todo!(),
+            _ => return Err("cannot infer type for invocations of non-identifiers".to_string()),
         },
         NodeKind::ExprList(nodes) => {
             if nodes.len() == 0 {
@@ -139,3 +170,50 @@ pub fn infer_type_of_node(compiler: &Compiler, node: &Node) -> Result<SeaType, S
         _ => return Err(format!("cannot infer type for node {node}")),
     })
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberLiteral {
+    Integer,
+    Float,
+}
+
+// Whether the node is a number literal, i.e, `5` or `-(2.5)`. Literals can take
+// on the type of whatever they're used with.
+pub fn number_literal(node: &Node) -> Option<NumberLiteral> {
+    match &node.node {
+        NodeKind::ExprNumber(value) if value.contains('.') => Some(NumberLiteral::Float),
+        NodeKind::ExprNumber(_) => Some(NumberLiteral::Integer),
+        NodeKind::ExprGroup(value)
+        | NodeKind::ExprUnaryOperator {
+            kind: OperatorKind::Negate,
+            value,
+        } => number_literal(value),
+        _ => None,
+    }
+}
+
+// The type of an arithmetic expression. Literals take on the type of the other
+// side, otherwise the result is whichever side the other widens to.
+fn infer_arithmetic_type(
+    compiler: &Compiler,
+    left: &Node,
+    right: &Node,
+) -> Result<SeaType, String> {
+    let left_type = infer_type_of_node(compiler, left)?;
+    let right_type = match infer_type_of_node(compiler, right) {
+        Ok(it) => it,
+        Err(_) => return Ok(left_type),
+    };
+
+    let use_right = match (number_literal(left), number_literal(right)) {
+        (Some(NumberLiteral::Integer), Some(NumberLiteral::Float)) => true,
+        (Some(_), None) => right_type.is_numeric(),
+        (None, None) => match (left_type.number_kind(), right_type.number_kind()) {
+            (Some(l), Some(r)) => l != r && l.converts_implicitly_to(r),
+            _ => false,
+        },
+        _ => false,
+    };
+
+    Ok(if use_right { right_type } else { left_type })
+}
@@ -1,10 +1,16 @@
 use std::{io::ErrorKind, path::PathBuf, process::Command};
 
+pub mod analysis;
+pub mod cache;
 pub mod compiler;
+pub mod contracts;
 pub mod error;
+pub mod flow;
+pub mod generics;
 pub mod infer;
 pub mod pragmas;
 pub mod symbol;
+pub mod traits;
 pub mod type_;
 
 pub fn run_compile_cmds(
@@ -12,18 +18,56 @@ pub fn run_compile_cmds(
     executable_path: PathBuf,
     cc: String,
     cc_flags: Vec<String>,
+) -> Result<(), String> {
+    run_link_cmds(vec![c_source_path], executable_path, cc, cc_flags)
+}
+
+// Links object files (or compiles and links C files) into an executable.
+pub fn run_link_cmds(
+    inputs: Vec<PathBuf>,
+    executable_path: PathBuf,
+    cc: String,
+    cc_flags: Vec<String>,
 ) -> Result<(), String> {
     let mut compile_cmd = Command::new(&cc);
 
+    compile_cmd.arg("-g3");
+    compile_cmd.arg("-o");
+    compile_cmd.arg(executable_path.to_str().unwrap());
+    // Libraries have to come after the code that uses them, or linkers skip them
+    for input in &inputs {
+        compile_cmd.arg(input.to_str().unwrap());
+    }
     cc_flags.iter().for_each(|it| {
         compile_cmd.arg(it);
     });
+
+    run_cc(compile_cmd, &cc)
+}
+
+// Compiles one C file to an object file, without linking it.
+pub fn run_object_cmds(
+    c_source_path: PathBuf,
+    object_path: PathBuf,
+    cc: String,
+    cc_flags: Vec<String>,
+) -> Result<(), String> {
+    let mut compile_cmd = Command::new(&cc);
+
     compile_cmd.arg("-g3");
+    compile_cmd.arg("-c");
     compile_cmd.arg("-o");
-    compile_cmd.arg(executable_path.to_str().unwrap());
+    compile_cmd.arg(object_path.to_str().unwrap());
     compile_cmd.arg(c_source_path.to_str().unwrap());
+    cc_flags.iter().for_each(|it| {
+        compile_cmd.arg(it);
+    });
+
+    run_cc(compile_cmd, &cc)
+}
 
-    println!(
+fn run_cc(mut compile_cmd: Command, cc: &str) -> Result<(), String> {
+    eprintln!(
         "\x1b[35m: Compiling C: \x1b[1;35m{} {}\x1b[0m",
         cc,
         compile_cmd
@@ -38,7 +82,7 @@ pub fn run_compile_cmds(
             let res = child.wait().expect("failed to wait for child");
             if !res.success() {
                 let code = res.code().unwrap_or(-1);
-                println!("\x1b[31m: Process exited with code: {code}\x1b[0m",);
+                eprintln!("\x1b[31m: Process exited with code: {code}\x1b[0m",);
                 Err(format!("process exited with code: {code}").to_string())
             } else {
                 Ok(())
@@ -52,7 +96,7 @@ pub fn run_compile_cmds(
 }
 
 pub fn run_executable(path: PathBuf, args: Vec<String>) -> Result<(), String> {
-    println!(
+    eprintln!(
         "\x1b[35m: Executing: \x1b[1;35m{}\x1b[0m",
         path.to_str().unwrap()
     );
@@ -64,7 +108,7 @@ pub fn run_executable(path: PathBuf, args: Vec<String>) -> Result<(), String> {
         Ok(mut child) => {
             let res = child.wait().expect("failed to wait for child");
             if !res.success() {
-                println!(
+                eprintln!(
                     "\x1b[31m: Process exited with code: {}\x1b[0m",
                     res.code().unwrap_or(-1)
                 );
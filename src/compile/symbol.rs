@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    path::PathBuf,
+};
 
 use crate::hashtags::{DefTags, FunTags, RecTags, TagRecTags, TagTags};
 
@@ -31,6 +34,17 @@ pub enum Symbol {
         typ: SeaType,
         mutable: bool,
     },
+    // Each method is its name, parameter types, and return type, where the
+    // first parameter is `^Self`.
+    Trait {
+        methods: Vec<(String, Vec<SeaType>, SeaType)>,
+    },
+    // A declaration with type parameters, where `symbol` uses the parameters as
+    // types. Its instances are declared as symbols of their own.
+    Generic {
+        params: Vec<String>,
+        symbol: Box<Symbol>,
+    },
 }
 
 impl Symbol {
@@ -45,6 +59,60 @@ impl Symbol {
         }
     }
 
+    // Describes the symbol the way it would be declared in Sea, i.e,
+    // `fun add(i32, i32): i32`.
+    pub fn signature(&self, name: &str) -> String {
+        let join = |items: Vec<String>| items.join(", ");
+        let fields = |fields: &Vec<(String, SeaType)>| {
+            join(
+                fields
+                    .iter()
+                    .map(|(field, typ)| format!("{field}: {typ}"))
+                    .collect(),
+            )
+        };
+
+        match self {
+            Symbol::Fun {
+                tags: _,
+                params,
+                rets,
+            } => format!(
+                "fun {name}({}): {rets}",
+                join(params.iter().map(|it| it.to_string()).collect())
+            ),
+            Symbol::Rec { tags: _, fields: f } => format!("rec {name}({})", fields(f)),
+            Symbol::Def { tags: _, typ } => format!("def {name} = {typ}"),
+            Symbol::Tag { tags: _, entries } => format!("tag {name}({})", entries.join(", ")),
+            Symbol::TagRec { tags: _, entries } => format!(
+                "tag rec {name}({})",
+                join(
+                    entries
+                        .iter()
+                        .map(|(entry, f)| format!("{entry}({})", fields(f)))
+                        .collect()
+                )
+            ),
+            Symbol::Var { typ, mutable } => {
+                format!("{} {name}: {typ}", if *mutable { "var" } else { "let" })
+            }
+            Symbol::Trait { methods } => format!(
+                "trait {name} {{ {} }}",
+                methods
+                    .iter()
+                    .map(|(method, params, rets)| format!(
+                        "fun {method}({}): {rets}",
+                        join(params.iter().map(|it| it.to_string()).collect())
+                    ))
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            ),
+            Symbol::Generic { params, symbol } => {
+                symbol.signature(&format!("{name}[{}]", params.join(", ")))
+            }
+        }
+    }
+
     pub fn invocable(&self) -> bool {
         match self {
             Symbol::Fun {
@@ -58,39 +126,223 @@ impl Symbol {
     }
 }
 
+// A position in a source file, using the line and column of a node.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Default)]
+struct Scope {
+    symbols: HashMap<String, Symbol>,
+    definitions: HashMap<String, Location>, // where each symbol was declared
+}
+
 pub struct SymbolTable {
-    symbols: HashMap<String, (usize, Symbol)>,
+    scopes: Vec<Scope>, // the global scope comes first, the innermost scope last
+    statics: HashMap<PathBuf, Scope>, // `#static` symbols, which are only visible in their file
+    static_indices: HashMap<(PathBuf, String), usize>, // how many files declared a static before
+    file: PathBuf,      // the file being compiled, whose statics are visible
+    types: HashMap<(PathBuf, usize, usize), SeaType>, // the type of each expression, keyed by its location
+    references: HashMap<Location, Location>, // the definition that each identifier refers to
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
         SymbolTable {
-            symbols: HashMap::new(),
+            scopes: vec![Scope::default()],
+            statics: HashMap::new(),
+            static_indices: HashMap::new(),
+            file: PathBuf::new(),
+            types: HashMap::new(),
+            references: HashMap::new(),
         }
     }
 
-    pub fn add_symbol(&mut self, symbol: String, kind: Symbol) {
-        self.add_scoped_symbol(symbol, 0, kind);
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Scope::default());
     }
 
-    pub fn add_scoped_symbol(&mut self, symbol: String, scope: usize, kind: Symbol) {
-        self.symbols.insert(symbol, (scope, kind));
+    // Removes the innermost scope along with its symbols, which brings back any
+    // symbols that they shadowed. The global scope is never removed.
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
     }
 
-    pub fn remove_symbol(&mut self, symbol: String) {
-        self.symbols.remove(&symbol);
+    // Sets the file being compiled, which decides the statics that are visible.
+    pub fn set_file(&mut self, file: PathBuf) {
+        self.file = file;
+    }
+
+    // The scopes that names are looked up in, innermost first: local scopes,
+    // then the current file's statics, then the global scope.
+    fn visible(&self) -> impl Iterator<Item = &Scope> {
+        self.scopes[1..]
+            .iter()
+            .rev()
+            .chain(self.statics.get(&self.file))
+            .chain(self.scopes.first())
+    }
+
+    fn visible_mut(&mut self) -> impl Iterator<Item = &mut Scope> {
+        let (global, locals) = self.scopes.split_at_mut(1);
+        locals
+            .iter_mut()
+            .rev()
+            .chain(self.statics.get_mut(&self.file))
+            .chain(global.iter_mut())
+    }
+
+    // Adds a symbol to the global scope. Returns false if the scope already has
+    // a symbol with the same name, in which case the existing one is kept.
+    pub fn add_symbol(&mut self, symbol: String, kind: Symbol) -> bool {
+        self.add_scoped_symbol(symbol, 0, kind)
+    }
+
+    // Adds a symbol to the given scope, where `0` is the global scope. Returns
+    // false if the scope already has a symbol with the same name, in which case
+    // the existing one is kept. Globals can't share a name with any static,
+    // since every module's declarations are visible to C.
+    pub fn add_scoped_symbol(&mut self, symbol: String, scope: usize, kind: Symbol) -> bool {
+        if scope == 0
+            && self
+                .statics
+                .values()
+                .any(|it| it.symbols.contains_key(&symbol))
+        {
+            return false;
+        }
+        match self.scopes[scope].symbols.entry(symbol) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(kind);
+                true
+            }
+        }
     }
 
-    // Removes all symbols from scopes deeper or in the provided `scope` index.
-    pub fn remove_symbols_from_scopes(&mut self, scope: usize) {
-        let _ = self.symbols.iter_mut().filter(|(_, (s, _))| *s < scope);
+    // Adds a `#static` symbol to the current file. Returns false if the file or
+    // the global scope already has a symbol with the same name. Other files can
+    // have statics with the same name.
+    pub fn add_static_symbol(&mut self, symbol: String, kind: Symbol) -> bool {
+        if self.scopes[0].symbols.contains_key(&symbol) {
+            return false;
+        }
+        let index = self
+            .statics
+            .values()
+            .filter(|it| it.symbols.contains_key(&symbol))
+            .count();
+        match self
+            .statics
+            .entry(self.file.clone())
+            .or_default()
+            .symbols
+            .entry(symbol.clone())
+        {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(kind);
+                if index > 0 {
+                    self.static_indices
+                        .insert((self.file.clone(), symbol), index);
+                }
+                true
+            }
+        }
     }
 
+    // Gets how many other files declared a static with the same name before
+    // the current file did, or 0 if the name doesn't refer to a static. Backends
+    // use this to give statics unique names when the files are combined.
+    pub fn static_index(&self, symbol: &str) -> usize {
+        if self.scopes[1..]
+            .iter()
+            .any(|it| it.symbols.contains_key(symbol))
+        {
+            return 0;
+        }
+        self.static_indices
+            .get(&(self.file.clone(), symbol.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // Removes the innermost symbol with the given name.
+    pub fn remove_symbol(&mut self, symbol: String) {
+        if let Some(scope) = self
+            .visible_mut()
+            .find(|it| it.symbols.contains_key(&symbol))
+        {
+            scope.symbols.remove(&symbol);
+            scope.definitions.remove(&symbol);
+        }
+    }
+
+    // Looks up a symbol, starting from the innermost scope.
     pub fn get_symbol(&self, symbol: String) -> Option<&Symbol> {
-        if self.symbols.contains_key(&symbol) {
-            Some(&self.symbols[&symbol].1)
-        } else {
-            None
+        self.visible().find_map(|it| it.symbols.get(&symbol))
+    }
+
+    // Whether the innermost symbol with the given name is a variable that was
+    // declared outside of any function.
+    pub fn is_global_var(&self, symbol: &str) -> bool {
+        !self.scopes[1..]
+            .iter()
+            .any(|it| it.symbols.contains_key(symbol))
+            && matches!(
+                self.get_symbol(symbol.to_string()),
+                Some(Symbol::Var { .. })
+            )
+    }
+
+    // Every symbol in the global scope, and the current file's statics.
+    pub fn globals(&self) -> impl Iterator<Item = (&String, &Symbol)> {
+        self.scopes[0].symbols.iter().chain(
+            self.statics
+                .get(&self.file)
+                .into_iter()
+                .flat_map(|it| it.symbols.iter()),
+        )
+    }
+
+    // Remembers where the innermost symbol with the given name was declared.
+    pub fn set_definition(&mut self, symbol: String, location: Location) {
+        if let Some(scope) = self
+            .visible_mut()
+            .find(|it| it.symbols.contains_key(&symbol))
+        {
+            scope.definitions.insert(symbol, location);
         }
     }
+
+    // Gets where the innermost symbol with the given name was declared.
+    pub fn get_definition(&self, symbol: &str) -> Option<&Location> {
+        self.visible()
+            .find(|it| it.symbols.contains_key(symbol))
+            .and_then(|it| it.definitions.get(symbol))
+    }
+
+    pub fn set_reference(&mut self, at: Location, definition: Location) {
+        self.references.insert(at, definition);
+    }
+
+    // Gets the definition of the identifier at the given location.
+    pub fn get_reference(&self, at: &Location) -> Option<&Location> {
+        self.references.get(at)
+    }
+
+    // Expressions like `a + b` share their location with their leftmost child,
+    // so the first (innermost) expression to be given a type keeps it.
+    pub fn set_type_of(&mut self, file: PathBuf, line: usize, column: usize, typ: SeaType) {
+        self.types.entry((file, line, column)).or_insert(typ);
+    }
+
+    pub fn get_type_of(&self, file: PathBuf, line: usize, column: usize) -> Option<&SeaType> {
+        self.types.get(&(file, line, column))
+    }
 }
@@ -0,0 +1,51 @@
+// Names of what analysis declares for traits and their impls. For a trait
+// `Shape` with a method `area`, implemented for `Circle`:
+//
+// Shape'vtable         - a rec with a function pointer for each method
+// Shape'dyn            - the `dyn Shape` fat pointer, `rec(self: ^void, vtable: ^Shape'vtable)`
+// Shape'area           - calls `area` through a `dyn Shape`'s vtable
+// Circle'Shape'area    - the implementation of `area` for `Circle`
+// Circle'Shape'vtable  - the vtable of `Circle`'s implementation
+
+use super::type_::SeaType;
+
+pub const SELF: &str = "Self";
+
+pub fn vtable_name(trait_: &str) -> String {
+    format!("{trait_}'vtable")
+}
+
+pub fn dyn_name(trait_: &str) -> String {
+    format!("{trait_}'dyn")
+}
+
+pub fn method_name(trait_: &str, method: &str) -> String {
+    format!("{trait_}'{method}")
+}
+
+pub fn impl_method_name(trait_: &str, rec: &str, method: &str) -> String {
+    format!("{rec}'{trait_}'{method}")
+}
+
+pub fn impl_vtable_name(trait_: &str, rec: &str) -> String {
+    format!("{rec}'{trait_}'vtable")
+}
+
+// Whether a method's name would be the same as a generated one, i.e, `Shape'vtable`.
+pub fn is_generated(trait_: &str, method: &str) -> bool {
+    let name = method_name(trait_, method);
+    name == vtable_name(trait_) || name == dyn_name(trait_)
+}
+
+// The trait of a `dyn` type's name, i.e, `Shape` for `Shape'dyn`.
+pub fn dyn_trait(name: &str) -> Option<&str> {
+    name.strip_suffix("'dyn")
+}
+
+// The trait of a `dyn` value's type, which isn't a pointer or array.
+pub fn dyn_trait_of(typ: &SeaType) -> Option<&str> {
+    if typ.pointers > 0 || !typ.arrays.is_empty() || typ.funptr_rets.is_some() {
+        return None;
+    }
+    dyn_trait(&typ.name)
+}
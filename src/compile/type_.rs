@@ -1,7 +1,38 @@
-use std::sync::LazyLock;
+use std::{fmt, sync::LazyLock};
 
 use crate::parse::ast::{Node, NodeKind};
 
+use super::traits;
+
+// Numeric types, with their size in bits where it matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberKind {
+    Signed(u8),
+    Unsigned(u8),
+    Float(u8),
+    Bool,
+    Char,
+}
+
+impl NumberKind {
+    // Whether a value of this kind can be converted to `other` without an
+    // explicit cast. Only conversions that can't lose information are allowed.
+    pub fn converts_implicitly_to(self, other: NumberKind) -> bool {
+        match (self, other) {
+            (NumberKind::Signed(from), NumberKind::Signed(to))
+            | (NumberKind::Unsigned(from), NumberKind::Unsigned(to))
+            | (NumberKind::Float(from), NumberKind::Float(to)) => from <= to,
+            (NumberKind::Unsigned(from), NumberKind::Signed(to)) => from < to,
+            (NumberKind::Char, NumberKind::Signed(to)) => to > 8,
+            (from, to) => from == to,
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        matches!(self, NumberKind::Signed(_) | NumberKind::Unsigned(_))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SeaType {
     pub pointers: u8,
@@ -18,6 +49,7 @@ impl SeaType {
                 pointers,
                 name,
                 arrays,
+                generics,
                 funptr_args,
                 funptr_rets,
             } => {
@@ -34,6 +66,18 @@ impl SeaType {
                 let seatype_funptr_rets =
                     funptr_rets.map(|it| Box::new(SeaType::from_node(*it).unwrap()));
 
+                // `List[i32]`, which is also the name of the instance
+                let name = if generics.is_empty() {
+                    name
+                } else {
+                    let args = generics
+                        .into_iter()
+                        .map(|it| SeaType::from_node(it).unwrap().to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    format!("{name}[{args}]")
+                };
+
                 Some(SeaType {
                     pointers,
                     name,
@@ -46,6 +90,20 @@ impl SeaType {
         }
     }
 
+    pub fn to_node(&self) -> Node {
+        Node::of_kind(NodeKind::Type {
+            pointers: self.pointers,
+            name: self.name.clone(),
+            arrays: self.arrays.clone(),
+            generics: vec![],
+            funptr_args: self
+                .funptr_args
+                .as_ref()
+                .map(|it| it.iter().map(|arg| arg.to_node()).collect()),
+            funptr_rets: self.funptr_rets.as_ref().map(|it| Box::new(it.to_node())),
+        })
+    }
+
     pub fn named_type(name: &str) -> Self {
         SeaType {
             pointers: 0,
@@ -79,6 +137,34 @@ impl SeaType {
         }
     }
 
+    // The kind of number this type is, if it is one. Both Sea and C names are
+    // recognised since type aliases resolve to C types.
+    pub fn number_kind(&self) -> Option<NumberKind> {
+        if self.pointers > 0 || !self.arrays.is_empty() || self.funptr_rets.is_some() {
+            return None;
+        }
+
+        Some(match self.name.as_str() {
+            "i8" | "int8_t" => NumberKind::Signed(8),
+            "i16" | "int16_t" | "short" => NumberKind::Signed(16),
+            "i32" | "int32_t" | "int" => NumberKind::Signed(32),
+            "i64" | "int64_t" | "long" => NumberKind::Signed(64),
+            "u8" | "uint8_t" => NumberKind::Unsigned(8),
+            "u16" | "uint16_t" => NumberKind::Unsigned(16),
+            "u32" | "uint32_t" | "unsigned" => NumberKind::Unsigned(32),
+            "u64" | "uint64_t" | "usize" | "size_t" => NumberKind::Unsigned(64),
+            "f32" | "float" => NumberKind::Float(32),
+            "f64" | "double" => NumberKind::Float(64),
+            "bool" => NumberKind::Bool,
+            "char" => NumberKind::Char,
+            _ => return None,
+        })
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.number_kind().is_some()
+    }
+
     pub fn pointer(&self) -> Self {
         SeaType {
             pointers: self.pointers + 1,
@@ -130,3 +216,30 @@ impl SeaType {
     pub const C_STRING: LazyLock<SeaType> = LazyLock::new(|| SeaType::named_type("char").pointer());
     pub const STRING: LazyLock<SeaType> = LazyLock::new(|| SeaType::named_type("String"));
 }
+
+// Formats the type the way it would be written in Sea, i.e, `^char` or `fun(i32): bool`.
+impl fmt::Display for SeaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", "^".repeat(self.pointers.into()))?;
+        if let (Some(args), Some(rets)) = (&self.funptr_args, &self.funptr_rets) {
+            let args = args
+                .iter()
+                .map(|it| it.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            return write!(f, "fun({args}): {rets}");
+        }
+        match traits::dyn_trait(&self.name) {
+            Some(trait_) => write!(f, "dyn {trait_}")?,
+            None => write!(f, "{}", self.name)?,
+        }
+        for array in &self.arrays {
+            match array {
+                (Some(size), _) => write!(f, "[{size}]")?,
+                (_, Some(size)) => write!(f, "[{size}]")?,
+                _ => write!(f, "[]")?,
+            }
+        }
+        Ok(())
+    }
+}
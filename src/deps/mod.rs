@@ -0,0 +1,375 @@
+// Dependencies are Sea libraries declared in `build.reef` as `dep_<name> = "path"`,
+// where the path is a directory or a vendored archive (anything `tar` unpacks).
+// They're copied to `.sea/deps/<name>`, which the compiler searches for modules,
+// and `sea.lock` records a hash of each one's contents so that a dependency
+// can't change without anyone noticing:
+//
+// json_source = "vendor/json-1.2.tar.gz"
+// json_sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+//
+// Everything here is relative to the project's directory, which is the current
+// directory when building. Files and directories starting with `.` (like `.git`)
+// aren't copied or hashed.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    diagnostics::Diagnostics,
+    reef::{self, error::ReefError, ReefValue},
+};
+
+use sha256::Sha256;
+
+pub mod sha256;
+
+pub const DIR: &str = ".sea/deps";
+pub const LOCK_FILE: &str = "sea.lock";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    pub source: String, // as it's written in the manifest
+}
+
+impl Dependency {
+    pub fn new(name: String, source: String) -> Self {
+        Dependency { name, source }
+    }
+
+    // Where the dependency is resolved to.
+    pub fn dir(&self) -> PathBuf {
+        Path::new(DIR).join(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Unlocked,      // it isn't in `sea.lock`, or its source was changed in the manifest
+    Unresolved,    // it isn't in `.sea/deps` yet
+    Modified,      // the copy in `.sea/deps` doesn't match the lock
+    SourceChanged, // the source doesn't match the lock
+    SourceMissing,
+}
+
+impl Status {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Unlocked => "not locked",
+            Status::Unresolved => "not resolved",
+            Status::Modified => "modified in .sea/deps",
+            Status::SourceChanged => "source changed since it was locked",
+            Status::SourceMissing => "source is missing",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locked {
+    pub source: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Lock {
+    pub entries: BTreeMap<String, Locked>,
+}
+
+impl Lock {
+    // Reads `sea.lock`, which might not exist yet.
+    pub fn read(path: &Path, diagnostics: &Diagnostics) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(code) => Ok(Lock::parse(path, &code, diagnostics)),
+            Err(why) if why.kind() == io::ErrorKind::NotFound => Ok(Lock::default()),
+            Err(why) => Err(why),
+        }
+    }
+
+    pub fn parse(path: &Path, code: &str, diagnostics: &Diagnostics) -> Self {
+        let mut sources: BTreeMap<String, String> = BTreeMap::new();
+        let mut hashes: BTreeMap<String, String> = BTreeMap::new();
+
+        for entry in reef::parse(path, code, diagnostics) {
+            let key = (entry.line, entry.key_column, entry.key.len());
+            let (name, map) = if let Some(name) = entry.key.strip_suffix("_source") {
+                (name, &mut sources)
+            } else if let Some(name) = entry.key.strip_suffix("_sha256") {
+                (name, &mut hashes)
+            } else {
+                let help = Some("keys are `<name>_source` or `<name>_sha256`".to_string());
+                let error = ReefError::UnknownKey(entry.key.clone());
+                reef::report(diagnostics, path, code, error, help, key);
+                continue;
+            };
+
+            let ReefValue::String(value) = &entry.value else {
+                let error =
+                    ReefError::WrongType(entry.key.clone(), "string", entry.value.type_name());
+                let value = (entry.line, entry.column, entry.len);
+                reef::report(diagnostics, path, code, error, None, value);
+                continue;
+            };
+            map.insert(name.to_string(), value.clone());
+        }
+
+        // Entries without a hash are as good as unlocked
+        let entries = sources
+            .into_iter()
+            .filter_map(|(name, source)| {
+                let hash = hashes.remove(&name)?;
+                Some((name, Locked { source, hash }))
+            })
+            .collect();
+        Lock { entries }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut out =
+            String::from("# Written by `sea deps` and `sea build`, don't edit it by hand\n");
+        for (name, locked) in &self.entries {
+            let source = locked.source.replace('\\', "\\\\").replace('"', "\\\"");
+            out.push_str(&format!(
+                "\n{name}_source = \"{source}\"\n{name}_sha256 = \"{}\"\n",
+                locked.hash
+            ));
+        }
+        fs::write(path, out)
+    }
+
+    // The lock entry for a dependency, if it was locked from the same source.
+    pub fn get(&self, dep: &Dependency) -> Option<&Locked> {
+        self.entries
+            .get(&dep.name)
+            .filter(|it| it.source == dep.source)
+    }
+}
+
+pub struct Deps {
+    pub deps: Vec<Dependency>,
+    pub lock: Lock,
+}
+
+impl Deps {
+    pub fn new(deps: Vec<Dependency>, lock: Lock) -> Self {
+        Deps { deps, lock }
+    }
+
+    // Checks a dependency against the lock without changing anything.
+    pub fn status(&self, dep: &Dependency) -> io::Result<Status> {
+        let Some(locked) = self.lock.get(dep) else {
+            return Ok(Status::Unlocked);
+        };
+        if !Path::new(&dep.source).exists() {
+            return Ok(Status::SourceMissing);
+        }
+        if !dep.dir().is_dir() {
+            return Ok(Status::Unresolved);
+        }
+        if hash_dir(&dep.dir())? != locked.hash {
+            return Ok(Status::Modified);
+        }
+        if hash_source(dep)? != locked.hash {
+            return Ok(Status::SourceChanged);
+        }
+        Ok(Status::Ok)
+    }
+
+    // Makes sure that every dependency is in `.sea/deps`, resolving and locking
+    // new ones. Locked dependencies have to match their hashes. Returns the
+    // names of the dependencies that were resolved.
+    pub fn sync(&mut self) -> Result<Vec<String>, String> {
+        let mut resolved: Vec<String> = vec![];
+        for dep in &self.deps {
+            let Some(locked) = self.lock.get(dep) else {
+                let hash = resolve(dep)?;
+                self.lock.entries.insert(
+                    dep.name.clone(),
+                    Locked {
+                        source: dep.source.clone(),
+                        hash,
+                    },
+                );
+                resolved.push(dep.name.clone());
+                continue;
+            };
+
+            if dep.dir().is_dir() {
+                let hash = hash_dir(&dep.dir()).map_err(|why| failed(dep, why))?;
+                if hash != locked.hash {
+                    return Err(format!(
+                        "{:?} doesn't match {LOCK_FILE}, run `sea deps --refresh` to resolve `{}` again",
+                        dep.dir(),
+                        dep.name
+                    ));
+                }
+                continue;
+            }
+
+            let hash = resolve(dep)?;
+            if hash != locked.hash {
+                _ = fs::remove_dir_all(dep.dir());
+                return Err(format!(
+                    "`{}` changed since it was locked, run `sea deps --refresh` if that's expected",
+                    dep.name
+                ));
+            }
+            resolved.push(dep.name.clone());
+        }
+
+        self.forget_removed();
+        Ok(resolved)
+    }
+
+    // Resolves every dependency again and locks them with their new hashes.
+    pub fn refresh(&mut self) -> Result<(), String> {
+        for dep in &self.deps {
+            let hash = resolve(dep)?;
+            self.lock.entries.insert(
+                dep.name.clone(),
+                Locked {
+                    source: dep.source.clone(),
+                    hash,
+                },
+            );
+        }
+        self.forget_removed();
+        Ok(())
+    }
+
+    // Removes dependencies that aren't in the manifest anymore, so that they
+    // can't be `use`d by accident.
+    fn forget_removed(&mut self) {
+        let names: Vec<&String> = self.deps.iter().map(|it| &it.name).collect();
+        self.lock.entries.retain(|name, _| names.contains(&name));
+
+        let Ok(entries) = fs::read_dir(DIR) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !names.contains(&&name) && entry.path().is_dir() {
+                _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+}
+
+fn failed(dep: &Dependency, why: io::Error) -> String {
+    format!(
+        "failed to resolve `{}` from {:?}: {why}",
+        dep.name, dep.source
+    )
+}
+
+// Copies or unpacks a dependency into `.sea/deps`, returning its hash.
+fn resolve(dep: &Dependency) -> Result<String, String> {
+    unpack(Path::new(&dep.source), &dep.dir()).map_err(|why| failed(dep, why))?;
+    hash_dir(&dep.dir()).map_err(|why| failed(dep, why))
+}
+
+// Hashes the source itself, so archives are unpacked somewhere temporary.
+fn hash_source(dep: &Dependency) -> io::Result<String> {
+    let source = Path::new(&dep.source);
+    if source.is_dir() {
+        return hash_dir(source);
+    }
+
+    let dir = Path::new(DIR).join(format!(".{}.check", dep.name));
+    let hash = unpack(source, &dir).and_then(|_| hash_dir(&dir));
+    _ = fs::remove_dir_all(&dir);
+    hash
+}
+
+fn unpack(source: &Path, into: &Path) -> io::Result<()> {
+    if into.exists() {
+        fs::remove_dir_all(into)?;
+    }
+    fs::create_dir_all(into)?;
+    if source.is_dir() {
+        return copy_dir(source, into);
+    }
+
+    // `tar` changes to the directory before opening the archive
+    let archive = fs::canonicalize(source)?;
+    let output = Command::new("tar")
+        .arg("-xf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(into)
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!("tar failed: {}", stderr.trim())));
+    }
+
+    // Archives usually have everything in one directory, i.e, `json-1.2/`
+    let entries = visible_entries(into)?;
+    if let [only] = entries.as_slice() {
+        if only.is_dir() {
+            let name = into.file_name().unwrap_or_default().to_string_lossy();
+            let unpacked = into.with_file_name(format!(".{name}.unpacked"));
+            fs::rename(only, &unpacked)?;
+            fs::remove_dir_all(into)?;
+            fs::rename(&unpacked, into)?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for path in visible_entries(from)? {
+        let target = to.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+// The sorted paths in `dir`, without hidden ones.
+fn visible_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_name().to_string_lossy().starts_with('.') {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+// Hashes the paths and contents of every file in `dir`, in a stable order.
+pub fn hash_dir(dir: &Path) -> io::Result<String> {
+    fn walk(dir: &Path, prefix: &str, hasher: &mut Sha256) -> io::Result<()> {
+        for path in visible_entries(dir)? {
+            let name = format!(
+                "{prefix}{}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            );
+            if path.is_dir() {
+                walk(&path, &format!("{name}/"), hasher)?;
+                continue;
+            }
+            let contents = fs::read(&path)?;
+            hasher.update(name.as_bytes());
+            hasher.update(&[0]);
+            hasher.update(&(contents.len() as u64).to_be_bytes());
+            hasher.update(&contents);
+        }
+        Ok(())
+    }
+
+    let mut hasher = Sha256::new();
+    walk(dir, "", &mut hasher)?;
+    Ok(hasher.finish())
+}
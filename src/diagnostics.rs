@@ -0,0 +1,215 @@
+// Collects errors and warnings from the lexer, parser, compiler, and backends so
+// that every problem in a compilation can be reported at once.
+
+use std::{cell::RefCell, fmt, fmt::Write, path::PathBuf, rc::Rc};
+
+use serde_json::json;
+use strum::EnumString;
+
+use crate::error::SeaError;
+
+// How diagnostics get printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString)]
+pub enum ErrorFormat {
+    // Coloured, human-readable messages with source code excerpts.
+    #[default]
+    #[strum(serialize = "human")]
+    Human,
+    // One JSON object per line, for editors and CI.
+    #[strum(serialize = "json")]
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub error: SeaError,
+    pub help: Option<String>,
+    pub note: Option<String>, // more context, i.e, which generic instance the error is in
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub len: usize,
+    pub lines: Vec<(usize, String)>, // the source lines surrounding the diagnostic
+}
+
+impl Diagnostic {
+    fn colour(&self) -> &'static str {
+        match self.severity {
+            Severity::Error => "\x1b[31;1m",
+            Severity::Warning => "\x1b[33;1m",
+        }
+    }
+
+    fn render_human(&self) -> String {
+        let colour = self.colour();
+        let mut out = String::new();
+
+        _ = writeln!(
+            out,
+            "{colour}{}:{}:{}: {}:\x1b[0;1m {}\x1b[0m",
+            self.file.to_string_lossy(),
+            self.line,
+            self.column,
+            self.severity,
+            self.error
+        );
+
+        if self.lines.is_empty() {
+            _ = writeln!(out, "No line information available :(");
+            _ = writeln!(out, "This error shouldn't happen, please report it.");
+        } else {
+            // Determine the longest integer by digit so that we can make our error prettier.
+            let longest_length = self
+                .lines
+                .iter()
+                .map(|it| it.0)
+                .max()
+                .unwrap()
+                .to_string()
+                .len();
+
+            for (line_index, line_str) in &self.lines {
+                // We replace `\t` with ` ` so that no matter the terminal indentation, the underline will be aligned
+                let indents = line_str.chars().filter(|it| *it == '\t').count();
+                let sanitized = line_str.replace('\t', "    ");
+
+                _ = writeln!(
+                    out,
+                    "\x1b[1;34m{line_index:>longest_length$} | \x1b[0m{sanitized}"
+                );
+                if *line_index == self.line {
+                    // Columns point just past the end of the token, so the
+                    // highlight starts `len` columns before it
+                    _ = writeln!(
+                        out,
+                        "\x1b[1;34m{} | {}{colour}^{}\x1b[0m",
+                        " ".repeat(longest_length),
+                        " ".repeat(self.column.saturating_sub(self.len + 1) + indents * 3),
+                        "~".repeat(self.len.saturating_sub(1))
+                    );
+                }
+            }
+        }
+
+        if let Some(note) = &self.note {
+            _ = writeln!(out, "\x1b[1;36mnote:\x1b[0m {note}");
+        }
+        if let Some(help) = &self.help {
+            _ = writeln!(out, "\x1b[1;32mhelp:\x1b[0m {help}");
+        }
+
+        out
+    }
+
+    fn render_json(&self) -> String {
+        json!({
+            "file": self.file.to_string_lossy(),
+            "line": self.line,
+            "column": self.column,
+            "length": self.len,
+            "severity": self.severity.to_string(),
+            "kind": self.error.kind(),
+            "message": self.error.to_string(),
+            "help": self.help,
+            "note": self.note,
+        })
+        .to_string()
+            + "\n"
+    }
+
+    pub fn render(&self, format: ErrorFormat) -> String {
+        match format {
+            ErrorFormat::Human => self.render_human(),
+            ErrorFormat::Json => self.render_json(),
+        }
+    }
+}
+
+// A shared sink for diagnostics. Cloning a `Diagnostics` gives another handle to
+// the same list, so the lexer, parser, compiler, and backend all report into one
+// place.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn report(&self, diagnostic: Diagnostic) {
+        self.diagnostics.borrow_mut().push(diagnostic);
+    }
+
+    pub fn all(&self) -> Vec<Diagnostic> {
+        self.diagnostics.borrow().clone()
+    }
+
+    pub fn clear(&self) {
+        self.diagnostics.borrow_mut().clear();
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .borrow()
+            .iter()
+            .filter(|it| it.severity == severity)
+            .count()
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    // Prints every diagnostic. Human-readable output is followed by a summary
+    // of how many were reported.
+    pub fn print(&self, format: ErrorFormat) {
+        for diagnostic in self.diagnostics.borrow().iter() {
+            print!("{}", diagnostic.render(format));
+        }
+
+        if format != ErrorFormat::Human {
+            return;
+        }
+
+        let errors = self.error_count();
+        let warnings = self.warning_count();
+        if warnings > 0 {
+            println!(
+                "\x1b[1;33mwarning:\x1b[0m {warnings} warning{} emitted",
+                if warnings == 1 { "" } else { "s" }
+            );
+        }
+        if errors > 0 {
+            println!(
+                "\x1b[1;31merror:\x1b[0m aborting due to {errors} previous error{}",
+                if errors == 1 { "" } else { "s" }
+            );
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use strum::IntoStaticStr;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error, IntoStaticStr)]
+pub enum DocError {
+    #[error("doc comment links to an unknown symbol: `<{0}>`")]
+    BrokenLink(String),
+}
@@ -0,0 +1,188 @@
+// The markdown-style markup used in doc comments:
+//
+// `text`     - code
+// *text*     - italic
+// **text**   - bold
+// ***text*** - italic and bold
+// <text>     - link to a function, record, etc
+// <'text>    - link to a parameter or global variable
+//
+// Line breaks are kept as they are, since doc comments tend to line things up.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Span {
+    Text(String),
+    Code(String),
+    Emphasis(usize), // toggles italic (1), bold (2), or both (3)
+    Link(String),    // the text between `<` and `>`
+    Url(String),
+}
+
+// What a link points at, as decided by whoever is rendering the doc comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Item { page: String, anchor: String }, // a documented item, or a package with no anchor
+    Plain,                                 // something that exists, but isn't documented
+}
+
+fn is_link(text: &str) -> bool {
+    let name = text.strip_prefix('\'').unwrap_or(text);
+    name.starts_with(|it: char| it.is_alphabetic() || it == '_' || it == '$')
+        && name
+            .chars()
+            .all(|it| it.is_alphanumeric() || it == '_' || it == '$' || it == '\'')
+}
+
+pub fn parse(text: &str) -> Vec<Span> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans: Vec<Span> = vec![];
+    let mut buffer = String::new();
+    let mut index = 0;
+
+    // Finds the next `end` after `index`, returning the text in between
+    let until = |index: usize, end: char| -> Option<String> {
+        chars[index + 1..]
+            .iter()
+            .position(|it| *it == end)
+            .map(|it| chars[index + 1..index + 1 + it].iter().collect())
+    };
+
+    while index < chars.len() {
+        let ch = chars[index];
+        let span = match ch {
+            '`' => until(index, '`').map(|it| (it.chars().count() + 2, Span::Code(it))),
+            '<' => until(index, '>').and_then(|it| {
+                let len = it.chars().count() + 2;
+                if is_link(&it) {
+                    Some((len, Span::Link(it)))
+                } else if it.starts_with("http://") || it.starts_with("https://") {
+                    Some((len, Span::Url(it)))
+                } else {
+                    None
+                }
+            }),
+            '*' => {
+                let run = chars[index..].iter().take_while(|it| **it == '*').count();
+                let before = index.checked_sub(1).map(|it| chars[it]);
+                let after = chars.get(index + run).copied();
+                // A lone `*` between spaces is just an asterisk
+                let spaced = |it: Option<char>| it.is_none_or(|it| it.is_whitespace());
+                if run <= 3 && !(spaced(before) && spaced(after)) {
+                    Some((run, Span::Emphasis(run)))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        match span {
+            Some((len, span)) => {
+                if !buffer.is_empty() {
+                    spans.push(Span::Text(std::mem::take(&mut buffer)));
+                }
+                spans.push(span);
+                index += len;
+            }
+            None => {
+                buffer.push(ch);
+                index += 1;
+            }
+        }
+    }
+
+    if !buffer.is_empty() {
+        spans.push(Span::Text(buffer));
+    }
+    spans
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Links to a whole page don't have an anchor.
+fn fragment(anchor: &str) -> String {
+    if anchor.is_empty() {
+        String::new()
+    } else {
+        format!("#{anchor}")
+    }
+}
+
+pub fn to_html(spans: &[Span], resolve: &mut dyn FnMut(&str) -> Target) -> String {
+    let mut out = String::new();
+    let mut open: Vec<usize> = vec![]; // emphasis that hasn't been closed yet
+
+    let tags = |level: usize, closing: bool| -> String {
+        let slash = if closing { "/" } else { "" };
+        match level {
+            1 => format!("<{slash}em>"),
+            2 => format!("<{slash}strong>"),
+            _ if closing => "</em></strong>".to_string(),
+            _ => "<strong><em>".to_string(),
+        }
+    };
+
+    for span in spans {
+        match span {
+            Span::Text(it) => out.push_str(&escape_html(it)),
+            Span::Code(it) => out.push_str(&format!("<code>{}</code>", escape_html(it))),
+            Span::Emphasis(level) => {
+                if open.last() == Some(level) {
+                    open.pop();
+                    out.push_str(&tags(*level, true));
+                } else {
+                    open.push(*level);
+                    out.push_str(&tags(*level, false));
+                }
+            }
+            Span::Link(it) => {
+                let name = escape_html(it.strip_prefix('\'').unwrap_or(it));
+                match resolve(it) {
+                    Target::Item { page, anchor } => out.push_str(&format!(
+                        "<a href=\"{page}.html{}\"><code>{name}</code></a>",
+                        fragment(&escape_html(&anchor))
+                    )),
+                    Target::Plain => out.push_str(&format!("<code>{name}</code>")),
+                }
+            }
+            Span::Url(it) => {
+                let it = escape_html(it);
+                out.push_str(&format!("<a href=\"{it}\">{it}</a>"));
+            }
+        }
+    }
+
+    while let Some(level) = open.pop() {
+        out.push_str(&tags(level, true));
+    }
+    out
+}
+
+pub fn to_markdown(spans: &[Span], resolve: &mut dyn FnMut(&str) -> Target) -> String {
+    let mut out = String::new();
+
+    for span in spans {
+        match span {
+            Span::Text(it) => out.push_str(it),
+            Span::Code(it) => out.push_str(&format!("`{it}`")),
+            Span::Emphasis(level) => out.push_str(&"*".repeat(*level)),
+            Span::Link(it) => {
+                let name = it.strip_prefix('\'').unwrap_or(it);
+                match resolve(it) {
+                    Target::Item { page, anchor } => {
+                        out.push_str(&format!("[`{name}`]({page}.md{})", fragment(&anchor)))
+                    }
+                    Target::Plain => out.push_str(&format!("`{name}`")),
+                }
+            }
+            Span::Url(it) => out.push_str(&format!("<{it}>")),
+        }
+    }
+
+    out
+}
@@ -0,0 +1,594 @@
+// `sea doc`: turns the doc comments in a module into a static site, with one
+// HTML and one Markdown page per package. Links in doc comments are resolved
+// against the module's symbol table, and broken ones are reported as warnings.
+
+pub mod error;
+pub mod markup;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    compile::{analysis::Analyzer, compiler::Compiler, symbol::SymbolTable, type_::SeaType},
+    diagnostics::{Diagnostic, Diagnostics, Severity},
+    parse::{
+        ast::{Node, NodeKind},
+        lexer::{Docs, Lexer},
+        parser::Parser,
+    },
+    util,
+};
+
+use error::DocError;
+use markup::{escape_html as escape, Span, Target};
+
+// A documented function, record, tag, etc.
+pub struct Item {
+    pub name: String, // qualified with its packages, i.e, `list'make_list`
+    pub signature: String,
+    pub doc: String,
+    pub locals: Vec<String>, // parameters and fields, which `<'name>` can link to
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Item {
+    fn short_name(&self) -> &str {
+        self.name.rsplit('\'').next().unwrap_or_default()
+    }
+}
+
+#[derive(Default)]
+pub struct Package {
+    pub doc: Option<Item>, // the `pkg` statement, if it was documented
+    pub items: Vec<Item>,
+}
+
+pub struct DocGenerator {
+    pub private: bool, // whether to include `_names` and `_internal` packages
+    packages: BTreeMap<String, Package>, // keyed by the package's qualified name, `""` is the top level
+    diagnostics: Diagnostics,
+}
+
+impl DocGenerator {
+    pub fn new(diagnostics: Diagnostics) -> Self {
+        DocGenerator {
+            private: false,
+            packages: BTreeMap::from([(String::new(), Package::default())]),
+            diagnostics,
+        }
+    }
+
+    // #region: Collection
+
+    // Parses a file and collects its top level statements. Returns false if the
+    // file failed to parse.
+    pub fn add_file(&mut self, file: PathBuf, source: &String) -> bool {
+        let diagnostics = Diagnostics::new();
+        let mut parser = Parser::new(Lexer::new(file.clone(), source, diagnostics.clone()));
+        let program = parser.parse(false);
+
+        for it in diagnostics.all() {
+            self.diagnostics.report(it);
+        }
+        if diagnostics.has_errors() {
+            return false;
+        }
+
+        if let NodeKind::Program(statements) = program.node {
+            self.add_statements(&file, &parser.docs, "", statements);
+        }
+        true
+    }
+
+    fn add_statements(&mut self, file: &Path, docs: &Docs, namespace: &str, statements: Vec<Node>) {
+        let qualify = |id: &str| {
+            if namespace.is_empty() {
+                id.to_string()
+            } else {
+                format!("{namespace}'{id}")
+            }
+        };
+
+        for node in statements {
+            let doc = docs
+                .get(&(node.line, node.column))
+                .map(|it| clean(it))
+                .unwrap_or_default();
+            let item = |name: String, signature: String, locals: Vec<String>| Item {
+                name,
+                signature,
+                doc: doc.clone(),
+                locals,
+                file: file.to_path_buf(),
+                line: node.line,
+                column: node.column,
+            };
+
+            // Generics are documented as their declaration, with `[T]` after its name
+            let (kind, type_params) = match node.node.clone() {
+                NodeKind::TopGeneric { params, node } => {
+                    (node.node, format!("[{}]", params.join(", ")))
+                }
+                kind => (kind, String::new()),
+            };
+            let item = match kind {
+                NodeKind::TopPkg { name, statements } => {
+                    let name = qualify(&name);
+                    if !self.is_public(&name) {
+                        continue;
+                    }
+
+                    // `pkg a'b` declares `a` too, which gets a page to list `b` on
+                    let mut parent = name.as_str();
+                    while let Some((it, _)) = parent.rsplit_once('\'') {
+                        self.packages.entry(it.to_string()).or_default();
+                        parent = it;
+                    }
+
+                    let package = self.packages.entry(name.clone()).or_default();
+                    if package.doc.is_none() && !doc.is_empty() {
+                        package.doc = Some(item(name.clone(), format!("pkg {name}"), vec![]));
+                    }
+                    self.add_statements(file, docs, &name, statements);
+                    continue;
+                }
+                NodeKind::TopFun {
+                    id,
+                    params,
+                    rets,
+                    contracts,
+                    ..
+                } => {
+                    let name = qualify(&id);
+                    let params_text = params
+                        .iter()
+                        .map(|(param, typ)| format!("{param}: {}", type_text(typ)))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    // Contracts are part of the API, so they're shown with it
+                    let contracts_text: String = contracts
+                        .iter()
+                        .filter_map(|it| match &it.node {
+                            NodeKind::Contract { text, .. } => Some(format!(" {text}")),
+                            _ => None,
+                        })
+                        .collect();
+                    let signature = format!(
+                        "fun {name}{type_params}({params_text}): {}{contracts_text}",
+                        type_text(&rets)
+                    );
+                    item(name, signature, params.into_iter().map(|it| it.0).collect())
+                }
+                NodeKind::TopRec { id, fields, .. } => {
+                    let name = qualify(&id);
+                    let fields_text = fields
+                        .iter()
+                        .map(|(field, typ)| format!("{field}: {}", type_text(typ)))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let signature = format!("rec {name}{type_params}({fields_text})");
+                    item(name, signature, fields.into_iter().map(|it| it.0).collect())
+                }
+                NodeKind::TopDef { id, typ, .. } => {
+                    let name = qualify(&id);
+                    let signature = format!("def {name}{type_params} = {}", type_text(&typ));
+                    item(name, signature, vec![])
+                }
+                NodeKind::TopTag { id, entries, .. } => {
+                    let name = qualify(&id);
+                    let entries: Vec<String> = entries.into_iter().map(|it| it.0).collect();
+                    let signature = format!("tag {name}({})", entries.join(", "));
+                    item(name, signature, entries)
+                }
+                NodeKind::TopTagRec { id, entries, .. } => {
+                    let name = qualify(&id);
+                    let entries_text = entries
+                        .iter()
+                        .map(|(entry, fields)| {
+                            let fields = fields
+                                .iter()
+                                .map(|(field, typ)| format!("{field}: {}", type_text(typ)))
+                                .collect::<Vec<String>>()
+                                .join(", ");
+                            format!("{entry}({fields})")
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let signature = format!("tag rec {name}{type_params}({entries_text})");
+                    item(
+                        name,
+                        signature,
+                        entries.into_iter().map(|it| it.0).collect(),
+                    )
+                }
+                NodeKind::TopTrait { id, methods } => {
+                    let name = qualify(&id);
+                    let methods_text = methods
+                        .iter()
+                        .map(|(method, params, rets)| {
+                            let params = params
+                                .iter()
+                                .map(|(param, typ)| format!("{param}: {}", type_text(typ)))
+                                .collect::<Vec<String>>()
+                                .join(", ");
+                            format!("fun {method}({params}): {}", type_text(rets))
+                        })
+                        .collect::<Vec<String>>()
+                        .join("; ");
+                    let signature = format!("trait {name} {{ {methods_text} }}");
+                    item(
+                        name,
+                        signature,
+                        methods.into_iter().map(|it| it.0).collect(),
+                    )
+                }
+                _ => continue,
+            };
+
+            if self.is_public(&item.name) {
+                self.packages
+                    .entry(namespace.to_string())
+                    .or_default()
+                    .items
+                    .push(item);
+            }
+        }
+    }
+
+    // Names starting with `_` are private, along with anything in their packages.
+    fn is_public(&self, name: &str) -> bool {
+        self.private || !name.split('\'').any(|it| it.starts_with('_'))
+    }
+
+    // #endregion: Collection
+
+    // #region: Links
+
+    // Finds what a link in the doc comment of `item` points at, where
+    // `namespace` is the package that the item is in.
+    fn resolve(
+        &self,
+        link: &str,
+        item: &Item,
+        namespace: &str,
+        symbols: &SymbolTable,
+    ) -> Option<Target> {
+        let (name, is_local) = match link.strip_prefix('\'') {
+            Some(it) => (it, true),
+            None => (link, false),
+        };
+        if is_local && item.locals.iter().any(|it| it == name) {
+            return Some(Target::Plain);
+        }
+
+        // Names are looked up in the item's package first, then its parents
+        let mut candidates: Vec<String> = vec![];
+        let mut prefix = namespace;
+        while !prefix.is_empty() {
+            candidates.push(format!("{prefix}'{name}"));
+            prefix = prefix.rsplit_once('\'').map_or("", |it| it.0);
+        }
+        candidates.push(name.to_string());
+
+        for candidate in &candidates {
+            if let Some(target) = self.target_of(candidate) {
+                return Some(target);
+            }
+            if symbols.get_symbol(candidate.clone()).is_some() {
+                return Some(Target::Plain);
+            }
+        }
+        None
+    }
+
+    // Gets the page that documents a package or item.
+    fn target_of(&self, name: &str) -> Option<Target> {
+        if self.packages.contains_key(name) {
+            return Some(Target::Item {
+                page: page_of(name),
+                anchor: String::new(),
+            });
+        }
+
+        let (namespace, _) = name.rsplit_once('\'').unwrap_or(("", name));
+        let package = self.packages.get(namespace)?;
+        package
+            .items
+            .iter()
+            .find(|it| it.name == name)
+            .map(|it| Target::Item {
+                page: page_of(namespace),
+                anchor: it.short_name().to_string(),
+            })
+    }
+
+    // Resolves every link in a doc comment, reporting the ones that are broken.
+    fn links(
+        &self,
+        spans: &[Span],
+        item: &Item,
+        namespace: &str,
+        symbols: &SymbolTable,
+    ) -> HashMap<String, Target> {
+        let mut links: HashMap<String, Target> = HashMap::new();
+        for span in spans {
+            let Span::Link(link) = span else {
+                continue;
+            };
+            if links.contains_key(link) {
+                continue;
+            }
+
+            match self.resolve(link, item, namespace, symbols) {
+                Some(target) => {
+                    links.insert(link.clone(), target);
+                }
+                None => {
+                    self.report_broken_link(link, item, symbols);
+                    links.insert(link.clone(), Target::Plain);
+                }
+            }
+        }
+        links
+    }
+
+    fn report_broken_link(&self, link: &str, item: &Item, symbols: &SymbolTable) {
+        let mut options: Vec<&str> = symbols.globals().map(|it| it.0.as_str()).collect();
+        options.extend(self.packages.keys().map(|it| it.as_str()));
+        options.retain(|it| !it.contains('$'));
+        let help = util::closest_match(link.trim_start_matches('\''), &options)
+            .map(|it| format!("did you mean `<{it}>`?"));
+
+        self.diagnostics.report(Diagnostic {
+            severity: Severity::Warning,
+            error: DocError::BrokenLink(link.to_string()).into(),
+            help,
+            note: None,
+            file: item.file.clone(),
+            line: item.line,
+            column: item.column,
+            len: 1,
+            lines: util::get_lines_from_file(item.file.to_str().unwrap(), item.line),
+        });
+    }
+
+    // #endregion: Links
+
+    // #region: Output
+
+    // Writes the HTML and Markdown pages into `output`, returning how many
+    // packages were documented, not counting the top level.
+    pub fn write(&self, symbols: &SymbolTable, title: &str, output: &Path) -> io::Result<usize> {
+        fs::create_dir_all(output)?;
+
+        for (namespace, package) in &self.packages {
+            let page = page_of(namespace);
+            let heading = if namespace.is_empty() {
+                title.to_string()
+            } else {
+                format!("pkg {namespace}")
+            };
+
+            // Sub-packages are only listed on the page of their direct parent
+            let children: Vec<&String> = self
+                .packages
+                .keys()
+                .filter(|it| {
+                    !it.is_empty() && it.rsplit_once('\'').map_or("", |it| it.0) == namespace
+                })
+                .collect();
+
+            let mut html = String::new();
+            let mut md = String::new();
+            _ = write!(
+                html,
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n",
+                escape(&heading)
+            );
+            if !namespace.is_empty() {
+                _ = writeln!(
+                    html,
+                    "<nav><a href=\"index.html\">{}</a></nav>",
+                    escape(title)
+                );
+                _ = writeln!(md, "[{title}](index.md)\n");
+            }
+            _ = writeln!(html, "<h1>{}</h1>", escape(&heading));
+            _ = writeln!(md, "# {heading}\n");
+
+            if let Some(item) = &package.doc {
+                let (doc_html, doc_md) = self.render(item, namespace, symbols);
+                _ = writeln!(html, "<div class=\"doc\">{doc_html}</div>");
+                _ = writeln!(md, "{doc_md}\n");
+            }
+
+            if !children.is_empty() {
+                _ = writeln!(html, "<h2>Packages</h2>\n<ul>");
+                _ = writeln!(md, "## Packages\n");
+                for child in children {
+                    _ = writeln!(
+                        html,
+                        "<li><a href=\"{}.html\"><code>{}</code></a></li>",
+                        page_of(child),
+                        escape(child)
+                    );
+                    _ = writeln!(md, "- [`{child}`]({}.md)", page_of(child));
+                }
+                _ = writeln!(html, "</ul>");
+                _ = writeln!(md);
+            }
+
+            for item in &package.items {
+                let anchor = item.short_name();
+                let (doc_html, doc_md) = self.render(item, namespace, symbols);
+                _ = writeln!(
+                    html,
+                    "<h2 id=\"{}\"><code>{}</code></h2>",
+                    escape(anchor),
+                    escape(&item.signature)
+                );
+                _ = writeln!(md, "<a id=\"{anchor}\"></a>\n\n## `{}`\n", item.signature);
+                if !doc_html.is_empty() {
+                    _ = writeln!(html, "<div class=\"doc\">{doc_html}</div>");
+                    _ = writeln!(md, "{doc_md}\n");
+                }
+                let source = format!("{}:{}", item.file.to_string_lossy(), item.line);
+                _ = writeln!(html, "<p class=\"source\">{}</p>", escape(&source));
+                _ = writeln!(md, "*{source}*\n");
+            }
+
+            _ = writeln!(html, "</body>\n</html>");
+            fs::write(output.join(format!("{page}.html")), html)?;
+            fs::write(
+                output.join(format!("{page}.md")),
+                md.trim_end().to_string() + "\n",
+            )?;
+        }
+
+        Ok(self.packages.len() - 1)
+    }
+
+    // Renders an item's doc comment as HTML and Markdown.
+    fn render(&self, item: &Item, namespace: &str, symbols: &SymbolTable) -> (String, String) {
+        let spans = markup::parse(&item.doc);
+        let links = self.links(&spans, item, namespace, symbols);
+        let mut resolve = |link: &str| links.get(link).cloned().unwrap_or(Target::Plain);
+
+        let html = markup::to_html(&spans, &mut resolve);
+        // Markdown joins lines unless they end with two spaces
+        let md = markup::to_markdown(&spans, &mut resolve);
+        let lines: Vec<&str> = md.lines().map(|it| it.trim_end()).collect();
+        let mut joined = String::new();
+        for (index, line) in lines.iter().enumerate() {
+            joined.push_str(line);
+            match lines.get(index + 1) {
+                Some(next) if !line.is_empty() && !next.is_empty() => joined.push_str("  \n"),
+                Some(_) => joined.push('\n'),
+                None => {}
+            }
+        }
+        (html, joined)
+    }
+
+    // #endregion: Output
+}
+
+const STYLE: &str = "body{max-width:60em;margin:auto;padding:1em;font-family:sans-serif}\
+h2{font-size:1.1em;margin-top:2em}\
+.doc{white-space:pre-wrap}\
+.source{color:gray;font-size:0.8em}";
+
+// Analyzes each `.sea` module under `module` the way they'd be `use`d, so that
+// doc comments can link to anything they declare.
+pub fn analyze(module: &Path, mut libpaths: Vec<PathBuf>, diagnostics: Diagnostics) -> SymbolTable {
+    let root = module.parent().unwrap_or(Path::new("")).to_path_buf();
+    libpaths.insert(0, root.clone());
+
+    let mut uses = vec![Node::of_kind(NodeKind::TopUse(PathBuf::from("std")))];
+    for dir in module_dirs(module) {
+        let path = dir.strip_prefix(&root).unwrap_or(&dir).to_path_buf();
+        uses.push(Node::of_kind(NodeKind::TopUse(path)));
+    }
+
+    let source = String::new();
+    let parser = Parser::new(Lexer::new(module.to_path_buf(), &source, diagnostics));
+    let mut compiler = Compiler::new(
+        module.to_path_buf(),
+        PathBuf::from(".sea/build/output.c"),
+        libpaths,
+        parser,
+    );
+    Analyzer::new(&mut compiler).analyze(Node::of_kind(NodeKind::Program(uses)));
+    std::mem::replace(&mut compiler.symbols, SymbolTable::new())
+}
+
+// Gets `dir` and each directory under it that has `.sea` files.
+fn module_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(it) => it.filter_map(|it| it.ok().map(|it| it.path())).collect(),
+        Err(_) => return vec![],
+    };
+    entries.sort();
+
+    let mut dirs: Vec<PathBuf> = vec![];
+    if entries
+        .iter()
+        .any(|it| it.extension().is_some_and(|it| it == "sea"))
+    {
+        dirs.push(dir.to_path_buf());
+    }
+    for entry in entries.iter().filter(|it| it.is_dir()) {
+        dirs.extend(module_dirs(entry));
+    }
+    dirs
+}
+
+// Strips the comment markers from doc comments:
+//
+// /// text     -> text
+// /**
+//  * text     -> text
+// **/
+fn clean(doc: &[String]) -> String {
+    let mut lines: Vec<String> = vec![];
+    for comment in doc {
+        let comment = comment.trim();
+        if let Some(block) = comment.strip_prefix("/**") {
+            let block = block
+                .strip_suffix("*/")
+                .unwrap_or(block)
+                .trim_end_matches('*');
+            for line in block.lines() {
+                let line = line.trim_start();
+                let line = line.strip_prefix('*').unwrap_or(line);
+                lines.push(
+                    line.strip_prefix(' ')
+                        .unwrap_or(line)
+                        .trim_end()
+                        .to_string(),
+                );
+            }
+        } else {
+            for line in comment.lines() {
+                let line = line.trim_start();
+                let line = line.strip_prefix("///").unwrap_or(line);
+                lines.push(
+                    line.strip_prefix(' ')
+                        .unwrap_or(line)
+                        .trim_end()
+                        .to_string(),
+                );
+            }
+        }
+    }
+
+    // Blank lines around the comment are only there to make the source look nice
+    let start = lines
+        .iter()
+        .position(|it| !it.is_empty())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|it| !it.is_empty())
+        .map_or(start, |it| it + 1);
+    lines[start..end].join("\n")
+}
+
+fn type_text(node: &Node) -> String {
+    SeaType::from_node(node.clone()).map_or_else(|| "?".to_string(), |it| it.to_string())
+}
+
+// Pages are named after their package, i.e, `hash'wyhash` is `hash.wyhash.html`.
+fn page_of(namespace: &str) -> String {
+    if namespace.is_empty() {
+        "index".to_string()
+    } else {
+        namespace.replace('\'', ".")
+    }
+}
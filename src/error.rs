@@ -1,15 +1,39 @@
-use std::path::PathBuf;
-
 use thiserror::Error;
 
-use crate::parse::error::ParseError;
+use crate::{
+    compile::error::CompilerError,
+    doc::error::DocError,
+    parse::error::{LexErrorKind, ParseError},
+    reef::error::ReefError,
+};
 
 #[derive(Debug, Clone, Error)]
 pub enum SeaError {
-    #[error("parsing error")]
-    Parse {
-        path: PathBuf,
-        #[source]
-        why: ParseError,
-    },
+    #[error(transparent)]
+    Lex(#[from] LexErrorKind),
+
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+
+    #[error(transparent)]
+    Compile(#[from] CompilerError),
+
+    #[error(transparent)]
+    Doc(#[from] DocError),
+
+    #[error(transparent)]
+    Reef(#[from] ReefError),
+}
+
+impl SeaError {
+    // The name of the error's variant, i.e, `UnknownSymbol`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SeaError::Lex(it) => it.into(),
+            SeaError::Parse(it) => it.into(),
+            SeaError::Compile(it) => it.into(),
+            SeaError::Doc(it) => it.into(),
+            SeaError::Reef(it) => it.into(),
+        }
+    }
 }
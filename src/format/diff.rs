@@ -0,0 +1,133 @@
+// Line-based diffs for `sea fmt --check`, printed like `diff -u`.
+
+use std::fmt::Write;
+
+const CONTEXT: usize = 3; // unchanged lines to show around each change
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Remove,
+    Add,
+}
+
+// The edits that turn `old` into `new`, from the longest common subsequence of
+// their lines. Lines shared at the start and end are skipped over first, since
+// formatting usually only touches a few lines.
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    // lengths[i][j] is the length of the LCS of old_middle[i..] and new_middle[j..]
+    let mut lengths = vec![vec![0u32; new_middle.len() + 1]; old_middle.len() + 1];
+    for i in (0..old_middle.len()).rev() {
+        for j in (0..new_middle.len()).rev() {
+            lengths[i][j] = if old_middle[i] == new_middle[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = vec![Edit::Keep; prefix];
+    let (mut i, mut j) = (0, 0);
+    while i < old_middle.len() || j < new_middle.len() {
+        if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+            edits.push(Edit::Keep);
+            i += 1;
+            j += 1;
+        } else if i < old_middle.len()
+            && (j == new_middle.len() || lengths[i + 1][j] >= lengths[i][j + 1])
+        {
+            edits.push(Edit::Remove);
+            i += 1;
+        } else {
+            edits.push(Edit::Add);
+            j += 1;
+        }
+    }
+    edits.extend(vec![Edit::Keep; suffix]);
+    edits
+}
+
+// Describes how to turn `old` into `new`, or returns an empty string if they're
+// the same.
+pub fn unified(old_name: &str, old: &str, new_name: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let edits = edits(&old, &new);
+
+    let mut out = String::new();
+    let changes: Vec<usize> = (0..edits.len())
+        .filter(|it| edits[*it] != Edit::Keep)
+        .collect();
+    if changes.is_empty() {
+        return out;
+    }
+
+    _ = writeln!(out, "\x1b[1m--- {old_name}\n+++ {new_name}\x1b[0m");
+
+    // Group changes that are close enough to share their context
+    let mut hunks: Vec<(usize, usize)> = vec![];
+    for change in changes {
+        let start = change.saturating_sub(CONTEXT);
+        let end = (change + CONTEXT + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some(hunk) if start <= hunk.1 => hunk.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    for (start, end) in hunks {
+        // Where the hunk starts in each file
+        let old_start = edits[..start].iter().filter(|it| **it != Edit::Add).count();
+        let new_start = edits[..start]
+            .iter()
+            .filter(|it| **it != Edit::Remove)
+            .count();
+        let old_len = edits[start..end]
+            .iter()
+            .filter(|it| **it != Edit::Add)
+            .count();
+        let new_len = edits[start..end]
+            .iter()
+            .filter(|it| **it != Edit::Remove)
+            .count();
+
+        _ = writeln!(
+            out,
+            "\x1b[36m@@ -{},{old_len} +{},{new_len} @@\x1b[0m",
+            old_start + 1,
+            new_start + 1
+        );
+
+        let (mut i, mut j) = (old_start, new_start);
+        for edit in &edits[start..end] {
+            match edit {
+                Edit::Keep => {
+                    _ = writeln!(out, " {}", old[i]);
+                    i += 1;
+                    j += 1;
+                }
+                Edit::Remove => {
+                    _ = writeln!(out, "\x1b[31m-{}\x1b[0m", old[i]);
+                    i += 1;
+                }
+                Edit::Add => {
+                    _ = writeln!(out, "\x1b[32m+{}\x1b[0m", new[j]);
+                    j += 1;
+                }
+            }
+        }
+    }
+
+    out
+}
@@ -0,0 +1,447 @@
+// The code formatter behind `sea fmt`. It works on tokens rather than the AST so
+// that comments survive: the lexer keeps comments and newlines as tokens, the
+// tokens get regrouped into lines, and then each line is reprinted with
+// canonical indentation and spacing.
+//
+// Line breaks are kept as written, except that:
+// - `{` always ends the line of the statement it belongs to, and `}` always
+//   starts a line of its own, so `{}` blocks span lines unless they're empty.
+// - `->` is for blocks with a single statement, which stays on the same line or
+//   gets indented on the next one.
+// - Blank lines are collapsed into one, and removed at the start and end of
+//   blocks and files.
+// - `#(tag)` is shortened to `#tag`.
+
+pub mod diff;
+
+use std::path::PathBuf;
+
+use crate::{
+    diagnostics::Diagnostics,
+    parse::{
+        lexer::Lexer,
+        parser::Parser,
+        token::{Token, TokenKind},
+    },
+};
+
+type Line = Vec<Token>; // empty for blank lines
+
+// What a token does to the tokens around it, for the ones that are ambiguous.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Prefix,  // `-x`, `^T`, `++x`
+    Postfix, // `x^`, `x++`
+    Name,    // `ret` in `@ret(...)`, which isn't the statement
+    Other,
+}
+
+// Formats a Sea file, returning `None` if it has errors. Files that don't parse
+// aren't formatted, since we can't be sure what they're supposed to mean.
+pub fn format(file: PathBuf, source: &String, diagnostics: Diagnostics) -> Option<String> {
+    Parser::new(Lexer::new(file.clone(), source, diagnostics.clone())).parse(false);
+    if diagnostics.has_errors() {
+        return None;
+    }
+
+    let mut lexer = Lexer::new(file, source, diagnostics);
+    lexer.trivia = true;
+
+    let mut lines: Vec<Line> = vec![vec![]];
+    loop {
+        let token = lexer.next_token();
+        match token.kind {
+            TokenKind::Eof => break,
+            TokenKind::Newline => lines.push(vec![]),
+            _ => lines.last_mut().unwrap().push(token),
+        }
+    }
+
+    let lines = remove_blank_lines(split_lines(join_lines(shorten_hashtags(lines))));
+    Some(print(source, lines))
+}
+
+// #region: Lines
+
+fn shorten_hashtags(lines: Vec<Line>) -> Vec<Line> {
+    lines
+        .into_iter()
+        .map(|line| {
+            let mut tokens: Line = vec![];
+            let mut index = 0;
+            while index < line.len() {
+                let kinds: Vec<TokenKind> =
+                    line[index..].iter().take(4).map(|it| it.kind).collect();
+                tokens.push(line[index].clone());
+                if kinds
+                    == [
+                        TokenKind::Hashtag,
+                        TokenKind::OpenParen,
+                        TokenKind::Identifier,
+                        TokenKind::CloseParen,
+                    ]
+                {
+                    tokens.push(line[index + 2].clone());
+                    index += 4;
+                } else {
+                    index += 1;
+                }
+            }
+            tokens
+        })
+        .collect()
+}
+
+// Moves `{` up to the end of the statement that it starts a block for, and
+// `else` up to the `}` that it follows.
+fn join_lines(lines: Vec<Line>) -> Vec<Line> {
+    let mut joined: Vec<Line> = vec![];
+
+    for line in lines {
+        let previous = joined.iter().rposition(|it| !it.is_empty());
+        let joins = match (line.first(), previous) {
+            (Some(first), Some(previous)) => {
+                let previous = &joined[previous];
+                let last = previous.last().unwrap().kind;
+                match first.kind {
+                    TokenKind::OpenCurly => {
+                        opens_block(previous[0].kind)
+                            && !matches!(
+                                last,
+                                TokenKind::Comment | TokenKind::OpenCurly | TokenKind::Arrow
+                            )
+                    }
+                    TokenKind::KwElse => last == TokenKind::CloseCurly,
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+
+        if joins {
+            while joined.last().is_some_and(|it| it.is_empty()) {
+                joined.pop();
+            }
+            joined.last_mut().unwrap().extend(line);
+        } else {
+            joined.push(line);
+        }
+    }
+
+    joined
+}
+
+// Whether a statement starting with the given token can have a block.
+fn opens_block(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::KwFun
+            | TokenKind::KwIf
+            | TokenKind::KwElse
+            | TokenKind::KwFor
+            | TokenKind::KwSwitch
+            | TokenKind::KwCase
+            | TokenKind::KwFall
+            | TokenKind::KwPkg
+            | TokenKind::KwTrait
+            | TokenKind::KwImpl
+            | TokenKind::KwDefer
+            | TokenKind::Hashtag
+            | TokenKind::CloseCurly
+    )
+}
+
+// Puts whatever comes after `{` and before `}` onto its own line.
+fn split_lines(lines: Vec<Line>) -> Vec<Line> {
+    let mut split: Vec<Line> = vec![];
+
+    for line in lines {
+        let mut current: Line = vec![];
+        for (index, token) in line.iter().enumerate() {
+            if token.kind == TokenKind::CloseCurly
+                && current
+                    .last()
+                    .is_some_and(|it| it.kind != TokenKind::OpenCurly)
+            {
+                split.push(current);
+                current = vec![];
+            }
+
+            current.push(token.clone());
+
+            let next = match line.get(index + 1) {
+                Some(it) => it.kind,
+                None => continue,
+            };
+            let breaks = match token.kind {
+                TokenKind::OpenCurly => !matches!(next, TokenKind::CloseCurly | TokenKind::Comment),
+                TokenKind::CloseCurly => !matches!(
+                    next,
+                    TokenKind::KwElse
+                        | TokenKind::Comma
+                        | TokenKind::CloseParen
+                        | TokenKind::CloseBracket
+                        | TokenKind::CloseCurly
+                        | TokenKind::Comment
+                ),
+                _ => false,
+            };
+            if breaks {
+                split.push(current);
+                current = vec![];
+            }
+        }
+        split.push(current);
+    }
+
+    split
+}
+
+fn remove_blank_lines(lines: Vec<Line>) -> Vec<Line> {
+    let mut kept: Vec<Line> = vec![];
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            let after_open = kept
+                .last()
+                .is_none_or(|it| it.is_empty() || it.last().unwrap().kind == TokenKind::OpenCurly);
+            let before_close = lines[index + 1..]
+                .iter()
+                .find(|it| !it.is_empty())
+                .is_none_or(|it| it[0].kind == TokenKind::CloseCurly);
+            if after_open || before_close {
+                continue;
+            }
+        }
+        kept.push(line.clone());
+    }
+
+    kept
+}
+
+// #endregion: Lines
+
+// #region: Printing
+
+fn print(source: &str, lines: Vec<Line>) -> String {
+    let source_lines: Vec<&str> = source.lines().collect();
+    let mut out = String::new();
+
+    // Each open bracket or unfinished statement, along with the line it's on.
+    // Lines get indented once for each line that has something still open.
+    let mut open: Vec<(bool, usize)> = vec![]; // (is a continuation, line)
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            out.push('\n');
+            continue;
+        }
+
+        // Closing brackets at the start of a line belong to the outer level
+        let leading = line.iter().take_while(|it| is_closer(it.kind)).count();
+        for _ in 0..leading {
+            close(&mut open);
+        }
+
+        let mut depth = open.iter().map(|it| it.1).collect::<Vec<usize>>();
+        depth.dedup();
+        let indent = "\t".repeat(depth.len());
+
+        for token in &line[leading..] {
+            if is_opener(token.kind) {
+                open.push((false, index));
+            } else if is_closer(token.kind) {
+                close(&mut open);
+            }
+        }
+
+        // The next line continues this statement if it ends with `->` or an
+        // operator, otherwise every continued statement before it is finished
+        let last = line.iter().rfind(|it| it.kind != TokenKind::Comment);
+        match last {
+            Some(it) if continues(it.kind) => open.push((true, index)),
+            Some(_) => {
+                while open.last().is_some_and(|it| it.0) {
+                    open.pop();
+                }
+            }
+            None => {}
+        }
+
+        out.push_str(&indent);
+        out.push_str(&print_line(&source_lines, &indent, line));
+        out.push('\n');
+    }
+
+    out
+}
+
+fn print_line(source_lines: &[&str], indent: &str, line: &Line) -> String {
+    let roles = roles(line);
+    let mut out = String::new();
+
+    // Module paths are written like file paths, i.e, `use std/io`
+    let is_use = line[0].kind == TokenKind::KwUse;
+
+    for (index, token) in line.iter().enumerate() {
+        let in_path = is_use && index > 1 && token.kind != TokenKind::Comment;
+        if index > 0 && !in_path && spaced(&line[index - 1], roles[index - 1], token, roles[index])
+        {
+            out.push(' ');
+        }
+
+        match token.kind {
+            TokenKind::String => out.push_str(&format!("\"{}\"", token.text)),
+            TokenKind::CString => out.push_str(&format!("c\"{}\"", token.text)),
+            TokenKind::Character => out.push_str(&format!("`{}`", token.text)),
+            TokenKind::LiteralText => out.push_str(&format!("[{}]", token.text)),
+            TokenKind::Comment => out.push_str(&reindent(source_lines, indent, token)),
+            _ => out.push_str(&token.text),
+        }
+    }
+
+    out
+}
+
+// Block comments keep their shape, but move along with the code around them.
+fn reindent(source_lines: &[&str], indent: &str, comment: &Token) -> String {
+    let newlines = comment.text.matches('\n').count();
+    if newlines == 0 {
+        return comment.text.clone();
+    }
+
+    // The token's line is the one that it ends on
+    let first_line = source_lines
+        .get(comment.line - newlines - 1)
+        .copied()
+        .unwrap_or_default();
+    let old_indent: String = first_line
+        .chars()
+        .take_while(|it| it.is_whitespace())
+        .collect();
+
+    let mut lines = comment.text.split('\n');
+    let mut out = lines.next().unwrap().to_string();
+    for line in lines {
+        out.push('\n');
+        let line = line.strip_prefix(old_indent.as_str()).unwrap_or(line);
+        if !line.trim().is_empty() {
+            out.push_str(indent);
+            out.push_str(line.trim_end());
+        }
+    }
+    out
+}
+
+// Works out which tokens are prefix or postfix operators, since that decides
+// whether they're spaced like binary operators.
+fn roles(line: &Line) -> Vec<Role> {
+    let mut roles: Vec<Role> = vec![];
+
+    for (index, token) in line.iter().enumerate() {
+        let after_value = index > 0 && ends_value(&line[index - 1], roles[index - 1]);
+        roles.push(match token.kind {
+            TokenKind::Pointer | TokenKind::OpInc | TokenKind::OpDec if after_value => {
+                Role::Postfix
+            }
+            TokenKind::Pointer | TokenKind::OpInc | TokenKind::OpDec => Role::Prefix,
+            TokenKind::OpSub if !after_value => Role::Prefix,
+            TokenKind::KwRet if index > 0 && line[index - 1].kind == TokenKind::At => Role::Name,
+            _ => Role::Other,
+        });
+    }
+
+    roles
+}
+
+// Whether the token can be the last one in a value, i.e, `x` or `)`.
+fn ends_value(token: &Token, role: Role) -> bool {
+    match token.kind {
+        TokenKind::Identifier
+        | TokenKind::Int
+        | TokenKind::Float
+        | TokenKind::Hex
+        | TokenKind::Binary
+        | TokenKind::String
+        | TokenKind::CString
+        | TokenKind::Character
+        | TokenKind::True
+        | TokenKind::False
+        | TokenKind::CloseParen
+        | TokenKind::CloseBracket => true,
+        TokenKind::Pointer | TokenKind::OpInc | TokenKind::OpDec => role == Role::Postfix,
+        TokenKind::KwRet => role == Role::Name,
+        _ => false,
+    }
+}
+
+// Whether there's a space between two tokens on the same line.
+fn spaced(left: &Token, left_role: Role, right: &Token, right_role: Role) -> bool {
+    match (left.kind, right.kind) {
+        (_, TokenKind::Comment) => true,
+        (TokenKind::OpenParen | TokenKind::OpenBracket, _) => false,
+        (
+            _,
+            TokenKind::CloseParen
+            | TokenKind::CloseBracket
+            | TokenKind::Comma
+            | TokenKind::Colon
+            | TokenKind::Semicolon,
+        ) => false,
+        (TokenKind::OpDot, _) | (_, TokenKind::OpDot) => false,
+        (TokenKind::Hashtag | TokenKind::At, _) => false,
+        (TokenKind::OpenCurly, TokenKind::CloseCurly) => false,
+        // Calls, indexing, and array types
+        (_, TokenKind::OpenParen | TokenKind::OpenBracket) => !ends_value(left, left_role),
+        _ => left_role != Role::Prefix && right_role != Role::Postfix,
+    }
+}
+
+fn is_opener(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::OpenParen | TokenKind::OpenBracket | TokenKind::OpenCurly
+    )
+}
+
+fn is_closer(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::CloseParen | TokenKind::CloseBracket | TokenKind::CloseCurly
+    )
+}
+
+// Whether a line ending with the token continues onto the next line.
+fn continues(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Arrow
+            | TokenKind::Eq
+            | TokenKind::OpAnd
+            | TokenKind::OpOr
+            | TokenKind::OpEq
+            | TokenKind::OpNeq
+            | TokenKind::OpGt
+            | TokenKind::OpGtEq
+            | TokenKind::OpLt
+            | TokenKind::OpLtEq
+            | TokenKind::OpAdd
+            | TokenKind::OpSub
+            | TokenKind::OpMul
+            | TokenKind::OpDiv
+            | TokenKind::OpMod
+            | TokenKind::OpPipe
+            | TokenKind::OpDot
+            | TokenKind::KwAs
+    )
+}
+
+// Closes the innermost bracket, along with any statements continued inside it.
+fn close(open: &mut Vec<(bool, usize)>) {
+    while open.last().is_some_and(|it| it.0) {
+        open.pop();
+    }
+    open.pop();
+}
+
+// #endregion: Printing
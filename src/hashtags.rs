@@ -1,6 +1,17 @@
-use strum::EnumString;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+use strum::{EnumString, IntoStaticStr, VariantNames};
+
+// Implemented by each statement's hashtag enum so that the parser can validate
+// them the same way.
+pub trait Hashtag: FromStr + VariantNames + Into<&'static str> + PartialEq + Copy {
+    // Whether this hashtag can't be used together with `other`.
+    fn conflicts_with(self, _other: Self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, IntoStaticStr, VariantNames)]
 pub enum FunTags {
     #[strum(serialize = "noret")]
     NoRet,
@@ -10,24 +21,32 @@ pub enum FunTags {
     Extern,
     #[strum(serialize = "static")]
     Static,
+    #[strum(serialize = "loose")]
+    Loose,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, IntoStaticStr, VariantNames)]
 pub enum RecTags {
     #[strum(serialize = "union")]
     Union,
+    #[strum(serialize = "extern")]
+    Extern,
     #[strum(serialize = "static")]
     Static,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, IntoStaticStr, VariantNames)]
 pub enum DefTags {
+    #[strum(serialize = "extern")]
+    Extern,
     #[strum(serialize = "static")]
     Static,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, IntoStaticStr, VariantNames)]
 pub enum TagTags {
+    #[strum(serialize = "extern")]
+    Extern,
     #[strum(serialize = "static")]
     Static,
 
@@ -35,8 +54,47 @@ pub enum TagTags {
     NoHelpers,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, IntoStaticStr, VariantNames)]
 pub enum TagRecTags {
     #[strum(serialize = "static")]
     Static,
 }
+
+impl Hashtag for FunTags {
+    fn conflicts_with(self, other: Self) -> bool {
+        // These give the function opposite linkage
+        matches!(
+            (self, other),
+            (FunTags::Extern, FunTags::Static) | (FunTags::Static, FunTags::Extern)
+        )
+    }
+}
+
+impl Hashtag for RecTags {
+    fn conflicts_with(self, other: Self) -> bool {
+        matches!(
+            (self, other),
+            (RecTags::Extern, RecTags::Static) | (RecTags::Static, RecTags::Extern)
+        )
+    }
+}
+
+impl Hashtag for DefTags {
+    fn conflicts_with(self, other: Self) -> bool {
+        matches!(
+            (self, other),
+            (DefTags::Extern, DefTags::Static) | (DefTags::Static, DefTags::Extern)
+        )
+    }
+}
+
+impl Hashtag for TagTags {
+    fn conflicts_with(self, other: Self) -> bool {
+        matches!(
+            (self, other),
+            (TagTags::Extern, TagTags::Static) | (TagTags::Static, TagTags::Extern)
+        )
+    }
+}
+
+impl Hashtag for TagRecTags {}
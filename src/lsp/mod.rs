@@ -0,0 +1,416 @@
+// A language server for Sea, spoken over stdin and stdout. Each time a document
+// changes it gets parsed and analyzed like `sea c` would, and the symbol table
+// from the analysis answers hover, go to definition, and completion requests.
+
+pub mod protocol;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufRead, Write},
+    mem,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    compile::{
+        analysis::Analyzer,
+        compiler::Compiler,
+        symbol::{Location, Symbol, SymbolTable},
+    },
+    diagnostics::{Diagnostic, Diagnostics, Severity},
+    parse::{
+        lexer::Lexer,
+        parser::Parser,
+        token::{Token, TokenKind},
+    },
+};
+
+use protocol::{
+    error_response, notification, path_to_uri, position_of, range, read_message, response,
+    token_contains, token_range, uri_to_path, write_message, METHOD_NOT_FOUND,
+};
+
+struct Document {
+    text: String,
+    symbols: SymbolTable, // from the last analysis that didn't fail to parse
+    published: HashSet<String>, // URIs that this document's analysis reported diagnostics in
+}
+
+pub struct Server {
+    libpaths: Vec<PathBuf>,
+    documents: HashMap<PathBuf, Document>,
+    shutdown: bool,
+}
+
+impl Server {
+    pub fn new(libpaths: Vec<PathBuf>) -> Self {
+        Server {
+            libpaths,
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    // Handles messages until the client sends `exit`, returning the exit code.
+    pub fn run(&mut self, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<i32> {
+        while let Some(message) = read_message(input)? {
+            let method = message["method"].as_str().unwrap_or_default();
+            let params = &message["params"];
+
+            if method == "exit" {
+                return Ok(if self.shutdown { 0 } else { 1 });
+            }
+
+            // Requests have an ID and need a response, notifications don't
+            let Some(id) = message.get("id") else {
+                for it in self.notify(method, params) {
+                    write_message(output, &it)?;
+                }
+                continue;
+            };
+
+            let reply = match self.request(method, params) {
+                Some(result) => response(id, result),
+                None => error_response(id, METHOD_NOT_FOUND, &format!("unknown method: {method}")),
+            };
+            write_message(output, &reply)?;
+        }
+
+        // The client went away without saying goodbye
+        Ok(1)
+    }
+
+    // #region: Messages
+
+    // Handles a request, returning `None` if the method isn't supported.
+    fn request(&mut self, method: &str, params: &Value) -> Option<Value> {
+        match method {
+            "initialize" => Some(json!({
+                "capabilities": {
+                    "textDocumentSync": 1, // the client sends the full text on change
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": { "triggerCharacters": ["'"] },
+                },
+                "serverInfo": { "name": "sea", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Some(Value::Null)
+            }
+            "textDocument/hover" => Some(self.hover(params).unwrap_or(Value::Null)),
+            "textDocument/definition" => Some(self.definition(params).unwrap_or(Value::Null)),
+            "textDocument/completion" => Some(self.completion(params).unwrap_or(json!([]))),
+            _ => None,
+        }
+    }
+
+    // Handles a notification, returning the notifications to send back.
+    fn notify(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let document = &params["textDocument"];
+        let Some(path) = document["uri"].as_str().and_then(uri_to_path) else {
+            return vec![];
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = document["text"].as_str().unwrap_or_default().to_string();
+                self.update(path, text)
+            }
+            "textDocument/didChange" => {
+                // We only ask for full text changes, so the last change is the whole document
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|it| it.last())
+                    .and_then(|it| it["text"].as_str());
+                match text {
+                    Some(text) => self.update(path, text.to_string()),
+                    None => vec![],
+                }
+            }
+            "textDocument/didClose" => match self.documents.remove(&path) {
+                Some(document) => document
+                    .published
+                    .into_iter()
+                    .map(|uri| publish(uri, vec![]))
+                    .collect(),
+                None => vec![],
+            },
+            _ => vec![],
+        }
+    }
+
+    // #endregion: Messages
+
+    // #region: Analysis
+
+    // Analyzes the new text of a document, returning diagnostics to publish.
+    fn update(&mut self, path: PathBuf, text: String) -> Vec<Value> {
+        let (diagnostics, symbols) = analyze(&path, &text, self.libpaths.clone());
+
+        let mut by_uri: HashMap<String, Vec<Value>> = HashMap::new();
+        by_uri.insert(path_to_uri(&path), vec![]);
+        for it in diagnostics {
+            by_uri
+                .entry(path_to_uri(&it.file))
+                .or_default()
+                .push(to_lsp_diagnostic(&it));
+        }
+
+        let document = self.documents.entry(path).or_insert_with(|| Document {
+            text: String::new(),
+            symbols: SymbolTable::new(),
+            published: HashSet::new(),
+        });
+        document.text = text;
+        if let Some(symbols) = symbols {
+            document.symbols = symbols;
+        }
+
+        // Clear diagnostics from files that don't have any problems anymore
+        let mut messages: Vec<Value> = document
+            .published
+            .iter()
+            .filter(|it| !by_uri.contains_key(*it))
+            .map(|it| publish(it.clone(), vec![]))
+            .collect();
+
+        document.published = by_uri.keys().cloned().collect();
+        messages.extend(
+            by_uri
+                .into_iter()
+                .map(|(uri, diagnostics)| publish(uri, diagnostics)),
+        );
+        messages
+    }
+
+    // Gets a document's text, either from the client or from disk.
+    fn text_of(&self, path: &PathBuf) -> Option<String> {
+        match self.documents.get(path) {
+            Some(it) => Some(it.text.clone()),
+            None => fs::read_to_string(path).ok(),
+        }
+    }
+
+    // Finds the document and identifier that a request's position points at.
+    fn identifier_at(&self, params: &Value) -> Option<(PathBuf, &Document, Token)> {
+        let path = uri_to_path(params["textDocument"]["uri"].as_str()?)?;
+        let document = self.documents.get(&path)?;
+        let (line, character) = position_of(params)?;
+
+        let token = tokens(&path, &document.text)
+            .into_iter()
+            .find(|it| it.kind == TokenKind::Identifier && token_contains(it, line, character))?;
+
+        Some((path, document, token))
+    }
+
+    // #endregion: Analysis
+
+    // #region: Features
+
+    fn hover(&self, params: &Value) -> Option<Value> {
+        let (path, document, token) = self.identifier_at(params)?;
+        let symbols = &document.symbols;
+        let name = token.text.clone();
+        let at = Location {
+            file: path.clone(),
+            line: token.line,
+            column: token.column,
+        };
+
+        // Globals get their declaration, anything else gets the type of the expression
+        let global = symbols
+            .globals()
+            .find(|(it, _)| **it == name)
+            .map(|(_, symbol)| symbol);
+        let is_global = match symbols.get_reference(&at) {
+            Some(definition) => symbols.get_definition(&name) == Some(definition),
+            None => true,
+        };
+
+        let text = match global {
+            Some(symbol) if is_global => symbol.signature(&name),
+            _ => format!(
+                "{name}: {}",
+                symbols.get_type_of(path, token.line, token.column)?
+            ),
+        };
+
+        Some(json!({
+            "contents": { "kind": "markdown", "value": format!("```sea\n{text}\n```") },
+            "range": token_range(&token),
+        }))
+    }
+
+    fn definition(&self, params: &Value) -> Option<Value> {
+        let (path, document, token) = self.identifier_at(params)?;
+        let symbols = &document.symbols;
+        let at = Location {
+            file: path,
+            line: token.line,
+            column: token.column,
+        };
+
+        let definition = symbols
+            .get_reference(&at)
+            .or_else(|| symbols.get_definition(&token.text))?;
+
+        // Definitions point at the start of their statement, so look for the
+        // name on that line. Names in packages are declared without the package.
+        let short = token.text.rsplit('\'').next().unwrap_or_default();
+        let text = self.text_of(&definition.file)?;
+        let target = tokens(&definition.file, &text).into_iter().find(|it| {
+            it.kind == TokenKind::Identifier
+                && it.line == definition.line
+                && (it.text == token.text || it.text == short)
+        });
+
+        Some(json!({
+            "uri": path_to_uri(&definition.file),
+            "range": match target {
+                Some(it) => token_range(&it),
+                None => range(definition.line, 1, 0),
+            },
+        }))
+    }
+
+    fn completion(&self, params: &Value) -> Option<Value> {
+        let path = uri_to_path(params["textDocument"]["uri"].as_str()?)?;
+        let document = self.documents.get(&path)?;
+        let (line, character) = position_of(params)?;
+
+        // The name being typed, i.e, `io'pr`
+        let before: Vec<char> = document
+            .text
+            .lines()
+            .nth(line)
+            .unwrap_or_default()
+            .chars()
+            .take(character)
+            .collect();
+        let start = before
+            .iter()
+            .rposition(|it| !(it.is_alphanumeric() || *it == '_' || *it == '\''))
+            .map_or(0, |it| it + 1);
+        let prefix: String = before[start..].iter().collect();
+
+        let items: Vec<Value> = document
+            .symbols
+            .globals()
+            .filter(|(name, _)| name.starts_with(&prefix) && !name.contains('$'))
+            .map(|(name, symbol)| {
+                json!({
+                    "label": name,
+                    "kind": completion_kind(symbol),
+                    "detail": symbol.signature(name),
+                    "textEdit": {
+                        "range": {
+                            "start": { "line": line, "character": start },
+                            "end": { "line": line, "character": before.len() },
+                        },
+                        "newText": name,
+                    },
+                })
+            })
+            .collect();
+
+        Some(json!(items))
+    }
+
+    // #endregion: Features
+}
+
+// Parses and analyzes a document, returning its diagnostics along with the
+// symbol table. There's no symbol table if the document couldn't be parsed.
+fn analyze(
+    path: &Path,
+    text: &String,
+    libpaths: Vec<PathBuf>,
+) -> (Vec<Diagnostic>, Option<SymbolTable>) {
+    let diagnostics = Diagnostics::new();
+    let mut parser = Parser::new(Lexer::new(path.to_path_buf(), text, diagnostics.clone()));
+    let program = parser.parse(true);
+
+    // The AST is incomplete if parsing failed, so there's no use in analyzing it
+    if diagnostics.has_errors() {
+        return (diagnostics.all(), None);
+    }
+    let parsed = diagnostics.all();
+
+    // A half-written program can trip up the analyzer, which shouldn't take
+    // the whole server down with it
+    let symbols = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut compiler = Compiler::new(
+            path.to_path_buf(),
+            PathBuf::from(".sea/build/output.c"),
+            libpaths,
+            parser,
+        );
+        Analyzer::new(&mut compiler).analyze(program);
+        mem::replace(&mut compiler.symbols, SymbolTable::new())
+    }));
+
+    match symbols {
+        Ok(symbols) => (diagnostics.all(), Some(symbols)),
+        Err(_) => (parsed, None),
+    }
+}
+
+// Lexes a whole file, skipping over anything that fails to lex.
+fn tokens(path: &Path, text: &String) -> Vec<Token> {
+    let mut lexer = Lexer::new(path.to_path_buf(), text, Diagnostics::new());
+    let mut tokens: Vec<Token> = vec![];
+    loop {
+        let token = lexer.next_token();
+        if token.kind == TokenKind::Eof {
+            return tokens;
+        }
+        tokens.push(token);
+    }
+}
+
+fn to_lsp_diagnostic(diagnostic: &Diagnostic) -> Value {
+    let mut message = diagnostic.error.to_string();
+    if let Some(note) = &diagnostic.note {
+        message += &format!("\nnote: {note}");
+    }
+    if let Some(help) = &diagnostic.help {
+        message += &format!("\nhelp: {help}");
+    }
+
+    json!({
+        "range": range(diagnostic.line, diagnostic.column, diagnostic.len),
+        "severity": match diagnostic.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+        },
+        "code": diagnostic.error.kind(),
+        "source": "sea",
+        "message": message,
+    })
+}
+
+fn publish(uri: String, diagnostics: Vec<Value>) -> Value {
+    notification(
+        "textDocument/publishDiagnostics",
+        json!({ "uri": uri, "diagnostics": diagnostics }),
+    )
+}
+
+// https://microsoft.github.io/language-server-protocol/specifications/specification-current/#completionItemKind
+fn completion_kind(symbol: &Symbol) -> u8 {
+    match symbol {
+        Symbol::Fun { .. } => 3,
+        Symbol::Var { .. } => 6,
+        Symbol::Rec { .. } => 22,
+        Symbol::Def { .. } => 25,
+        Symbol::Tag { .. } | Symbol::TagRec { .. } => 13,
+        Symbol::Trait { .. } => 11,
+        Symbol::Generic { symbol, .. } => completion_kind(symbol),
+    }
+}
@@ -0,0 +1,149 @@
+// The parts of the Language Server Protocol that the server needs: reading and
+// writing JSON-RPC messages, converting between URIs and paths, and converting
+// between Sea and LSP positions.
+// https://microsoft.github.io/language-server-protocol/specifications/specification-current/
+
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use crate::parse::token::Token;
+
+// #region: Messages
+
+// Reads one message, returning `None` once the client closes the stream.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length: Option<usize> = None;
+
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse().ok();
+        }
+    }
+
+    let length = match length {
+        Some(it) => it,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message is missing a Content-Length header",
+            ))
+        }
+    };
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|it| io::Error::new(io::ErrorKind::InvalidData, it))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+pub fn response(id: &Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+// #endregion: Messages
+
+// #region: URIs
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+
+    // Decode percent-encoded bytes, i.e, `%20` for spaces
+    let mut bytes: Vec<u8> = vec![];
+    let mut chars = path.bytes();
+    while let Some(byte) = chars.next() {
+        if byte == b'%' {
+            let hex = [chars.next()?, chars.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path
+        .canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string();
+
+    let mut uri = "file://".to_string();
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+// #endregion: URIs
+
+// #region: Positions
+
+// Sea columns point just past the end of a token, so the token starts `len`
+// columns before that. LSP lines and characters count from zero.
+pub fn range(line: usize, column: usize, len: usize) -> Value {
+    let end = column.saturating_sub(1);
+    json!({
+        "start": { "line": line.saturating_sub(1), "character": end.saturating_sub(len) },
+        "end": { "line": line.saturating_sub(1), "character": end },
+    })
+}
+
+pub fn token_range(token: &Token) -> Value {
+    range(token.line, token.column, token.len)
+}
+
+// Whether an LSP position (zero-based line and character) is on the token.
+// Positions right after the token count too, since that's where the cursor is
+// while typing it.
+pub fn token_contains(token: &Token, line: usize, character: usize) -> bool {
+    let end = token.column.saturating_sub(1);
+    token.line == line + 1 && end.saturating_sub(token.len) <= character && character <= end
+}
+
+// Gets the zero-based line and character from a request's `position`.
+pub fn position_of(params: &Value) -> Option<(usize, usize)> {
+    let position = params.get("position")?;
+    Some((
+        position.get("line")?.as_u64()? as usize,
+        position.get("character")?.as_u64()? as usize,
+    ))
+}
+
+// #endregion: Positions
@@ -1,16 +1,29 @@
-use std::{fs, path::PathBuf, process::exit};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::exit,
+    str::FromStr,
+};
 
 use backend::{backend::Backend, backends::c::CBackend};
-use compile::compiler::Compiler;
+use compile::{analysis::Analyzer, compiler::Compiler};
+use diagnostics::{Diagnostics, ErrorFormat};
 use parse::{lexer::Lexer, parser::Parser};
 
 pub mod backend;
+pub mod bindgen;
 pub mod compile;
+pub mod deps;
+pub mod diagnostics;
+pub mod doc;
 pub mod error;
+pub mod format;
 pub mod hashtags;
+pub mod lsp;
 pub mod parse;
 pub mod reef;
 pub mod sandbox;
+pub mod suite;
 pub mod util;
 
 mod flags {
@@ -43,20 +56,123 @@ mod flags {
                 optional --print-ast
                 /// Skip C compilation
                 optional -n, --nobuild
+                /// How to print errors: `human` (default) or `json`
+                optional --error-format error_format: String
+                /// Allows implicit conversions between numbers and pointers, like C
+                optional --loose
+                /// Also writes a C header for the program to this path
+                optional --header header: PathBuf
+                /// Compiles every module again instead of reusing cached object files
+                optional --no-cache
+                /// Writes all of the C code to `.sea/build/output.c` and compiles it in one go
+                optional --single-file
+            }
+            cmd build b {
+                /// Executes the program after compilation
+                optional -r, --run
+                /// Arguments to pass to the program, only applies when --run is passed
+                repeated -a, --arg arg: String
+                /// Toggles optimizations for production builds
+                optional -p, --prod
+                /// The C compiler to build with, overrides `cc` in build.reef
+                optional -c, --cc cc: String
+                /// How to print errors: `human` (default) or `json`
+                optional --error-format error_format: String
+                /// Compiles every module again instead of reusing cached object files
+                optional --no-cache
+                /// Writes all of the C code to `.sea/build/output.c` and compiles it in one go
+                optional --single-file
+            }
+            cmd check {
+                /// The file to check
+                required input: PathBuf
+                /// Paths to search for libraries
+                repeated -l, --libpaths libpaths: String
+                /// Path to the standard library
+                optional -s, --std std: String
+                /// Disables implicit `use std`
+                optional -S, --nostd
+                /// How to print errors: `human` (default) or `json`
+                optional --error-format error_format: String
+                /// Allows implicit conversions between numbers and pointers, like C
+                optional --loose
+            }
+            cmd clean {}
+            cmd deps {
+                /// Checks that every dependency matches sea.lock, exiting with an error if not
+                optional --verify
+                /// Resolves every dependency again and updates sea.lock
+                optional --refresh
             }
             cmd sandbox s {
                 /// Path to the standard library
                 optional -s, --std std: String
+                /// How to print errors: `human` (default) or `json`
+                optional --error-format error_format: String
+            }
+            cmd fmt {
+                /// The files to format, directories are searched for `.sea` files
+                repeated files: PathBuf
+                /// Reports unformatted files instead of formatting them
+                optional --check
+            }
+            cmd doc {
+                /// The module to document, i.e, `std`
+                required module: PathBuf
+                /// The directory to write the pages to
+                optional -o, --output output: PathBuf
+                /// Path to the standard library
+                optional -s, --std std: String
+                /// Paths to search for libraries
+                repeated -l, --libpaths libpaths: String
+                /// Includes private names and `_internal` packages
+                optional --private
+            }
+            cmd bindgen {
+                /// The C header to generate bindings for
+                required header: PathBuf
+                /// The file to write the bindings to, they're printed if this isn't given
+                optional -o, --output output: PathBuf
+                /// The package to put the bindings in, defaults to the header's name
+                optional -p, --pkg pkg: String
+                /// What the bindings `#include`, defaults to the header's file name
+                optional -i, --include include: String
+            }
+            cmd test-suite {
+                /// The tests to run, directories are searched for `.sea` files
+                repeated tests: PathBuf
+                /// Writes what happened to each test's `.expected` file
+                optional --bless
+                /// The C compiler to build tests with
+                optional -c, --cc cc: String
+                /// Path to the standard library
+                optional -s, --std std: String
+                /// Paths to search for libraries
+                repeated -l, --libpaths libpaths: String
+            }
+            cmd lsp {
+                /// Path to the standard library
+                optional -s, --std std: String
+                /// Paths to search for libraries
+                repeated -l, --libpaths libpaths: String
             }
         }
     }
 }
 
 fn throw(msg: &str) -> ! {
-    println!("\x1b[1;31merror:\x1b[0m {msg}");
+    eprintln!("\x1b[1;31merror:\x1b[0m {msg}");
     exit(1);
 }
 
+fn get_error_format(format: Option<String>) -> ErrorFormat {
+    match format {
+        Some(it) => ErrorFormat::from_str(&it)
+            .unwrap_or_else(|_| throw(&format!("unknown error format: `{it}`"))),
+        None => ErrorFormat::Human,
+    }
+}
+
 fn get_cc(is_prod: bool) -> String {
     if is_prod {
         "gcc".to_string()
@@ -72,6 +188,8 @@ fn compile(flags: flags::Compile) {
         .output
         .unwrap_or_else(|| PathBuf::from(".sea/build/main"));
 
+    let error_format = get_error_format(flags.error_format);
+
     let mut libpaths: Vec<PathBuf> = vec![];
     let stdpath = PathBuf::from(flags.std.unwrap_or_else(|| "~/.sea/std/".to_string()));
     libpaths.push(stdpath);
@@ -79,13 +197,17 @@ fn compile(flags: flags::Compile) {
         libpaths.push(PathBuf::from(it))
     }
 
-    println!("\x1b[35m: Compiling Sea\x1b[0m");
+    if error_format == ErrorFormat::Human {
+        println!("\x1b[35m: Compiling Sea\x1b[0m");
+    }
 
     // Load the source code
-    let code = fs::read_to_string(path()).unwrap();
+    let code = fs::read_to_string(path())
+        .unwrap_or_else(|why| throw(&format!("failed to read {:?}: {why}", flags.input)));
 
     // Parse
-    let mut parser = Parser::new(Lexer::new(path(), &code));
+    let diagnostics = Diagnostics::new();
+    let mut parser = Parser::new(Lexer::new(path(), &code, diagnostics.clone()));
     let program = parser.parse(!flags.nostd);
 
     if flags.print_ast {
@@ -93,26 +215,74 @@ fn compile(flags: flags::Compile) {
         program.pretty_print();
     }
 
+    // The AST is incomplete if parsing failed, so there's no use in compiling it
+    if diagnostics.has_errors() {
+        diagnostics.print(error_format);
+        exit(1);
+    }
+
     // Make compiler and backend
     let mut compiler = Compiler::new(path(), c_output_path.clone(), libpaths, parser);
 
     // This gets used in C code compilation, I make it now so that the borrow checker doesn't make me cry
     let mut cc_flags: Vec<String> = vec![];
 
-    // Write output C code
-    CBackend::new(&mut compiler).write(program);
+    // Analyze the program, no code gets written if this finds errors
+    let mut analyzer = Analyzer::new(&mut compiler);
+    analyzer.loose = flags.loose;
+    analyzer.prod = flags.prod;
+    let program = analyzer.analyze(program);
+    diagnostics.print(error_format);
+    if diagnostics.has_errors() {
+        exit(1);
+    }
+
+    // Write a header first, since writing code consumes the analyzed modules
+    if let Some(header) = flags.header {
+        let imports = compiler.imports.clone();
+        let mut backend = CBackend::header(&mut compiler, &header)
+            .unwrap_or_else(|why| throw(&format!("failed to write {header:?}: {why}")));
+        backend.write_header(program.clone(), &header);
+        compiler.imports = imports;
+    }
 
-    // Compile C code
-    if !flags.nobuild {
-        cc_flags.extend_from_slice(&compiler.cc_flags);
-        cc_flags.extend_from_slice(&flags.ccflags);
+    cc_flags.extend_from_slice(&compiler.cc_flags);
+    cc_flags.extend_from_slice(&flags.ccflags);
+    let cc = flags.cc.unwrap_or(get_cc(flags.prod));
 
-        let compile_res = compile::run_compile_cmds(
-            c_output_path,
-            executable_path.clone(),
-            flags.cc.unwrap_or(get_cc(flags.prod)),
-            cc_flags,
-        );
+    // One file is easier to distribute, but it's compiled from scratch every time
+    if flags.single_file || flags.nobuild {
+        CBackend::new(&mut compiler)
+            .unwrap_or_else(|why| throw(&format!("failed to write {c_output_path:?}: {why}")))
+            .write(program);
+        if !flags.nobuild {
+            let compile_res =
+                compile::run_compile_cmds(c_output_path, executable_path.clone(), cc, cc_flags);
+
+            if compile_res.is_err() {
+                throw(compile_res.err().unwrap().as_str());
+            }
+        }
+    } else {
+        let units = CBackend::write_units(&mut compiler, program, Path::new(".sea/build"))
+            .unwrap_or_else(|why| throw(&format!("failed to write C code: {why}")));
+
+        // Each module is compiled on its own, so unchanged ones can be reused
+        let mut cache =
+            compile::cache::Cache::new(PathBuf::from(compile::cache::DIR), cc.clone(), &cc_flags);
+        cache.rebuild = flags.no_cache;
+        let objects = cache
+            .objects(&units, Path::new(".sea/build/prelude.h"))
+            .unwrap_or_else(|why| throw(&why));
+        if cache.reused > 0 && error_format == ErrorFormat::Human {
+            println!(
+                "\x1b[35m: Reused {} of {} cached modules\x1b[0m",
+                cache.reused,
+                objects.len()
+            );
+        }
+
+        let compile_res = compile::run_link_cmds(objects, executable_path.clone(), cc, cc_flags);
 
         if compile_res.is_err() {
             throw(compile_res.err().unwrap().as_str());
@@ -128,19 +298,450 @@ fn compile(flags: flags::Compile) {
     }
 }
 
+// Like `compile`, but it stops after analysis, so nothing is written and no C
+// compiler is needed.
+fn check(flags: flags::Check) {
+    let error_format = get_error_format(flags.error_format);
+
+    let mut libpaths: Vec<PathBuf> = vec![];
+    let stdpath = PathBuf::from(flags.std.unwrap_or_else(|| "~/.sea/std/".to_string()));
+    libpaths.push(stdpath);
+    for it in flags.libpaths {
+        libpaths.push(PathBuf::from(it))
+    }
+
+    let code = fs::read_to_string(&flags.input)
+        .unwrap_or_else(|why| throw(&format!("failed to read {:?}: {why}", flags.input)));
+
+    let diagnostics = Diagnostics::new();
+    let mut parser = Parser::new(Lexer::new(flags.input.clone(), &code, diagnostics.clone()));
+    let program = parser.parse(!flags.nostd);
+    if diagnostics.has_errors() {
+        diagnostics.print(error_format);
+        exit(1);
+    }
+
+    let mut compiler = Compiler::new(
+        flags.input.clone(),
+        PathBuf::from(".sea/build/output.c"),
+        libpaths,
+        parser,
+    );
+    let mut analyzer = Analyzer::new(&mut compiler);
+    analyzer.loose = flags.loose;
+    analyzer.analyze(program);
+    diagnostics.print(error_format);
+    if diagnostics.has_errors() {
+        exit(1);
+    }
+    if error_format == ErrorFormat::Human {
+        println!("\x1b[35m: Checked {}\x1b[0m", flags.input.to_string_lossy());
+    }
+}
+
+// Finds and reads the nearest `build.reef`, then enters its directory, since
+// everything in the manifest is relative to it (and so are `.sea/build` and
+// `.sea/deps`).
+fn load_manifest(error_format: ErrorFormat) -> (PathBuf, reef::manifest::Manifest) {
+    let cwd = std::env::current_dir()
+        .unwrap_or_else(|why| throw(&format!("failed to get the current directory: {why}")));
+    let Some(path) = reef::manifest::Manifest::find(&cwd) else {
+        throw(&format!(
+            "no {} in {} or its parent directories",
+            reef::manifest::FILE_NAME,
+            cwd.to_string_lossy()
+        ));
+    };
+    let code = fs::read_to_string(&path)
+        .unwrap_or_else(|why| throw(&format!("failed to read {path:?}: {why}")));
+
+    let diagnostics = Diagnostics::new();
+    let manifest = reef::manifest::Manifest::parse(&path, &code, &diagnostics);
+    if diagnostics.has_errors() {
+        diagnostics.print(error_format);
+        exit(1);
+    }
+
+    std::env::set_current_dir(&manifest.dir)
+        .unwrap_or_else(|why| throw(&format!("failed to enter {:?}: {why}", manifest.dir)));
+    (path, manifest)
+}
+
+fn load_deps(manifest: &reef::manifest::Manifest, error_format: ErrorFormat) -> deps::Deps {
+    let path = Path::new(deps::LOCK_FILE);
+    let diagnostics = Diagnostics::new();
+    let lock = deps::Lock::read(path, &diagnostics)
+        .unwrap_or_else(|why| throw(&format!("failed to read {path:?}: {why}")));
+    if diagnostics.has_errors() {
+        diagnostics.print(error_format);
+        exit(1);
+    }
+    deps::Deps::new(manifest.deps.clone(), lock)
+}
+
+fn write_lock(deps: &deps::Deps) {
+    let path = Path::new(deps::LOCK_FILE);
+    deps.lock
+        .write(path)
+        .unwrap_or_else(|why| throw(&format!("failed to write {path:?}: {why}")));
+}
+
+fn build(flags: flags::Build) {
+    let error_format = get_error_format(flags.error_format.clone());
+    let (path, manifest) = load_manifest(error_format);
+    if !manifest.entry.is_file() {
+        throw(&format!(
+            "no such file: {:?}, set `entry` in {}",
+            manifest.entry,
+            reef::manifest::FILE_NAME
+        ));
+    }
+    let output_dir = manifest.output.as_ref().and_then(|it| it.parent());
+    for dir in [Some(Path::new(".sea/build")), output_dir].into_iter().flatten() {
+        fs::create_dir_all(dir)
+            .unwrap_or_else(|why| throw(&format!("failed to create {dir:?}: {why}")));
+    }
+
+    if !manifest.deps.is_empty() || Path::new(deps::LOCK_FILE).exists() {
+        let mut deps = load_deps(&manifest, error_format);
+        let resolved = deps.sync().unwrap_or_else(|why| throw(&why));
+        write_lock(&deps);
+        if error_format == ErrorFormat::Human {
+            for name in resolved {
+                println!("\x1b[35m: Resolved {name}\x1b[0m");
+            }
+        }
+    }
+
+    if error_format == ErrorFormat::Human {
+        println!("\x1b[35m: Building {}\x1b[0m", path.to_string_lossy());
+    }
+    compile(flags::Compile {
+        input: manifest.entry.clone(),
+        output: manifest.output.clone(),
+        run: flags.run,
+        arg: flags.arg,
+        prod: flags.prod,
+        cc: flags.cc.or(manifest.cc.clone()),
+        ccflags: manifest.cc_flags(),
+        libpaths: manifest.libpaths.clone(),
+        std: manifest.std.clone(),
+        nostd: manifest.nostd,
+        print_ast: false,
+        nobuild: false,
+        error_format: flags.error_format,
+        loose: false,
+        header: None,
+        no_cache: flags.no_cache,
+        single_file: flags.single_file,
+    });
+}
+
+// Removes the build and the cached modules of the project, or of the current
+// directory if it isn't in one.
+fn clean() {
+    let dir = std::env::current_dir()
+        .ok()
+        .and_then(|it| reef::manifest::Manifest::find(&it))
+        .and_then(|it| it.parent().map(|it| it.to_path_buf()))
+        .unwrap_or(PathBuf::from("."));
+    for it in [".sea/build", compile::cache::DIR] {
+        let path = dir.join(it);
+        if !path.exists() {
+            continue;
+        }
+        fs::remove_dir_all(&path)
+            .unwrap_or_else(|why| throw(&format!("failed to remove {path:?}: {why}")));
+        println!("\x1b[35m: Removed {}\x1b[0m", path.to_string_lossy());
+    }
+}
+
+fn deps(flags: flags::Deps) {
+    let (_, manifest) = load_manifest(ErrorFormat::Human);
+    if manifest.deps.is_empty() {
+        println!("no dependencies in {}", reef::manifest::FILE_NAME);
+        return;
+    }
+    let mut deps = load_deps(&manifest, ErrorFormat::Human);
+
+    if flags.refresh {
+        deps.refresh().unwrap_or_else(|why| throw(&why));
+        write_lock(&deps);
+    }
+
+    let width = deps.deps.iter().map(|it| it.name.len()).max().unwrap_or(0);
+    let mut failed = 0;
+    for dep in &deps.deps {
+        let status = deps
+            .status(dep)
+            .unwrap_or_else(|why| throw(&format!("failed to check `{}`: {why}", dep.name)));
+        let hash = deps
+            .lock
+            .get(dep)
+            .map(|it| it.hash.get(..12).unwrap_or(&it.hash))
+            .unwrap_or("-");
+        let color = if status == deps::Status::Ok { 32 } else { 31 };
+        println!(
+            "{:width$}  {hash:12}  {}  \x1b[{color}m{}\x1b[0m",
+            dep.name,
+            dep.source,
+            status.describe()
+        );
+        if status != deps::Status::Ok {
+            failed += 1;
+        }
+    }
+
+    if flags.verify && failed > 0 {
+        throw(&format!(
+            "{failed} dependencies don't match {}, run `sea deps --refresh` to resolve them again",
+            deps::LOCK_FILE
+        ));
+    }
+}
+
 fn sandbox(flags: flags::Sandbox) {
     let mut sandbox = sandbox::Sandbox::new();
+    sandbox.error_format = get_error_format(flags.error_format);
     sandbox.libpaths.push(PathBuf::from(
         flags.std.unwrap_or_else(|| "~/.sea/std/".to_string()),
     ));
     sandbox.start();
 }
 
+// Gets every `.sea` file in the given paths, searching directories recursively.
+fn find_sea_files(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = vec![];
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path);
+            continue;
+        }
+
+        let mut entries: Vec<PathBuf> = fs::read_dir(&path)
+            .unwrap_or_else(|why| throw(&format!("failed to read {path:?}: {why}")))
+            .filter_map(|it| it.ok().map(|it| it.path()))
+            .filter(|it| it.is_dir() || it.extension().is_some_and(|extension| extension == "sea"))
+            .collect();
+        entries.sort();
+        files.extend(find_sea_files(entries));
+    }
+    files
+}
+
+fn fmt(flags: flags::Fmt) {
+    if flags.files.is_empty() {
+        throw("no files to format");
+    }
+
+    let mut failed = false;
+    let mut unformatted = 0;
+    for path in find_sea_files(flags.files) {
+        let code = fs::read_to_string(&path)
+            .unwrap_or_else(|why| throw(&format!("failed to read {path:?}: {why}")));
+
+        let diagnostics = Diagnostics::new();
+        let Some(formatted) = format::format(path.clone(), &code, diagnostics.clone()) else {
+            diagnostics.print(ErrorFormat::Human);
+            failed = true;
+            continue;
+        };
+        if formatted == code {
+            continue;
+        }
+
+        let name = path.to_string_lossy();
+        if flags.check {
+            unformatted += 1;
+            let diff =
+                format::diff::unified(&name, &code, &format!("{name} (formatted)"), &formatted);
+            if diff.is_empty() {
+                println!(
+                    "\x1b[1m{name}:\x1b[0m line endings or the final newline are not formatted"
+                );
+            } else {
+                print!("{diff}");
+            }
+        } else {
+            fs::write(&path, formatted)
+                .unwrap_or_else(|why| throw(&format!("failed to write {path:?}: {why}")));
+            println!("\x1b[35m: Formatted {name}\x1b[0m");
+        }
+    }
+
+    if unformatted > 0 {
+        println!(
+            "\x1b[1;31merror:\x1b[0m {unformatted} file{} not formatted, run `sea fmt` to fix",
+            if unformatted == 1 { " is" } else { "s are" }
+        );
+    }
+    if failed || unformatted > 0 {
+        exit(1);
+    }
+}
+
+fn doc(flags: flags::Doc) {
+    let output = flags.output.unwrap_or_else(|| PathBuf::from(".sea/doc"));
+
+    let mut libpaths: Vec<PathBuf> = vec![];
+    libpaths.push(PathBuf::from(
+        flags.std.unwrap_or_else(|| "~/.sea/std/".to_string()),
+    ));
+    for it in flags.libpaths {
+        libpaths.push(PathBuf::from(it))
+    }
+
+    if !flags.module.is_dir() {
+        throw(&format!("no such module: {:?}", flags.module));
+    }
+
+    println!("\x1b[35m: Documenting {}\x1b[0m", flags.module.to_string_lossy());
+
+    let diagnostics = Diagnostics::new();
+    let mut generator = doc::DocGenerator::new(diagnostics.clone());
+    generator.private = flags.private;
+    for path in find_sea_files(vec![flags.module.clone()]) {
+        let code = fs::read_to_string(&path)
+            .unwrap_or_else(|why| throw(&format!("failed to read {path:?}: {why}")));
+        generator.add_file(path, &code);
+    }
+    if diagnostics.has_errors() {
+        diagnostics.print(ErrorFormat::Human);
+        exit(1);
+    }
+
+    // Links are resolved against the analyzed module, so it has to be valid
+    let symbols = doc::analyze(&flags.module, libpaths, diagnostics.clone());
+    if diagnostics.has_errors() {
+        diagnostics.print(ErrorFormat::Human);
+        exit(1);
+    }
+
+    let title = flags
+        .module
+        .file_name()
+        .map_or_else(|| flags.module.to_string_lossy(), |it| it.to_string_lossy())
+        .to_string();
+    let packages = generator
+        .write(&symbols, &title, &output)
+        .unwrap_or_else(|why| throw(&format!("failed to write {output:?}: {why}")));
+    diagnostics.print(ErrorFormat::Human);
+    println!(
+        "\x1b[35m: Documented {packages} package{} in {}\x1b[0m",
+        if packages == 1 { "" } else { "s" },
+        output.to_string_lossy()
+    );
+}
+
+fn bindgen(flags: flags::Bindgen) {
+    let source = fs::read_to_string(&flags.header)
+        .unwrap_or_else(|why| throw(&format!("failed to read {:?}: {why}", flags.header)));
+    let file_name = flags
+        .header
+        .file_name()
+        .map_or_else(String::new, |it| it.to_string_lossy().to_string());
+
+    let package = flags
+        .pkg
+        .unwrap_or_else(|| bindgen::package_name(&file_name));
+    let include = flags.include.unwrap_or(file_name);
+    let mut generator = bindgen::Bindgen::new(package, include);
+    let bindings = generator.generate(&source);
+
+    let Some(output) = flags.output else {
+        print!("{bindings}");
+        return;
+    };
+    fs::write(&output, bindings)
+        .unwrap_or_else(|why| throw(&format!("failed to write {output:?}: {why}")));
+    println!(
+        "\x1b[35m: Wrote {} binding{} to {} ({} skipped)\x1b[0m",
+        generator.bindings,
+        if generator.bindings == 1 { "" } else { "s" },
+        output.to_string_lossy(),
+        generator.skipped
+    );
+}
+
+fn test_suite(flags: flags::TestSuite) {
+    if flags.tests.is_empty() {
+        throw("no tests to run");
+    }
+
+    let mut suite = suite::Suite::new(
+        flags.cc.unwrap_or(get_cc(false)),
+        flags.std.unwrap_or_else(|| "~/.sea/std/".to_string()),
+        flags.libpaths,
+    )
+    .unwrap_or_else(|why| throw(&format!("failed to set up the test suite: {why}")));
+    suite.bless = flags.bless;
+
+    let (mut passed, mut failed, mut missing, mut blessed) = (0, 0, 0, 0);
+    for test in find_sea_files(flags.tests) {
+        let name = test.to_string_lossy();
+        match suite.run(&test) {
+            Ok(suite::Verdict::Passed) => passed += 1,
+            Ok(suite::Verdict::Failed(diff)) => {
+                failed += 1;
+                println!("\x1b[1;31mFAILED:\x1b[0m {name}");
+                print!("{diff}");
+            }
+            Ok(suite::Verdict::Missing) => {
+                missing += 1;
+                println!("\x1b[1;33mMISSING:\x1b[0m {name} has no expectations, run with --bless");
+            }
+            Ok(suite::Verdict::Blessed) => {
+                blessed += 1;
+                println!("\x1b[35m: Blessed {name}\x1b[0m");
+            }
+            Err(why) => {
+                failed += 1;
+                println!("\x1b[1;31mFAILED:\x1b[0m {name}: {why}");
+            }
+        }
+    }
+
+    println!(
+        "\x1b[35m: {passed} passed, {failed} failed, {missing} missing, {blessed} blessed\x1b[0m"
+    );
+    if failed > 0 || missing > 0 {
+        exit(1);
+    }
+}
+
+fn lsp(flags: flags::Lsp) {
+    let mut libpaths: Vec<PathBuf> = vec![];
+    libpaths.push(PathBuf::from(
+        flags.std.unwrap_or_else(|| "~/.sea/std/".to_string()),
+    ));
+    for it in flags.libpaths {
+        libpaths.push(PathBuf::from(it))
+    }
+
+    let mut server = lsp::Server::new(libpaths);
+    match server.run(&mut io::stdin().lock(), &mut io::stdout().lock()) {
+        Ok(code) => exit(code),
+        Err(why) => {
+            eprintln!("sea lsp: {why}");
+            exit(1);
+        }
+    }
+}
+
 fn main() {
     let flags = flags::Sea::from_env_or_exit();
 
     match flags.subcommand {
         flags::SeaCmd::Compile(args) => compile(args),
+        flags::SeaCmd::Build(args) => build(args),
+        flags::SeaCmd::Check(args) => check(args),
+        flags::SeaCmd::Clean(_) => clean(),
+        flags::SeaCmd::Deps(args) => deps(args),
         flags::SeaCmd::Sandbox(args) => sandbox(args),
+        flags::SeaCmd::Fmt(args) => fmt(args),
+        flags::SeaCmd::Doc(args) => doc(args),
+        flags::SeaCmd::Bindgen(args) => bindgen(args),
+        flags::SeaCmd::TestSuite(args) => test_suite(args),
+        flags::SeaCmd::Lsp(args) => lsp(args),
     }
 }
@@ -1,5 +1,7 @@
 use std::{fmt, path::PathBuf};
 
+use strum::{EnumString, IntoStaticStr};
+
 use crate::hashtags;
 
 use super::operator::OperatorKind;
@@ -21,14 +23,43 @@ impl Node {
     }
 }
 
+// A function's name, parameters, and return type.
+pub type FunSignature = (String, Vec<(String, Node)>, Node);
+
+// What a contract on a function is about, i.e, `arg` in `@arg(n > 0)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, IntoStaticStr)]
+pub enum ContractKind {
+    // A condition that the arguments must meet
+    #[strum(serialize = "arg")]
+    Arg,
+    // A condition that the return value, called `ret`, must meet
+    #[strum(serialize = "ret")]
+    Ret,
+    // The globals that the function writes to
+    #[strum(serialize = "mut")]
+    Mut,
+}
+
 #[derive(Debug, Clone)]
 pub enum NodeKind {
     Program(Vec<Node>),
     Raw(String),
+    // Stands in for code that failed to parse, so that the rest of the file can
+    // still be parsed and checked.
+    Error,
+    // `@arg(<cond>)`, `@ret(<cond>)`, or `@mut(<globals>)`, which go between a
+    // function's signature and its body. `text` is the contract as written, for
+    // reporting it when it fails.
+    Contract {
+        kind: ContractKind,
+        params: Vec<Node>,
+        text: String,
+    },
     Type {
         pointers: u8,
         name: String,
         arrays: Vec<(Option<usize>, Option<String>)>,
+        generics: Vec<Node>, // type arguments, i.e, `i32` in `List[i32]`
         funptr_args: Option<Vec<Node>>,
         funptr_rets: Option<Box<Node>>,
     },
@@ -43,6 +74,7 @@ pub enum NodeKind {
         id: String,
         params: Vec<(String, Node)>,
         rets: Box<Node>,
+        contracts: Vec<Node>,
         expr: Box<Node>,
     },
     TopRec {
@@ -69,6 +101,26 @@ pub enum NodeKind {
         id: String,
         params: Vec<Node>,
     },
+    // Each method is its name, parameters, and return type. The first parameter
+    // is always `^Self`.
+    TopTrait {
+        id: String,
+        methods: Vec<FunSignature>,
+    },
+    TopImpl {
+        trait_: String,
+        rec: String,
+        funs: Vec<Node>,
+    },
+    // A `fun`, `rec`, `def`, or `tag rec` with type parameters, which is only
+    // analyzed and written for each set of type arguments that it's used with.
+    TopGeneric {
+        params: Vec<String>,
+        node: Box<Node>,
+    },
+    // An analyzed copy of a generic declaration for some type arguments, whose
+    // name is already complete, i.e, `ds'List[i32]`.
+    TopInstance(Box<Node>),
     // Statements
     StatRet(Option<Box<Node>>),
     StatIf {
@@ -97,7 +149,12 @@ pub enum NodeKind {
         to: Box<Node>,
         expr: Box<Node>,
     },
-    // StatForIn {},
+    StatForOf {
+        index: Option<String>,
+        var: String,
+        iter: Box<Node>,
+        expr: Box<Node>,
+    },
     StatContinue,
     StatBreak,
     StatDefer(Box<Node>),
@@ -114,6 +171,7 @@ pub enum NodeKind {
     ExprBlock(Vec<Node>),
     ExprNew {
         id: String,
+        generics: Vec<Node>,
         params: Vec<Node>,
     },
     ExprUnaryOperator {
@@ -157,6 +215,7 @@ impl Node {
                 pointers: 0,
                 name: "void".to_string(),
                 arrays: vec![],
+                generics: vec![],
                 funptr_args: None,
                 funptr_rets: None,
             },
@@ -174,4 +233,21 @@ impl Node {
             },
         }
     }
+
+    // `x |> f(a, b)` is `f(x, a, b)`, and `x |> f` is `f(x)`.
+    pub fn pipe(left: Node, right: Node) -> Self {
+        let (callee, mut params) = match right.node {
+            NodeKind::ExprInvoke { left, params } => (left, params),
+            _ => (Box::new(right), vec![]),
+        };
+        params.insert(0, left);
+        Node {
+            line: params[0].line,
+            column: params[0].column,
+            node: NodeKind::ExprInvoke {
+                left: callee,
+                params,
+            },
+        }
+    }
 }
@@ -42,10 +42,19 @@ impl Node {
                 print!("\x1b[0m");
             }
             NodeKind::Raw(code) => println!("raw code: '{TEXT}{code}{RESET}'"),
+            NodeKind::Error => println!("{EXPR}error"),
+            NodeKind::Contract { kind, params, .. } => {
+                let kind: &'static str = kind.into();
+                println!("{TOP_LEVEL_STAT}contract '{TEXT}@{kind}{TOP_LEVEL_STAT}':");
+                for param in params {
+                    param.pretty_print_inner(indent + 1, true);
+                }
+            }
             NodeKind::Type {
                 pointers,
                 name,
                 arrays,
+                generics,
                 funptr_args,
                 funptr_rets,
             } => {
@@ -97,6 +106,10 @@ impl Node {
                         }
                     }
                     println!("");
+                    for generic in generics {
+                        print!("{spacing}  type argument: ");
+                        generic.pretty_print_inner(indent + 1, false);
+                    }
                 }
             }
             NodeKind::TopUse(path_buf) => {
@@ -113,6 +126,7 @@ impl Node {
                 id,
                 params,
                 rets,
+                contracts,
                 expr,
             } => {
                 println!("{TOP_LEVEL_STAT}fun '{TEXT}{id}{TOP_LEVEL_STAT}':");
@@ -130,6 +144,13 @@ impl Node {
                 print!("{TOP_LEVEL_STAT}{spacing}  rets = ");
                 rets.pretty_print_inner(indent, false);
 
+                if !contracts.is_empty() {
+                    println!("{TOP_LEVEL_STAT}{spacing}  contracts:");
+                    for contract in contracts {
+                        contract.pretty_print_inner(indent + 2, true);
+                    }
+                }
+
                 println!("{TOP_LEVEL_STAT}{spacing}  code:");
                 expr.pretty_print_inner(indent + 2, true);
             }
@@ -208,6 +229,38 @@ impl Node {
                     println!("()");
                 }
             }
+            NodeKind::TopTrait { id, methods } => {
+                println!("{TOP_LEVEL_STAT}trait '{TEXT}{id}{TOP_LEVEL_STAT}':");
+                for (method, params, rets) in methods {
+                    println!("{spacing}  - '{TEXT}{method}{TOP_LEVEL_STAT}':");
+                    println!("{spacing}    params:");
+                    for (param_name, param_node) in params {
+                        print!("{TOP_LEVEL_STAT}{spacing}      '{TEXT}{param_name}{TOP_LEVEL_STAT}' = ");
+                        param_node.pretty_print_inner(indent + 3, false);
+                    }
+                    print!("{TOP_LEVEL_STAT}{spacing}    rets = ");
+                    rets.pretty_print_inner(indent + 2, false);
+                }
+            }
+            NodeKind::TopImpl { trait_, rec, funs } => {
+                println!(
+                    "{TOP_LEVEL_STAT}impl '{TEXT}{trait_}{TOP_LEVEL_STAT}' for '{TEXT}{rec}{TOP_LEVEL_STAT}':"
+                );
+                for fun in funs {
+                    fun.pretty_print_inner(indent + 1, true);
+                }
+            }
+            NodeKind::TopGeneric { params, node } => {
+                println!(
+                    "{TOP_LEVEL_STAT}generic with params '{TEXT}{}{TOP_LEVEL_STAT}':",
+                    params.join(", ")
+                );
+                node.pretty_print_inner(indent + 1, true);
+            }
+            NodeKind::TopInstance(node) => {
+                println!("{TOP_LEVEL_STAT}instance:");
+                node.pretty_print_inner(indent + 1, true);
+            }
             NodeKind::StatRet(node) => {
                 println!("{STAT}ret:");
                 node.as_ref().unwrap().pretty_print_inner(indent + 1, true);
@@ -284,6 +337,22 @@ impl Node {
                 println!("{STAT}{spacing}  expr:");
                 expr.pretty_print_inner(indent + 2, true);
             }
+            NodeKind::StatForOf {
+                index,
+                var,
+                iter,
+                expr,
+            } => {
+                println!("{STAT}for (of):");
+                if let Some(it) = index {
+                    println!("{spacing}  index: {it}");
+                }
+                println!("{spacing}  var: {var}");
+                println!("{spacing}  iter: ");
+                iter.pretty_print_inner(indent + 2, true);
+                println!("{STAT}{spacing}  expr:");
+                expr.pretty_print_inner(indent + 2, true);
+            }
             NodeKind::StatContinue => println!("{STAT}continue"),
             NodeKind::StatBreak => println!("{STAT}break"),
             NodeKind::StatDefer(nodes) => {
@@ -311,8 +380,16 @@ impl Node {
                     node.pretty_print_inner(indent + 1, true);
                 }
             }
-            NodeKind::ExprNew { id, params } => {
+            NodeKind::ExprNew {
+                id,
+                generics,
+                params,
+            } => {
                 println!("{EXPR}new: '{TEXT}{id}{EXPR}', params:");
+                for generic in generics {
+                    print!("{spacing}  type argument: ");
+                    generic.pretty_print_inner(indent + 1, false);
+                }
                 for param in params {
                     param.pretty_print_inner(indent + 1, true);
                 }
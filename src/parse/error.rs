@@ -1,8 +1,9 @@
+use strum::IntoStaticStr;
 use thiserror::Error;
 
 use super::token::{Token, TokenKind};
 
-#[derive(Debug, Clone, Error)]
+#[derive(Debug, Clone, Error, IntoStaticStr)]
 pub enum LexErrorKind {
     #[error("unexpected character: `{0}`")]
     UnexpectedCharacter(char),
@@ -27,7 +28,7 @@ pub struct LexError {
     pub token: Token,
 }
 
-#[derive(Debug, Clone, Error)]
+#[derive(Debug, Clone, Error, IntoStaticStr)]
 pub enum ParseError {
     #[error("function pointer type missing parenthesis")]
     FunPtrMissingParenthesis,
@@ -47,12 +48,28 @@ pub enum ParseError {
     #[error("expected statement but got `{}`", .0.text)]
     ExpectedStatement(Token),
 
-    #[error("lexing error: {0}")]
-    LexError(LexErrorKind),
-
     #[error("expected token of kind `{0:?}`")]
     ExpectedTokenOfKind(TokenKind),
 
     #[error("reached EOF before closing brace")]
     ReachedEOFBeforeClosingBrace,
+
+    #[error("unexpected end of file")]
+    UnexpectedEOF,
+
+    #[error("unknown hashtag for `{1}`: `#{0}`")]
+    UnknownHashtag(String, String),
+
+    #[error("duplicate hashtag: `#{0}`")]
+    DuplicateHashtag(String),
+
+    #[error("hashtags `#{0}` and `#{1}` cannot be used together")]
+    ConflictingHashtags(String, String),
+
+    #[error("unknown contract: `@{0}`")]
+    UnknownContract(String),
 }
+
+// Parsing errors are reported to the parser's diagnostics when they are thrown,
+// the `Err` is only used to unwind the parser.
+pub type ParseResult<T> = Result<T, ParseError>;
@@ -1,6 +1,10 @@
 use std::{collections::HashMap, iter::Peekable, path::PathBuf, str::Chars, sync::LazyLock};
 
-use crate::{parse::error::LexError, util};
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics, Severity},
+    parse::error::LexError,
+    util,
+};
 
 use super::{
     error::LexErrorKind,
@@ -16,11 +20,17 @@ pub struct Lexer<'a> {
     pub pos: usize,                // index to the current character
     pub line: usize,               // current line that the lexer is on
     pub prev_token: Token,         // the previous token emitted
+    pub diagnostics: Diagnostics,  // where lexing errors get reported
+    pub trivia: bool,              // whether comments and newlines become tokens, for the formatter
+    pub docs: Docs,                // doc comments, keyed by the position of the token after them
+    doc: Vec<String>,              // doc comments that haven't reached a token yet
     cur: char,                     // current character
     prev: char,                    // previous character
     buffer: String,                // all characters since `start`
 }
 
+pub type Docs = HashMap<(usize, usize), Vec<String>>;
+
 static KEYWORDS: LazyLock<HashMap<&str, TokenKind>> = LazyLock::new(|| {
     HashMap::from([
         ("use", TokenKind::KwUse),
@@ -49,6 +59,9 @@ static KEYWORDS: LazyLock<HashMap<&str, TokenKind>> = LazyLock::new(|| {
         ("switch", TokenKind::KwSwitch),
         ("case", TokenKind::KwCase),
         ("fall", TokenKind::KwFall),
+        ("trait", TokenKind::KwTrait),
+        ("impl", TokenKind::KwImpl),
+        ("dyn", TokenKind::KwDyn),
         ("not", TokenKind::OpNot),
         ("and", TokenKind::OpAnd),
         ("or", TokenKind::OpOr),
@@ -57,6 +70,10 @@ static KEYWORDS: LazyLock<HashMap<&str, TokenKind>> = LazyLock::new(|| {
     ])
 });
 
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains_key(word)
+}
+
 fn is_valid_id_start(ch: char) -> bool {
     ch.is_alphabetic() || ch == '_' || ch == '$'
 }
@@ -66,7 +83,7 @@ fn is_valid_id(ch: char) -> bool {
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(file: PathBuf, code: &'a String) -> Self {
+    pub fn new(file: PathBuf, code: &'a String, diagnostics: Diagnostics) -> Self {
         Lexer {
             file,
             source: code,
@@ -76,6 +93,10 @@ impl<'a> Lexer<'a> {
             pos: 0,
             line: 1,
             prev_token: Default::default(),
+            diagnostics,
+            trivia: false,
+            docs: HashMap::new(),
+            doc: vec![],
             cur: ' ',
             prev: ' ',
             buffer: Default::default(),
@@ -152,6 +173,7 @@ impl<'a> Lexer<'a> {
         loop {
             let ch = self.peek();
             match ch {
+                '\n' if self.trivia => return,
                 '\n' => {
                     self.skip_no_buffer();
                     self.line += 1;
@@ -381,7 +403,7 @@ impl<'a> Lexer<'a> {
             if cur != '[' {
                 Err(self.make_error(LexErrorKind::ExpectedCharacter('[', cur)))
             } else {
-                Ok(self.lex_raw_block().unwrap())
+                self.lex_raw_block().map_err(|it| self.make_error(it))
             }
         } else {
             match cur {
@@ -450,14 +472,14 @@ impl<'a> Lexer<'a> {
                 '*' => Ok(self.make_token(TokenKind::OpMul)),
                 '/' => {
                     if self.peek() == '/' {
-                        while self.peek() != '\n' {
-                            self.skip_no_buffer()
+                        while self.peek() != '\n' && self.peek() != '\0' {
+                            self.skip()
                         }
-                        return self.get_next_token();
+                        self.comment()
                     } else if self.peek() == '*' {
                         let mut depth = 1;
-                        while depth > 0 {
-                            self.skip_no_buffer();
+                        while depth > 0 && !self.is_done() {
+                            self.skip();
                             if self.cur == '\n' {
                                 self.line += 1;
                                 self.column = 1;
@@ -467,14 +489,20 @@ impl<'a> Lexer<'a> {
                                 depth += 1;
                             }
                         }
-                        self.skip_no_buffer(); // Skip the ending `/`
-                        return self.get_next_token();
+                        self.skip(); // Skip the ending `/`
+                        self.comment()
                     } else {
                         Ok(self.make_token(TokenKind::OpDiv))
                     }
                 }
                 '%' => Ok(self.make_token(TokenKind::OpMod)),
-                cur if cur == '|' && self.peek() == '>' => Ok(self.make_token(TokenKind::OpPipe)),
+                '|' => match self.peek() {
+                    '>' => {
+                        self.skip();
+                        Ok(self.make_token(TokenKind::OpPipe))
+                    }
+                    _ => Err(self.make_error(LexErrorKind::UnexpectedCharacter(cur))),
+                },
                 // Literals
                 '"' => self.lex_string(),
                 'c' if self.peek() == '"' => self.lex_c_string(),
@@ -482,16 +510,69 @@ impl<'a> Lexer<'a> {
                 cur if is_valid_id_start(cur) => self.lex_id_or_keyword(),
                 '0'..='9' => self.lex_number(),
                 '\0' => Ok(self.make_token(TokenKind::Eof)),
+                // Only reached when keeping trivia, otherwise newlines are skipped as whitespace
+                '\n' => {
+                    let token = self.make_token(TokenKind::Newline);
+                    self.line += 1;
+                    self.column = 1;
+                    Ok(token)
+                }
                 _ => Err(self.make_error(LexErrorKind::UnexpectedCharacter(cur))),
             }
         }
     }
 
-    pub fn next_token(&mut self) -> Result<Token, LexError> {
-        let tok = self.get_next_token();
-        if tok.as_ref().is_ok() {
-            self.prev_token = tok.as_ref().unwrap().clone();
+    // Comments are only tokens when keeping trivia, otherwise they're skipped,
+    // except that doc comments are remembered for the token after them.
+    fn comment(&mut self) -> Result<Token, LexError> {
+        if self.trivia {
+            return Ok(self.make_token(TokenKind::Comment));
+        }
+
+        let is_doc = (self.buffer.starts_with("///") && !self.buffer.starts_with("////"))
+            || (self.buffer.starts_with("/**") && self.buffer != "/**/");
+        if is_doc {
+            self.doc.push(self.buffer.clone());
+        }
+        self.get_next_token()
+    }
+
+    fn report(&self, error: LexError) {
+        self.diagnostics.report(Diagnostic {
+            severity: Severity::Error,
+            error: error.error.into(),
+            help: None,
+            note: None,
+            file: self.file.clone(),
+            line: error.token.line,
+            column: error.token.column,
+            len: error.token.len,
+            lines: self.get_lines(error.token.line),
+        });
+    }
+
+    // Gets the next token, reporting and skipping over any lexing errors.
+    pub fn next_token(&mut self) -> Token {
+        loop {
+            match self.get_next_token() {
+                Ok(tok) => {
+                    // Trivia isn't remembered so that `raw` can still find its block
+                    if !matches!(tok.kind, TokenKind::Comment | TokenKind::Newline) {
+                        self.prev_token = tok.clone();
+                    }
+                    if !self.doc.is_empty() {
+                        self.docs
+                            .insert((tok.line, tok.column), std::mem::take(&mut self.doc));
+                    }
+                    return tok;
+                }
+                Err(err) => {
+                    // Replace the previous token so that we don't get stuck
+                    // trying to lex the same `raw` block forever.
+                    self.prev_token = err.token.clone();
+                    self.report(err);
+                }
+            }
         }
-        tok
     }
 }
@@ -29,6 +29,7 @@ pub enum OperatorKind {
     Negate,
     Index,
     Invoke,
+    Pipe,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -56,12 +57,19 @@ pub enum Associativity {
 
 pub const OPERATORS: LazyLock<HashMap<TokenKind, Operator>> = LazyLock::new(|| {
     HashMap::from([
-        // . as
+        // .
         TokenKind::OpDot.li(1000), // expr.expr
-        TokenKind::KwAs.ri(1000),  // expr as type
-        // and or
-        TokenKind::OpAnd.li(500), // expr and expr
-        TokenKind::OpOr.li(500),  // expr or expr
+        // as
+        TokenKind::KwAs.li(900), // expr as type
+        // * / %
+        TokenKind::OpMul.li(600), // expr * expr
+        TokenKind::OpDiv.li(600), // expr / expr
+        TokenKind::OpMod.li(600), // expr % expr
+        // + -
+        TokenKind::OpAdd.li(500), // expr + expr
+        TokenKind::OpSub.li(500), // expr - expr
+        // |>
+        TokenKind::OpPipe.li(450), // expr |> expr
         // > >= < <=
         TokenKind::OpGt.li(400),   // expr > expr
         TokenKind::OpGtEq.li(400), // expr >= expr
@@ -70,13 +78,10 @@ pub const OPERATORS: LazyLock<HashMap<TokenKind, Operator>> = LazyLock::new(|| {
         // == !=
         TokenKind::OpEq.li(300),  // expr == expr
         TokenKind::OpNeq.li(300), // expr != expr
-        // * / %
-        TokenKind::OpMul.li(200), // expr * expr
-        TokenKind::OpDiv.li(200), // expr / expr
-        TokenKind::OpMod.li(200), // expr % expr
-        // + -
-        TokenKind::OpAdd.ri(100), // expr + expr
-        TokenKind::OpSub.ri(100), // expr - expr
+        // and
+        TokenKind::OpAnd.li(200), // expr and expr
+        // or
+        TokenKind::OpOr.li(100), // expr or expr
         // =
         TokenKind::Eq.ri(0), // expr = expr
     ])
@@ -113,6 +118,7 @@ impl TokenKind {
             TokenKind::OpNeq => OperatorKind::Neq,
             TokenKind::OpAnd => OperatorKind::And,
             TokenKind::OpOr => OperatorKind::Or,
+            TokenKind::OpPipe => OperatorKind::Pipe,
             TokenKind::OpNot => OperatorKind::Not,
             // TokenKind::OpSub => OperatorKind::Negate,
             TokenKind::KwRef => OperatorKind::Ref,
@@ -1,13 +1,16 @@
-use std::{fmt::Debug, path::PathBuf, process::exit, str::FromStr};
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
 
 use crate::{
-    hashtags::{DefTags, FunTags, RecTags, TagRecTags, TagTags},
+    compile::traits,
+    diagnostics::{Diagnostic, Severity},
+    hashtags::{DefTags, FunTags, Hashtag, RecTags, TagRecTags, TagTags},
     parse::operator::{Associativity, Precedence},
+    util,
 };
 
 use super::{
-    ast::{Node, NodeKind},
-    error::ParseError,
+    ast::{ContractKind, FunSignature, Node, NodeKind},
+    error::{ParseError, ParseResult},
     lexer,
     operator::OperatorKind,
     token::{Token, TokenKind},
@@ -18,6 +21,7 @@ pub struct Parser<'a> {
     pub token: Token, // current token
     pub prev: Token,  // previous token
     pub done: bool,
+    pub docs: lexer::Docs, // doc comments of top level statements, keyed by their position
 }
 
 impl<'a> Parser<'a> {
@@ -27,58 +31,41 @@ impl<'a> Parser<'a> {
             token: Default::default(),
             prev: Default::default(),
             done: false,
+            docs: HashMap::new(),
         }
     }
 
-    fn throw_exception_at(&self, error: ParseError, help: Option<&str>, token: Token) -> ! {
-        let line = token.line;
-
-        println!(
-            "\x1b[31;1m{}:{}:{}:\x1b[0;1m {error}\x1b[0m",
-            self.lexer.file.to_str().unwrap(),
-            token.line,
-            token.column
-        );
-
-        let lines = self.lexer.get_lines(line);
-        if lines.len() == 0 {
-            println!("No line information available :(");
-            println!("This error shouldn't happen, please report it.");
-            println!("Debug: token={}", token.clone())
-        } else {
-            // Determine the longest integer by digit so that we can make our error prettier.
-            let longest_length = lines.iter().map(|it| it.0).max().unwrap().to_string().len();
-
-            for (line_index, line_str) in lines {
-                // We replace `\t` with ` ` so that no matter the terminal indentation, the underline will be aligned
-                let indents = line_str.chars().filter(|it| *it == '\t').count();
-                let sanitized = line_str.replace('\t', "    ");
-
-                println!("\x1b[1;34m{line_index:>longest_length$} | \x1b[0m{sanitized}");
-                if line_index == token.line {
-                    // Determine the column that the token is on to highlight it
-                    println!(
-                        "\x1b[1;34m{} | {}\x1b[31m{}\x1b[0m",
-                        " ".repeat(longest_length),
-                        " ".repeat(token.column - 1 - indents + (indents * 4)),
-                        "~".repeat(token.len)
-                    );
-                }
-            }
-        }
-
-        if let Some(help) = help {
-            println!("\x1b[1;32mhelp:\x1b[0m {}", help);
-        }
+    // Reports the error and returns it so that callers can unwind with `?`.
+    fn throw_exception_at<T>(
+        &self,
+        error: ParseError,
+        help: Option<&str>,
+        token: Token,
+    ) -> ParseResult<T> {
+        self.report_at(error.clone(), help, &token);
+        Err(error)
+    }
 
-        exit(1)
+    // Reports the error without unwinding, for errors that we can parse past.
+    fn report_at(&self, error: ParseError, help: Option<&str>, token: &Token) {
+        self.lexer.diagnostics.report(Diagnostic {
+            severity: Severity::Error,
+            error: error.clone().into(),
+            help: help.map(|it| it.to_string()),
+            note: None,
+            file: self.lexer.file.clone(),
+            line: token.line,
+            column: token.column,
+            len: token.len,
+            lines: self.lexer.get_lines(token.line),
+        });
     }
 
-    fn throw_exception(&self, error: ParseError, help: Option<&str>) -> ! {
+    fn throw_exception<T>(&self, error: ParseError, help: Option<&str>) -> ParseResult<T> {
         self.throw_exception_at(error, help, self.token.clone())
     }
 
-    fn throw_exception_at_prev(&self, error: ParseError, help: Option<&str>) -> ! {
+    fn throw_exception_at_prev<T>(&self, error: ParseError, help: Option<&str>) -> ParseResult<T> {
         self.throw_exception_at(error, help, self.prev.clone())
     }
 
@@ -87,17 +74,13 @@ impl<'a> Parser<'a> {
     pub fn advance(&mut self) -> bool {
         self.prev = self.token.clone();
 
-        match self.lexer.next_token() {
-            Ok(it) => match it.kind {
-                TokenKind::Eof => {
-                    self.done = true;
-                    return false;
-                }
-                _ => self.token = it,
-            },
-            Err(err) => {
-                self.throw_exception_at(ParseError::LexError(err.error), None, err.token);
+        let it = self.lexer.next_token();
+        match it.kind {
+            TokenKind::Eof => {
+                self.done = true;
+                return false;
             }
+            _ => self.token = it,
         }
 
         return true;
@@ -112,19 +95,107 @@ impl<'a> Parser<'a> {
         }
     }
 
-    pub fn expect(&mut self, kind: TokenKind, msg: &str) {
-        if self.token.kind == kind {
+    pub fn expect(&mut self, kind: TokenKind, msg: &str) -> ParseResult<()> {
+        if self.done {
+            self.throw_exception(ParseError::UnexpectedEOF, Some(msg))
+        } else if self.token.kind == kind {
             self.advance();
+            Ok(())
         } else {
-            self.throw_exception(ParseError::ExpectedToken(self.token.clone()), Some(msg));
+            self.throw_exception(ParseError::ExpectedToken(self.token.clone()), Some(msg))
         }
     }
 
     // #endregion: Token Utilities
 
+    // #region: Error Recovery
+
+    fn starts_top_level_statement(kind: TokenKind) -> bool {
+        matches!(
+            kind,
+            TokenKind::KwFun
+                | TokenKind::KwRec
+                | TokenKind::KwTag
+                | TokenKind::KwDef
+                | TokenKind::KwPkg
+                | TokenKind::KwUse
+                | TokenKind::KwPragma
+                | TokenKind::KwTrait
+                | TokenKind::KwImpl
+                | TokenKind::Hashtag
+        )
+    }
+
+    fn starts_statement(kind: TokenKind) -> bool {
+        matches!(
+            kind,
+            TokenKind::KwRet
+                | TokenKind::KwIf
+                | TokenKind::KwSwitch
+                | TokenKind::KwFor
+                | TokenKind::KwContinue
+                | TokenKind::KwBreak
+                | TokenKind::KwDefer
+                | TokenKind::KwRaw
+                | TokenKind::KwVar
+                | TokenKind::KwLet
+        )
+    }
+
+    // Skips tokens until the start of the next top level statement. Inside of a
+    // `pkg` we also stop at its closing curly bracket.
+    fn synchronize_top_level(&mut self, in_pkg: bool) {
+        let mut depth = 0;
+        while !self.done {
+            match self.token.kind {
+                kind if Parser::starts_top_level_statement(kind) => return,
+                TokenKind::OpenCurly => depth += 1,
+                TokenKind::CloseCurly if depth > 0 => depth -= 1,
+                TokenKind::CloseCurly if in_pkg => return,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    // Skips tokens until the start of the next statement in the current block,
+    // which is a statement keyword, a new line, or the block's closing curly
+    // bracket. Returns false if we stopped at a top level statement instead, in
+    // which case the block is unrecoverable.
+    fn synchronize_statement(&mut self) -> bool {
+        let line = self.token.line;
+        let mut depth = 0;
+        while !self.done {
+            match self.token.kind {
+                kind if Parser::starts_top_level_statement(kind) => return false,
+                TokenKind::OpenCurly => depth += 1,
+                TokenKind::CloseCurly if depth > 0 => depth -= 1,
+                TokenKind::CloseCurly => return true,
+                kind if depth == 0
+                    && (Parser::starts_statement(kind) || self.token.line != line) =>
+                {
+                    return true
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+        true
+    }
+
+    fn error_node(&self) -> Node {
+        Node {
+            line: self.token.line,
+            column: self.token.column,
+            node: NodeKind::Error,
+        }
+    }
+
+    // #endregion: Error Recovery
+
     // #region: Misc Parsing
 
-    pub fn parse_type(&mut self) -> Node {
+    pub fn parse_type(&mut self) -> ParseResult<Node> {
         let line = self.token.line;
         let column = self.token.column;
 
@@ -134,6 +205,7 @@ impl<'a> Parser<'a> {
         }
 
         let name: String;
+        let mut generics: Vec<Node> = vec![];
         let mut funptr_args: Option<Vec<Node>> = None;
         let mut funptr_rets: Option<Box<Node>> = None;
 
@@ -143,10 +215,10 @@ impl<'a> Parser<'a> {
             self.expect(
                 TokenKind::OpenParen,
                 "expected open parenthesis after `fun` type",
-            );
+            )?;
             if self.token.kind != TokenKind::CloseParen {
                 loop {
-                    let it = self.parse_type();
+                    let it = self.parse_type()?;
                     funptr_args_vec.push(it);
                     if !self.accept(TokenKind::Comma) {
                         break;
@@ -156,30 +228,57 @@ impl<'a> Parser<'a> {
             self.expect(
                 TokenKind::CloseParen,
                 "expected closing parenthesis for funptr",
-            );
+            )?;
             funptr_args = Some(funptr_args_vec);
 
             if self.accept(TokenKind::Colon) {
-                funptr_rets = Some(Box::new(self.parse_type().clone()));
+                funptr_rets = Some(Box::new(self.parse_type()?));
             } else {
                 funptr_rets = Some(Box::new(Node::get_void_type(line, column)));
             }
+        } else if self.accept(TokenKind::KwDyn) {
+            // `dyn Shape` is a rec that analysis declares for the trait
+            self.expect(
+                TokenKind::Identifier,
+                "expected trait identifier after `dyn`",
+            )?;
+            name = traits::dyn_name(&self.prev.text);
         } else {
-            self.expect(TokenKind::Identifier, "expected type identifier");
+            self.expect(TokenKind::Identifier, "expected type identifier")?;
             name = self.prev.text.clone();
         }
 
         let mut arrays: Vec<(Option<usize>, Option<String>)> = vec![];
         while self.accept(TokenKind::OpenBracket) {
             if funptr_rets.is_some() {
-                self.throw_exception(ParseError::FunPtrWithArrays, None);
+                return self.throw_exception(ParseError::FunPtrWithArrays, None);
             }
 
             // `int[5]`
             if self.accept(TokenKind::Int) {
                 arrays.push((Some(self.prev.text.parse::<usize>().unwrap()), None))
             }
-            // `int[size]`
+            // `List[i32]`, which is also how `int[size]` starts, so analysis makes
+            // that an array again
+            else if funptr_args.is_none()
+                && traits::dyn_trait(&name).is_none()
+                && arrays.is_empty()
+                && generics.is_empty()
+                && self.token.kind != TokenKind::CloseBracket
+            {
+                loop {
+                    generics.push(self.parse_type()?);
+                    if !self.accept(TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.expect(
+                    TokenKind::CloseBracket,
+                    "expected closed bracket after type arguments",
+                )?;
+                continue;
+            }
+            // `int[5][size]`
             else if self.accept(TokenKind::Identifier) {
                 arrays.push((None, Some(self.prev.text.clone())))
             }
@@ -191,53 +290,98 @@ impl<'a> Parser<'a> {
             self.expect(
                 TokenKind::CloseBracket,
                 "expected closed bracket to match open bracket",
-            );
+            )?;
         }
 
-        Node {
+        Ok(Node {
             line,
             column,
             node: NodeKind::Type {
                 pointers,
                 name,
                 arrays,
+                generics,
                 funptr_args,
                 funptr_rets,
             },
-        }
+        })
     }
 
-    pub fn parse_hashtags(&mut self) -> Vec<String> {
+    // Returns the identifier token of each hashtag, so that errors can point at them.
+    pub fn parse_hashtags(&mut self) -> ParseResult<Vec<Token>> {
         if self.accept(TokenKind::OpenParen) {
-            let mut tags: Vec<String> = vec![];
+            let mut tags: Vec<Token> = vec![];
             loop {
-                self.accept(TokenKind::Identifier);
-                tags.push(self.prev.text.clone());
+                self.expect(
+                    TokenKind::Identifier,
+                    "expected identifier in hashtag list",
+                )?;
+                tags.push(self.prev.clone());
                 if self.accept(TokenKind::CloseParen) {
                     break;
                 }
                 self.expect(
                     TokenKind::Comma,
                     "expected comma or closed parenthesis after identifier in hashtag list",
-                )
+                )?
             }
-            tags
+            Ok(tags)
         } else {
             self.expect(
                 TokenKind::Identifier,
                 "expected identifier or parenthesis after hashtag (#)",
-            );
-            vec![self.prev.text.clone()]
+            )?;
+            Ok(vec![self.prev.clone()])
         }
     }
 
-    pub fn cast_hashtags<T: FromStr>(tags: Vec<String>) -> Vec<T>
-    where
-        <T as FromStr>::Err: Debug,
-    {
+    // Converts hashtags to the kind used by the statement, `statement` is its
+    // keyword(s). Unknown, duplicate, and conflicting hashtags are reported and
+    // left out.
+    pub fn cast_hashtags<T: Hashtag>(&self, tags: Vec<Token>, statement: &str) -> Vec<T> {
         let mut casted: Vec<T> = vec![];
-        for tag in tags {
-            casted.push(T::from_str(tag.as_str()).expect("unknown hashtag"));
+        for token in tags {
+            let tag = match T::from_str(&token.text) {
+                Ok(it) => it,
+                Err(_) => {
+                    let valid = T::VARIANTS
+                        .iter()
+                        .map(|it| format!("`#{it}`"))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let help = match util::closest_match(&token.text, T::VARIANTS) {
+                        Some(it) => format!("did you mean `#{it}`? `{statement}` accepts {valid}"),
+                        None => format!("`{statement}` accepts {valid}"),
+                    };
+                    self.report_at(
+                        ParseError::UnknownHashtag(token.text.clone(), statement.to_string()),
+                        Some(&help),
+                        &token,
+                    );
+                    continue;
+                }
+            };
+
+            if casted.contains(&tag) {
+                self.report_at(
+                    ParseError::DuplicateHashtag(token.text.clone()),
+                    None,
+                    &token,
+                );
+                continue;
+            }
+
+            if let Some(other) = casted.iter().find(|it| tag.conflicts_with(**it)) {
+                let other: &'static str = (*other).into();
+                self.report_at(
+                    ParseError::ConflictingHashtags(other.to_string(), token.text.clone()),
+                    None,
+                    &token,
+                );
+                continue;
+            }
+
+            casted.push(tag);
         }
         casted
     }
@@ -246,7 +390,7 @@ impl<'a> Parser<'a> {
 
     // #region: Expressions
 
-    pub fn parse_block(&mut self, advance: bool) -> Node {
+    pub fn parse_block(&mut self, advance: bool) -> ParseResult<Node> {
         if advance {
             self.advance();
         }
@@ -258,94 +402,103 @@ impl<'a> Parser<'a> {
             let start_tok = self.prev.clone();
 
             if self.accept(TokenKind::CloseCurly) {
-                return Node {
+                return Ok(Node {
                     line,
                     column,
                     node: NodeKind::ExprBlock(vec![]),
-                };
+                });
             }
 
             let mut exprs: Vec<Node> = vec![];
             loop {
-                exprs.push(self.parse_statement());
-                if self.accept(TokenKind::CloseCurly) {
-                    break;
-                } else if self.done {
-                    self.throw_exception_at(
+                if self.done {
+                    return self.throw_exception_at(
                         ParseError::ReachedEOFBeforeClosingBrace,
                         None,
                         start_tok,
                     );
                 }
+                match self.parse_statement() {
+                    Ok(it) => exprs.push(it),
+                    Err(err) => {
+                        exprs.push(self.error_node());
+                        if !self.synchronize_statement() {
+                            return Err(err);
+                        }
+                    }
+                }
+                if self.accept(TokenKind::CloseCurly) {
+                    break;
+                }
             }
-            Node {
+            Ok(Node {
                 line,
                 column,
                 node: NodeKind::ExprBlock(exprs),
-            }
+            })
         } else if self.prev.kind == TokenKind::Arrow {
-            Node {
+            Ok(Node {
                 line,
                 column,
-                node: NodeKind::ExprBlock(vec![self.parse_statement()]),
-            }
+                node: NodeKind::ExprBlock(vec![self.parse_statement()?]),
+            })
         } else {
             self.throw_exception_at_prev(ParseError::UnexpectedToken(self.prev.clone()), None)
         }
     }
 
-    pub fn parse_let(&mut self) -> Node {
+    pub fn parse_let(&mut self) -> ParseResult<Node> {
         let line = self.prev.line;
         let column = self.prev.column;
 
-        self.expect(TokenKind::Identifier, "expected identifier after `let`");
+        self.expect(TokenKind::Identifier, "expected identifier after `let`")?;
 
         let name = self.prev.text.clone();
 
         let typ: Option<Box<Node>> = if self.accept(TokenKind::Colon) {
-            Some(Box::new(self.parse_type()))
+            Some(Box::new(self.parse_type()?))
         } else {
             None
         };
 
-        self.expect(TokenKind::Eq, "expected `=` after `let <id>`");
+        self.expect(TokenKind::Eq, "expected `=` after `let <id>`")?;
 
-        let value = Box::new(self.parse_expression());
+        let value = Box::new(self.parse_expression()?);
 
-        return Node {
+        return Ok(Node {
             line,
             column,
             node: NodeKind::ExprLet { name, typ, value },
-        };
+        });
     }
 
-    pub fn parse_var(&mut self) -> Node {
+    pub fn parse_var(&mut self) -> ParseResult<Node> {
         let line = self.prev.line;
         let column = self.prev.column;
 
-        self.expect(TokenKind::Identifier, "expected identifier after `var`");
+        self.expect(TokenKind::Identifier, "expected identifier after `var`")?;
 
         let name = self.prev.text.clone();
 
         let typ: Option<Box<Node>> = if self.accept(TokenKind::Colon) {
-            Some(Box::new(self.parse_type()))
+            Some(Box::new(self.parse_type()?))
         } else {
             None
         };
 
-        self.expect(TokenKind::Eq, "expected `=` after `var <id>`");
+        self.expect(TokenKind::Eq, "expected `=` after `var <id>`")?;
 
-        let value = Box::new(self.parse_expression());
+        let value = Box::new(self.parse_expression()?);
 
-        return Node {
+        return Ok(Node {
             line,
             column,
             node: NodeKind::ExprVar { name, typ, value },
-        };
+        });
     }
 
     // Parses *non operator* expressions.
-    pub fn parse_atom(&mut self) -> Node {
+    pub fn parse_atom(&mut self) -> ParseResult<Node> {
         let line = self.token.line;
         let column = self.token.column;
 
@@ -353,11 +506,11 @@ impl<'a> Parser<'a> {
 
         let atom = match *self {
             _ if self.accept(TokenKind::OpenParen) => {
-                let node = n(NodeKind::ExprGroup(Box::new(self.parse_expression())));
+                let node = n(NodeKind::ExprGroup(Box::new(self.parse_expression()?)));
                 self.expect(
                     TokenKind::CloseParen,
                     "expected closed parenthesis to match open parenthesis in expression group",
-                );
+                )?;
                 node
             }
 
@@ -376,6 +529,8 @@ impl<'a> Parser<'a> {
             _ if self.accept(TokenKind::Identifier) => {
                 n(NodeKind::ExprIdentifier(self.prev.text.clone()))
             }
+            // The return value, in `@ret` contracts
+            _ if self.accept(TokenKind::KwRet) => n(NodeKind::ExprIdentifier("ret".to_string())),
 
             // List
             _ if self.accept(TokenKind::OpenBracket) => {
@@ -385,7 +540,7 @@ impl<'a> Parser<'a> {
                     let mut nodes: Vec<Node> = vec![];
 
                     loop {
-                        nodes.push(self.parse_expression());
+                        nodes.push(self.parse_expression()?);
 
                         if !self.accept(TokenKind::Comma) {
                             break;
@@ -395,29 +550,44 @@ impl<'a> Parser<'a> {
                     self.expect(
                         TokenKind::CloseBracket,
                         "expected closed bracket (`]`) to end list expression.",
-                    );
+                    )?;
 
                     n(NodeKind::ExprList(nodes))
                 }
             }
 
             // Blocks
-            _ if self.accept(TokenKind::OpenCurly) => self.parse_block(false),
-            _ if self.accept(TokenKind::Arrow) => self.parse_block(false),
+            _ if self.accept(TokenKind::OpenCurly) => self.parse_block(false)?,
+            _ if self.accept(TokenKind::Arrow) => self.parse_block(false)?,
 
             // Misc
             _ if self.accept(TokenKind::KwNew) => {
-                self.expect(TokenKind::Identifier, "expected identifier after `new`");
+                self.expect(TokenKind::Identifier, "expected identifier after `new`")?;
                 let id = self.prev.text.clone();
+                let mut generics: Vec<Node> = vec![];
                 let mut params: Vec<Node> = vec![];
 
+                // `new List[i32](...)`, they're inferred from the parameters otherwise
+                if self.accept(TokenKind::OpenBracket) {
+                    loop {
+                        generics.push(self.parse_type()?);
+                        if !self.accept(TokenKind::Comma) {
+                            break;
+                        }
+                    }
+                    self.expect(
+                        TokenKind::CloseBracket,
+                        "expected closed bracket after type arguments",
+                    )?;
+                }
+
                 self.expect(
                     TokenKind::OpenParen,
                     "expected open parenthesis in `new` expression",
-                );
+                )?;
                 if self.token.kind != TokenKind::CloseParen {
                     loop {
-                        params.push(self.parse_expression());
+                        params.push(self.parse_expression()?);
                         if !self.accept(TokenKind::Comma) {
                             break;
                         }
@@ -426,32 +596,38 @@ impl<'a> Parser<'a> {
                 self.expect(
                     TokenKind::CloseParen,
                     "expected closed parenthesis to end `new` expression",
-                );
+                )?;
 
-                n(NodeKind::ExprNew { id, params })
+                n(NodeKind::ExprNew {
+                    id,
+                    generics,
+                    params,
+                })
             }
 
             // Prefix unary operators
             _ if self.accept(TokenKind::OpNot) => n(NodeKind::ExprUnaryOperator {
                 kind: OperatorKind::Not,
-                value: Box::new(self.parse_atom()),
+                value: Box::new(self.parse_atom()?),
             }),
             _ if self.accept(TokenKind::OpSub) => n(NodeKind::ExprUnaryOperator {
                 kind: OperatorKind::Negate,
-                value: Box::new(self.parse_atom()),
+                value: Box::new(self.parse_atom()?),
             }),
             _ if self.accept(TokenKind::KwRef) => n(NodeKind::ExprUnaryOperator {
                 kind: OperatorKind::Ref,
-                value: Box::new(self.parse_atom()),
+                value: Box::new(self.parse_atom()?),
             }),
 
-            _ => self.throw_exception(ParseError::ExpectedExpression(self.token.clone()), None),
+            _ => {
+                return self.throw_exception(ParseError::ExpectedExpression(self.token.clone()), None)
+            }
         };
 
         self.parse_postfix(atom)
     }
 
-    pub fn parse_postfix(&mut self, node: Node) -> Node {
+    pub fn parse_postfix(&mut self, node: Node) -> ParseResult<Node> {
         let line = node.line;
         let column = node.column;
         let n = |node| Node { line, column, node };
@@ -473,7 +649,7 @@ impl<'a> Parser<'a> {
                 } else {
                     let mut params: Vec<Node> = vec![];
                     loop {
-                        params.push(self.parse_expression());
+                        params.push(self.parse_expression()?);
 
                         if !self.accept(TokenKind::Comma) {
                             break;
@@ -482,39 +658,44 @@ impl<'a> Parser<'a> {
                     self.expect(
                         TokenKind::CloseParen,
                         "expected closed parenthesis to end parameter list",
-                    );
+                    )?;
                     atom = n(NodeKind::ExprInvoke {
                         left: Box::new(atom),
                         params,
                     })
                 }
             } else if self.accept(TokenKind::OpenBracket) {
-                let right = self.parse_expression();
+                let mut right = self.parse_expression()?;
+                // `pair[i32, f32]`, the type arguments of a generic function
+                if self.token.kind == TokenKind::Comma {
+                    let mut items = vec![right];
+                    while self.accept(TokenKind::Comma) {
+                        items.push(self.parse_expression()?);
+                    }
+                    right = n(NodeKind::ExprList(items));
+                }
                 self.expect(
                     TokenKind::CloseBracket,
                     "expected closed bracket (`]`) to end index operator",
-                );
+                )?;
                 atom = n(NodeKind::ExprBinaryOperator {
                     kind: OperatorKind::Index,
                     left: Box::new(atom),
                     right: Box::new(right),
                 });
-            } else if self.accept(TokenKind::KwAs) {
-                let typ = self.parse_type();
-                atom = n(NodeKind::ExprBinaryOperator {
-                    kind: OperatorKind::As,
-                    left: Box::new(atom),
-                    right: Box::new(typ),
-                })
             } else {
                 break;
             }
         }
 
-        atom
+        Ok(atom)
     }
 
-    pub fn parse_expression_inner(&mut self, left_node: Node, min_prec: Precedence) -> Node {
+    pub fn parse_expression_inner(
+        &mut self,
+        left_node: Node,
+        min_prec: Precedence,
+    ) -> ParseResult<Node> {
         // https://en.wikipedia.org/wiki/Operator-precedence_parser#Pseudocode
         let mut left_atom = left_node.clone();
         let mut lookahead = self.token.clone();
@@ -524,9 +705,9 @@ impl<'a> Parser<'a> {
             let op = lookahead_op;
             self.advance();
             let mut right_atom = if op.kind == OperatorKind::As {
-                self.parse_type()
+                self.parse_type()?
             } else {
-                self.parse_atom()
+                self.parse_atom()?
             };
 
             lookahead = self.token.clone();
@@ -537,14 +718,10 @@ impl<'a> Parser<'a> {
                         || (lookahead_op.assoc == Associativity::Right
                             && lookahead_op.prec == op.prec))
                 {
-                    if lookahead_op.kind == OperatorKind::As {
-                        right_atom = self.parse_type();
-                    } else {
-                        right_atom = self.parse_expression_inner(
-                            right_atom,
-                            op.prec + if lookahead_op.prec > op.prec { 1 } else { 0 },
-                        );
-                    }
+                    right_atom = self.parse_expression_inner(
+                        right_atom,
+                        op.prec + if lookahead_op.prec > op.prec { 1 } else { 0 },
+                    )?;
                     lookahead = self.token.clone();
                     if lookahead.kind.is_operator() {
                         lookahead_op = lookahead.kind.get_operator().expect("expected operator");
@@ -554,21 +731,25 @@ impl<'a> Parser<'a> {
                 }
             }
 
-            left_atom = Node::join(op.kind, left_atom, right_atom);
+            left_atom = if op.kind == OperatorKind::Pipe {
+                Node::pipe(left_atom, right_atom)
+            } else {
+                Node::join(op.kind, left_atom, right_atom)
+            };
         }
 
         self.parse_postfix(left_atom)
     }
 
-    pub fn parse_expression(&mut self) -> Node {
+    pub fn parse_expression(&mut self) -> ParseResult<Node> {
         if self.accept(TokenKind::KwLet) {
             self.parse_let()
         } else if self.accept(TokenKind::KwVar) {
             self.parse_var()
         } else {
-            let left = self.parse_atom();
+            let left = self.parse_atom()?;
             let it = if self.token.kind.is_operator() {
-                self.parse_expression_inner(left, 0)
+                self.parse_expression_inner(left, 0)?
             } else {
                 left
             };
@@ -580,31 +761,31 @@ impl<'a> Parser<'a> {
 
     // #region: Statements
 
-    pub fn parse_ret(&mut self) -> Node {
-        Node {
+    pub fn parse_ret(&mut self) -> ParseResult<Node> {
+        Ok(Node {
             line: self.prev.line,
             column: self.prev.column,
-            node: NodeKind::StatRet(Some(Box::new(self.parse_expression()))),
-        }
+            node: NodeKind::StatRet(Some(Box::new(self.parse_expression()?))),
+        })
     }
 
-    pub fn parse_if(&mut self) -> Node {
+    pub fn parse_if(&mut self) -> ParseResult<Node> {
         let line = self.token.line;
         let column = self.token.column;
 
-        let cond = self.parse_expression();
-        let expr = self.parse_block(true);
+        let cond = self.parse_expression()?;
+        let expr = self.parse_block(true)?;
         let mut else_: Option<Box<Node>> = None;
         if self.accept(TokenKind::KwElse) {
             // the only statement allowed after `else` is another `if` statement, otherwise it must be a block
             if self.accept(TokenKind::KwIf) {
-                else_ = Some(Box::new(self.parse_if()));
+                else_ = Some(Box::new(self.parse_if()?));
             } else {
-                else_ = Some(Box::new(self.parse_block(true)));
+                else_ = Some(Box::new(self.parse_block(true)?));
             }
         }
 
-        Node {
+        Ok(Node {
             line,
             column,
             node: NodeKind::StatIf {
@@ -612,18 +793,18 @@ impl<'a> Parser<'a> {
                 expr: Box::new(expr),
                 else_,
             },
-        }
+        })
     }
 
-    pub fn parse_switch(&mut self) -> Node {
+    pub fn parse_switch(&mut self) -> ParseResult<Node> {
         let line = self.token.line;
         let column = self.token.column;
 
-        let switch = Box::new(self.parse_expression());
+        let switch = Box::new(self.parse_expression()?);
         self.expect(
             TokenKind::OpenCurly,
             "expected open curly brace (`{`) after `switch <expression>`",
-        );
+        )?;
 
         let mut cases: Vec<(Option<Box<Node>>, bool, Box<Node>)> = vec![];
         loop {
@@ -631,27 +812,27 @@ impl<'a> Parser<'a> {
 
             if self.accept(TokenKind::KwElse) {
                 if is_fall_case {
-                    self.throw_exception(
+                    return self.throw_exception(
                         ParseError::UnexpectedToken(self.prev.clone()),
                         Some("`fall` cannot be used with `else`"),
-                    )
+                    );
                 }
 
-                let block = self.parse_block(true);
+                let block = self.parse_block(true)?;
                 cases.push((None, is_fall_case, Box::new(block)));
 
                 if !self.accept(TokenKind::CloseCurly) {
-                    self.throw_exception(
+                    return self.throw_exception(
                         ParseError::UnexpectedToken(self.prev.clone()),
                         Some("`else` must be the last case of a `switch` statement."),
-                    )
+                    );
                 }
 
                 break;
             } else {
-                self.expect(TokenKind::KwCase, "expected `case`");
-                let case = Some(Box::new(self.parse_expression()));
-                let block = self.parse_block(true);
+                self.expect(TokenKind::KwCase, "expected `case`")?;
+                let case = Some(Box::new(self.parse_expression()?));
+                let block = self.parse_block(true)?;
                 cases.push((case, is_fall_case, Box::new(block)));
 
                 if self.accept(TokenKind::CloseCurly) {
@@ -660,27 +841,27 @@ impl<'a> Parser<'a> {
             }
         }
 
-        Node {
+        Ok(Node {
             line,
             column,
             node: NodeKind::StatSwitch { switch, cases },
-        }
+        })
     }
 
-    pub fn parse_for(&mut self) -> Node {
+    pub fn parse_for(&mut self) -> ParseResult<Node> {
         let line = self.token.line;
         let column = self.token.column;
 
-        let leftmost_expr = self.parse_expression();
+        let leftmost_expr = self.parse_expression()?;
 
         // c style for loop
         if self.accept(TokenKind::Semicolon) {
-            let cond = self.parse_expression();
-            self.expect(TokenKind::Semicolon, "C-style for loops require three expressions, separated by semicolons (i.e, `for <expr> ; <expr> ; <expr>`)");
-            let inc = self.parse_expression();
-            let expr = self.parse_block(true);
+            let cond = self.parse_expression()?;
+            self.expect(TokenKind::Semicolon, "C-style for loops require three expressions, separated by semicolons (i.e, `for <expr> ; <expr> ; <expr>`)")?;
+            let inc = self.parse_expression()?;
+            let expr = self.parse_block(true)?;
 
-            Node {
+            Ok(Node {
                 line,
                 column,
                 node: NodeKind::StatForCStyle {
@@ -689,19 +870,51 @@ impl<'a> Parser<'a> {
                     inc: Box::new(inc),
                     expr: Box::new(expr),
                 },
-            }
+            })
         }
         // single expr for loop
         else if self.accept(TokenKind::OpenCurly) || self.accept(TokenKind::Arrow) {
-            let expr = self.parse_block(false);
-            Node {
+            let expr = self.parse_block(false)?;
+            Ok(Node {
                 line,
                 column,
                 node: NodeKind::StatForSingleExpr {
                     cond: Box::new(leftmost_expr),
                     expr: Box::new(expr),
                 },
-            }
+            })
+        }
+        // for (<id>,)? <id> of <expr>
+        else if let (NodeKind::ExprIdentifier(first), TokenKind::Comma | TokenKind::KwOf) =
+            (&leftmost_expr.node, self.token.kind)
+        {
+            let first = first.clone();
+            let (index, var) = if self.accept(TokenKind::Comma) {
+                self.expect(
+                    TokenKind::Identifier,
+                    "expected the element's name after the index in a for of loop",
+                )?;
+                (Some(first), self.prev.text.clone())
+            } else {
+                (None, first)
+            };
+            self.expect(
+                TokenKind::KwOf,
+                "For of loop syntax: `for (<id>,)? <id> of <expr>`",
+            )?;
+            let iter = self.parse_expression()?;
+            let expr = self.parse_block(true)?;
+
+            Ok(Node {
+                line,
+                column,
+                node: NodeKind::StatForOf {
+                    index,
+                    var,
+                    iter: Box::new(iter),
+                    expr: Box::new(expr),
+                },
+            })
         }
         // range for loop
         else {
@@ -710,15 +923,15 @@ impl<'a> Parser<'a> {
 
             // for <id> in <expr> to <expr>
             if self.accept(TokenKind::KwIn) {
-                let from = self.parse_expression();
+                let from = self.parse_expression()?;
                 self.expect(
                     TokenKind::KwTo,
                     "Range for loop syntax: `for (<id> in)? <expr> to <expr>`",
-                );
-                let to = self.parse_expression();
-                let expr = self.parse_block(true);
+                )?;
+                let to = self.parse_expression()?;
+                let expr = self.parse_block(true)?;
 
-                Node {
+                Ok(Node {
                     line,
                     column,
                     node: NodeKind::StatForRange {
@@ -727,18 +940,18 @@ impl<'a> Parser<'a> {
                         to: Box::new(to),
                         expr: Box::new(expr),
                     },
-                }
+                })
             }
             // for <expr> to <expr>
             else {
                 self.expect(
                     TokenKind::KwTo,
                     "Range for loop syntax: `for (<id> in)? <expr> to <expr>`",
-                );
-                let to = self.parse_atom();
-                let expr = self.parse_block(true);
+                )?;
+                let to = self.parse_atom()?;
+                let expr = self.parse_block(true)?;
 
-                Node {
+                Ok(Node {
                     line,
                     column,
                     node: NodeKind::StatForRange {
@@ -747,41 +960,41 @@ impl<'a> Parser<'a> {
                         to: Box::new(to),
                         expr: Box::new(expr),
                     },
-                }
+                })
             }
         }
     }
 
-    pub fn parse_continue(&mut self) -> Node {
-        Node {
+    pub fn parse_continue(&mut self) -> ParseResult<Node> {
+        Ok(Node {
             line: self.token.line,
             column: self.token.column,
             node: NodeKind::StatContinue,
-        }
+        })
     }
 
-    pub fn parse_break(&mut self) -> Node {
-        Node {
+    pub fn parse_break(&mut self) -> ParseResult<Node> {
+        Ok(Node {
             line: self.token.line,
             column: self.token.column,
             node: NodeKind::StatBreak,
-        }
+        })
     }
 
-    pub fn parse_defer(&mut self) -> Node {
+    pub fn parse_defer(&mut self) -> ParseResult<Node> {
         let line = self.token.line;
         let column = self.token.column;
 
-        let code = self.parse_block(true);
+        let code = self.parse_block(true)?;
 
-        Node {
+        Ok(Node {
             line,
             column,
             node: NodeKind::StatDefer(Box::new(code)),
-        }
+        })
     }
 
-    fn parse_raw(&mut self) -> Node {
+    fn parse_raw(&mut self) -> ParseResult<Node> {
         let line = self.token.line;
         let column = self.token.column;
 
@@ -789,17 +1002,17 @@ impl<'a> Parser<'a> {
         self.expect(
             TokenKind::LiteralText,
             "internal error, literal text within raw[] block not found.",
-        );
+        )?;
         let text = self.prev.text.clone();
 
-        Node {
+        Ok(Node {
             line,
             column,
             node: NodeKind::Raw(text),
-        }
+        })
     }
 
-    pub fn parse_statement(&mut self) -> Node {
+    pub fn parse_statement(&mut self) -> ParseResult<Node> {
         match *self {
             _ if self.accept(TokenKind::KwRet) => self.parse_ret(),
             _ if self.accept(TokenKind::KwIf) => self.parse_if(),
@@ -811,12 +1024,12 @@ impl<'a> Parser<'a> {
             _ if self.accept(TokenKind::KwRaw) => self.parse_raw(),
             // if nothing works, we'll try to parse an expression, and if *that* doesn't work, then we have a syntax error
             _ => {
-                let expr = self.parse_expression();
-                Node {
+                let expr = self.parse_expression()?;
+                Ok(Node {
                     line: expr.line,
                     column: expr.column,
                     node: NodeKind::StatExpr(Box::new(expr)),
-                }
+                })
             }
         }
     }
@@ -825,33 +1038,33 @@ impl<'a> Parser<'a> {
 
     // #region: Top level statements
 
-    pub fn parse_use(&mut self) -> Node {
+    pub fn parse_use(&mut self) -> ParseResult<Node> {
         let line = self.prev.line;
         let column = self.prev.column;
 
         let mut path = PathBuf::new();
-        self.expect(TokenKind::Identifier, "expected identifier after `use`");
+        self.expect(TokenKind::Identifier, "expected identifier after `use`")?;
         path.push(self.prev.text.clone());
         while self.accept(TokenKind::OpDiv) {
             self.expect(
                 TokenKind::Identifier,
                 "expected identifier after `/` in `use`",
-            );
+            )?;
             path.push(self.prev.text.clone());
         }
 
-        Node {
+        Ok(Node {
             line,
             column,
             node: NodeKind::TopUse(path),
-        }
+        })
     }
 
-    pub fn parse_pkg(&mut self) -> Node {
+    pub fn parse_pkg(&mut self) -> ParseResult<Node> {
         let line = self.prev.line;
         let column = self.prev.column;
 
-        self.expect(TokenKind::Identifier, "expected identifier after `pkg`");
+        self.expect(TokenKind::Identifier, "expected identifier after `pkg`")?;
         let name = self.prev.text.clone();
         // while self.accept(TokenKind::OpPkg) {
         //     self.expect(TokenKind::Identifier, "expected identifier after `::`");
@@ -862,37 +1075,150 @@ impl<'a> Parser<'a> {
         self.expect(
             TokenKind::OpenCurly,
             "expected open curly bracket after pkg name",
-        );
+        )?;
+        let start_tok = self.prev.clone();
         while !self.accept(TokenKind::CloseCurly) {
-            statements.push(self.parse_top_level_statement())
+            if self.done {
+                return self.throw_exception_at(
+                    ParseError::ReachedEOFBeforeClosingBrace,
+                    None,
+                    start_tok,
+                );
+            }
+            match self.parse_top_level_statement() {
+                Ok(it) => statements.push(it),
+                Err(_) => {
+                    statements.push(self.error_node());
+                    self.synchronize_top_level(true);
+                }
+            }
         }
 
-        Node {
+        Ok(Node {
             line,
             column,
             node: NodeKind::TopPkg { name, statements },
+        })
+    }
+
+    pub fn parse_fun(&mut self, tags: Vec<FunTags>) -> ParseResult<Node> {
+        let line = self.prev.line;
+        let column = self.prev.column;
+
+        self.expect(TokenKind::Identifier, "expected identifier after `fun`")?;
+        let id = self.prev.text.clone();
+        let generics = self.parse_generic_params()?;
+        let (id, params, rets) = self.parse_fun_params(id, line, column)?;
+        let mut contracts: Vec<Node> = vec![];
+        while self.accept(TokenKind::At) {
+            contracts.push(self.parse_contract()?);
+        }
+
+        // `#extern` functions are defined in C, so they don't have a body
+        let expr = if tags.contains(&FunTags::Extern) {
+            Box::new(Node {
+                line,
+                column,
+                node: NodeKind::ExprBlock(vec![]),
+            })
+        } else {
+            Box::new(self.parse_block(true)?)
+        };
+
+        Ok(Parser::generic(
+            generics,
+            Node {
+                line,
+                column,
+                node: NodeKind::TopFun {
+                    tags,
+                    id,
+                    params,
+                    rets: Box::new(rets),
+                    contracts,
+                    expr,
+                },
+            },
+        ))
+    }
+
+    // Parses a contract after its `@`, i.e, `arg(n > 0)`. `@arg` and `@ret`
+    // take one condition, while `@mut` takes any number of globals.
+    fn parse_contract(&mut self) -> ParseResult<Node> {
+        let line = self.prev.line;
+        let column = self.prev.column;
+        let start = self.prev.start;
+
+        // `ret` is a keyword, so it isn't lexed as an identifier
+        if !self.accept(TokenKind::KwRet) {
+            self.expect(
+                TokenKind::Identifier,
+                "expected `arg`, `ret`, or `mut` after `@`",
+            )?;
+        }
+        let Ok(kind) = ContractKind::from_str(&self.prev.text) else {
+            return self.throw_exception_at_prev(
+                ParseError::UnknownContract(self.prev.text.clone()),
+                Some("expected `arg`, `ret`, or `mut`"),
+            );
+        };
+
+        self.expect(
+            TokenKind::OpenParen,
+            "expected open parenthesis after contract",
+        )?;
+        let mut params: Vec<Node> = vec![self.parse_expression()?];
+        while kind == ContractKind::Mut && self.accept(TokenKind::Comma) {
+            params.push(self.parse_expression()?);
         }
+        self.expect(
+            TokenKind::CloseParen,
+            "expected closed parenthesis after contract",
+        )?;
+
+        let end = self.prev.start + self.prev.len;
+        Ok(Node {
+            line,
+            column,
+            node: NodeKind::Contract {
+                kind,
+                params,
+                text: self.lexer.source[start..end].to_string(),
+            },
+        })
     }
 
-    pub fn parse_fun(&mut self, tags: Vec<FunTags>) -> Node {
+    // Parses `<id>(<params>): <type>`, which comes after `fun`.
+    fn parse_fun_signature(&mut self) -> ParseResult<FunSignature> {
         let line = self.prev.line;
         let column = self.prev.column;
 
-        self.expect(TokenKind::Identifier, "expected identifier after `fun`");
+        self.expect(TokenKind::Identifier, "expected identifier after `fun`")?;
         let id = self.prev.text.clone();
+        self.parse_fun_params(id, line, column)
+    }
+
+    // Parses `(<params>): <type>`, which comes after a function's name. Functions
+    // without a return type return `void`, positioned at `line` and `column`.
+    fn parse_fun_params(
+        &mut self,
+        id: String,
+        line: usize,
+        column: usize,
+    ) -> ParseResult<FunSignature> {
         let mut params: Vec<(String, Node)> = vec![];
 
         self.expect(
             TokenKind::OpenParen,
             "expected open parenthesis after function name",
-        );
+        )?;
         while self.accept(TokenKind::Identifier) {
             let param_id = self.prev.text.clone();
             self.expect(
                 TokenKind::Colon,
                 "expected colon in between parameter ID and its type",
-            );
-            let typ = self.parse_type();
+            )?;
+            let typ = self.parse_type()?;
             params.push((param_id, typ));
             if !self.accept(TokenKind::Comma) {
                 // if there is no comma then we must be on the last parameter
@@ -902,48 +1228,73 @@ impl<'a> Parser<'a> {
         self.expect(
             TokenKind::CloseParen,
             "expected closed parenthesis after function parameter list",
-        );
+        )?;
 
-        let rets: Box<Node> = if self.accept(TokenKind::Colon) {
-            Box::new(self.parse_type())
+        let rets = if self.accept(TokenKind::Colon) {
+            self.parse_type()?
         } else {
-            Box::new(Node::get_void_type(line, column))
+            Node::get_void_type(line, column)
         };
 
-        let expr = Box::new(self.parse_block(true));
+        Ok((id, params, rets))
+    }
+
+    // Parses the type parameters of a declaration, i.e, `[K, V]` in `rec Map[K, V]`,
+    // if it has any.
+    fn parse_generic_params(&mut self) -> ParseResult<Vec<String>> {
+        let mut params: Vec<String> = vec![];
+        if !self.accept(TokenKind::OpenBracket) {
+            return Ok(params);
+        }
+        loop {
+            self.expect(TokenKind::Identifier, "expected type parameter")?;
+            params.push(self.prev.text.clone());
+            if !self.accept(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(
+            TokenKind::CloseBracket,
+            "expected closed bracket after type parameters",
+        )?;
+        Ok(params)
+    }
 
+    // Declarations with type parameters are templates for analysis to instantiate.
+    fn generic(params: Vec<String>, node: Node) -> Node {
+        if params.is_empty() {
+            return node;
+        }
         Node {
-            line,
-            column,
-            node: NodeKind::TopFun {
-                tags,
-                id,
+            line: node.line,
+            column: node.column,
+            node: NodeKind::TopGeneric {
                 params,
-                rets,
-                expr,
+                node: Box::new(node),
             },
         }
     }
 
-    pub fn parse_rec(&mut self, tags: Vec<RecTags>) -> Node {
+    pub fn parse_rec(&mut self, tags: Vec<RecTags>) -> ParseResult<Node> {
         let line = self.prev.line;
         let column = self.prev.column;
 
-        self.expect(TokenKind::Identifier, "expected identifier after `rec`");
+        self.expect(TokenKind::Identifier, "expected identifier after `rec`")?;
         let id = self.prev.text.clone();
+        let generics = self.parse_generic_params()?;
         let mut fields: Vec<(String, Node)> = vec![];
 
         self.expect(
             TokenKind::OpenParen,
             "expected open parenthesis after record name",
-        );
+        )?;
         while self.accept(TokenKind::Identifier) {
             let param_id = self.prev.text.clone();
             self.expect(
                 TokenKind::Colon,
                 "expected colon in between field ID and its type",
-            );
-            let typ = self.parse_type();
+            )?;
+            let typ = self.parse_type()?;
             fields.push((param_id, typ));
             if !self.accept(TokenKind::Comma) {
                 // if there is no comma then we must be on the last field
@@ -953,55 +1304,63 @@ impl<'a> Parser<'a> {
         self.expect(
             TokenKind::CloseParen,
             "expected closed parenthesis after record field list",
-        );
+        )?;
 
-        Node {
-            line,
-            column,
-            node: NodeKind::TopRec { tags, id, fields },
-        }
+        Ok(Parser::generic(
+            generics,
+            Node {
+                line,
+                column,
+                node: NodeKind::TopRec { tags, id, fields },
+            },
+        ))
     }
 
-    fn parse_def(&mut self, tags: Vec<DefTags>) -> Node {
+    fn parse_def(&mut self, tags: Vec<DefTags>) -> ParseResult<Node> {
         let line = self.prev.line;
         let column = self.prev.column;
 
-        self.expect(TokenKind::Identifier, "expected identifier after `def`");
+        self.expect(TokenKind::Identifier, "expected identifier after `def`")?;
         let id = self.prev.text.clone();
-        self.expect(TokenKind::Eq, "expected `=` after `def <identifier>`");
-        let typ = self.parse_type();
+        let generics = self.parse_generic_params()?;
+        self.expect(TokenKind::Eq, "expected `=` after `def <identifier>`")?;
+        let typ = self.parse_type()?;
 
-        Node {
-            line,
-            column,
-            node: NodeKind::TopDef {
-                tags,
-                id,
-                typ: Box::new(typ),
+        Ok(Parser::generic(
+            generics,
+            Node {
+                line,
+                column,
+                node: NodeKind::TopDef {
+                    tags,
+                    id,
+                    typ: Box::new(typ),
+                },
             },
-        }
+        ))
     }
 
-    fn parse_tag(&mut self, tags: Vec<TagTags>) -> Node {
+    fn parse_tag(&mut self, tags: Vec<TagTags>) -> ParseResult<Node> {
         let line = self.prev.line;
         let column = self.prev.column;
 
-        self.expect(TokenKind::Identifier, "expected identifier after `tag`");
+        self.expect(TokenKind::Identifier, "expected identifier after `tag`")?;
         let id = self.prev.text.clone();
         let mut entries: Vec<(String, Option<Box<Node>>)> = vec![];
 
         self.expect(
             TokenKind::OpenParen,
             "expected open parenthesis after tag name",
-        );
+        )?;
         while self.accept(TokenKind::Identifier) {
             let entry_id = self.prev.text.clone();
             if self.accept(TokenKind::Eq) {
-                let atom = self.parse_atom();
+                let atom = self.parse_atom()?;
                 match atom.node {
                     NodeKind::ExprNumber(_) => entries.push((entry_id, Some(Box::new(atom)))),
                     _ => {
-                        self.throw_exception(ParseError::ExpectedTokenOfKind(TokenKind::Int), None)
+                        return self
+                            .throw_exception(ParseError::ExpectedTokenOfKind(TokenKind::Int), None)
                     }
                 }
             } else {
@@ -1012,27 +1371,28 @@ impl<'a> Parser<'a> {
         self.expect(
             TokenKind::CloseParen,
             "expected closed parenthesis after tag entry list",
-        );
+        )?;
 
-        Node {
+        Ok(Node {
             line,
             column,
             node: NodeKind::TopTag { tags, id, entries },
-        }
+        })
     }
 
-    fn parse_tagrec(&mut self, tags: Vec<TagRecTags>) -> Node {
+    fn parse_tagrec(&mut self, tags: Vec<TagRecTags>) -> ParseResult<Node> {
         let line = self.prev.line;
         let column = self.prev.column;
 
-        self.expect(TokenKind::Identifier, "expected identifier after `tag`");
+        self.expect(TokenKind::Identifier, "expected identifier after `tag`")?;
         let id = self.prev.text.clone();
+        let generics = self.parse_generic_params()?;
         let mut entries: Vec<(String, Vec<(String, Node)>)> = vec![];
 
         self.expect(
             TokenKind::OpenParen,
             "expected open parenthesis after tag name",
-        );
+        )?;
         while self.accept(TokenKind::Identifier) {
             let entry_id = self.prev.text.clone();
             let mut entry_entries: Vec<(String, Node)> = vec![];
@@ -1044,8 +1404,8 @@ impl<'a> Parser<'a> {
                     self.expect(
                         TokenKind::Colon,
                         "expected colon in between field ID and its type",
-                    );
-                    let typ = self.parse_type();
+                    )?;
+                    let typ = self.parse_type()?;
                     entry_entries.push((param_id, typ));
                     if !self.accept(TokenKind::Comma) {
                         // if there is no comma then we must be on the last field
@@ -1055,7 +1415,7 @@ impl<'a> Parser<'a> {
                 self.expect(
                     TokenKind::CloseParen,
                     "expected closed parenthesis after `tag rec` entry list",
-                )
+                )?
             }
 
             entries.push((entry_id, entry_entries));
@@ -1064,30 +1424,33 @@ impl<'a> Parser<'a> {
         self.expect(
             TokenKind::CloseParen,
             "expected closed parenthesis after tag entry list",
-        );
+        )?;
 
-        Node {
-            line,
-            column,
-            node: NodeKind::TopTagRec { tags, id, entries },
-        }
+        Ok(Parser::generic(
+            generics,
+            Node {
+                line,
+                column,
+                node: NodeKind::TopTagRec { tags, id, entries },
+            },
+        ))
     }
 
-    pub fn parse_pragma(&mut self) -> Node {
+    pub fn parse_pragma(&mut self) -> ParseResult<Node> {
         let line = self.prev.line;
         let column = self.prev.column;
 
-        self.expect(TokenKind::Identifier, "expected identifier after `pragma`");
+        self.expect(TokenKind::Identifier, "expected identifier after `pragma`")?;
         let id = self.prev.text.clone();
 
         let mut params: Vec<Node> = vec![];
         self.expect(
             TokenKind::OpenParen,
             "expected open parenthesis after pragma identifier",
-        );
+        )?;
         if !self.accept(TokenKind::CloseParen) {
             loop {
-                params.push(self.parse_expression());
+                params.push(self.parse_expression()?);
 
                 if !self.accept(TokenKind::Comma) {
                     break;
@@ -1097,31 +1460,125 @@ impl<'a> Parser<'a> {
         self.expect(
             TokenKind::CloseParen,
             "expected closed parenthesis after pragma argument list",
-        );
+        )?;
 
-        Node {
+        Ok(Node {
             line,
             column,
             node: NodeKind::TopPragma { id, params },
+        })
+    }
+
+    pub fn parse_trait(&mut self) -> ParseResult<Node> {
+        let line = self.prev.line;
+        let column = self.prev.column;
+
+        self.expect(TokenKind::Identifier, "expected identifier after `trait`")?;
+        let id = self.prev.text.clone();
+
+        let mut methods: Vec<FunSignature> = vec![];
+        self.expect(
+            TokenKind::OpenCurly,
+            "expected open curly bracket after trait name",
+        )?;
+        while !self.accept(TokenKind::CloseCurly) {
+            self.expect(
+                TokenKind::KwFun,
+                "traits can only declare functions, i.e, `fun area(self: ^Self): f32`",
+            )?;
+            methods.push(self.parse_fun_signature()?);
+        }
+
+        Ok(Node {
+            line,
+            column,
+            node: NodeKind::TopTrait { id, methods },
+        })
+    }
+
+    pub fn parse_impl(&mut self) -> ParseResult<Node> {
+        let line = self.prev.line;
+        let column = self.prev.column;
+
+        self.expect(
+            TokenKind::Identifier,
+            "expected trait identifier after `impl`",
+        )?;
+        let trait_ = self.prev.text.clone();
+        self.expect(TokenKind::KwFor, "expected `for` after `impl <trait>`")?;
+        self.expect(
+            TokenKind::Identifier,
+            "expected rec identifier after `impl <trait> for`",
+        )?;
+        let rec = self.prev.text.clone();
+
+        let mut funs: Vec<Node> = vec![];
+        self.expect(
+            TokenKind::OpenCurly,
+            "expected open curly bracket after `impl <trait> for <rec>`",
+        )?;
+        let start_tok = self.prev.clone();
+        while !self.accept(TokenKind::CloseCurly) {
+            if self.done {
+                return self.throw_exception_at(
+                    ParseError::ReachedEOFBeforeClosingBrace,
+                    None,
+                    start_tok,
+                );
+            }
+            let fun = if self.accept(TokenKind::Hashtag) {
+                let tags = self.parse_hashtags()?;
+                self.expect(TokenKind::KwFun, "impls can only have functions")
+                    .and_then(|_| self.parse_fun(self.cast_hashtags::<FunTags>(tags, "fun")))
+            } else {
+                self.expect(TokenKind::KwFun, "impls can only have functions")
+                    .and_then(|_| self.parse_fun(vec![]))
+            };
+            match fun {
+                Ok(it) => funs.push(it),
+                Err(_) => {
+                    funs.push(self.error_node());
+                    self.synchronize_top_level(true);
+                }
+            }
+        }
+
+        Ok(Node {
+            line,
+            column,
+            node: NodeKind::TopImpl { trait_, rec, funs },
+        })
+    }
+
+    // Parses a top level statement, remembering its doc comment if it has one.
+    pub fn parse_top_level_statement(&mut self) -> ParseResult<Node> {
+        let doc = self
+            .lexer
+            .docs
+            .remove(&(self.token.line, self.token.column));
+        let node = self.parse_top_level_statement_inner()?;
+        if let Some(doc) = doc {
+            self.docs.insert((node.line, node.column), doc);
         }
+        Ok(node)
     }
 
-    pub fn parse_top_level_statement(&mut self) -> Node {
+    pub fn parse_top_level_statement_inner(&mut self) -> ParseResult<Node> {
         if self.accept(TokenKind::KwUse) {
             self.parse_use()
         } else if self.accept(TokenKind::Hashtag) {
-            let tags = self.parse_hashtags();
+            let tags = self.parse_hashtags()?;
             if self.accept(TokenKind::KwFun) {
-                self.parse_fun(Parser::cast_hashtags::<FunTags>(tags))
+                self.parse_fun(self.cast_hashtags::<FunTags>(tags, "fun"))
             } else if self.accept(TokenKind::KwRec) {
-                self.parse_rec(Parser::cast_hashtags::<RecTags>(tags))
+                self.parse_rec(self.cast_hashtags::<RecTags>(tags, "rec"))
             } else if self.accept(TokenKind::KwDef) {
-                self.parse_def(Parser::cast_hashtags::<DefTags>(tags))
+                self.parse_def(self.cast_hashtags::<DefTags>(tags, "def"))
             } else if self.accept(TokenKind::KwTag) {
                 if self.accept(TokenKind::KwRec) {
-                    self.parse_tagrec(Parser::cast_hashtags::<TagRecTags>(tags))
+                    self.parse_tagrec(self.cast_hashtags::<TagRecTags>(tags, "tag rec"))
                 } else {
-                    self.parse_tag(Parser::cast_hashtags::<TagTags>(tags))
+                    self.parse_tag(self.cast_hashtags::<TagTags>(tags, "tag"))
                 }
             } else {
                 self.throw_exception(
@@ -1147,27 +1604,34 @@ impl<'a> Parser<'a> {
             self.parse_raw()
         } else if self.accept(TokenKind::KwPragma) {
             self.parse_pragma()
+        } else if self.accept(TokenKind::KwTrait) {
+            self.parse_trait()
+        } else if self.accept(TokenKind::KwImpl) {
+            self.parse_impl()
         } else if self.accept(TokenKind::KwVar) {
-            let it = self.parse_var();
-            Node {
+            let it = self.parse_var()?;
+            Ok(Node {
                 line: it.line,
                 column: it.line,
                 node: NodeKind::StatExpr(Box::new(it)),
-            }
+            })
         } else if self.accept(TokenKind::KwLet) {
-            let it = self.parse_let();
-            Node {
+            let it = self.parse_let()?;
+            Ok(Node {
                 line: it.line,
                 column: it.line,
                 node: NodeKind::StatExpr(Box::new(it)),
-            }
+            })
         } else {
-            self.throw_exception(ParseError::UnexpectedToken(self.token.clone()), None);
+            self.throw_exception(ParseError::UnexpectedToken(self.token.clone()), None)
         }
     }
 
     // #endregion: Top level statements
 
+    // Parses the whole file. Errors are reported to the lexer's diagnostics and
+    // replaced with `NodeKind::Error` nodes, then parsing resumes at the next
+    // top level statement.
     pub fn parse(&mut self, add_implicit_use_std: bool) -> Node {
         let mut nodes: Vec<Node> = vec![];
         if add_implicit_use_std {
@@ -1179,7 +1643,13 @@ impl<'a> Parser<'a> {
         }
         self.advance();
         while !self.done {
-            nodes.push(self.parse_top_level_statement());
+            match self.parse_top_level_statement() {
+                Ok(it) => nodes.push(it),
+                Err(_) => {
+                    nodes.push(self.error_node());
+                    self.synchronize_top_level(false);
+                }
+            }
         }
         Node {
             line: 0,
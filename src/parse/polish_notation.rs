@@ -41,7 +41,7 @@ impl PolishNodeTree {
             NodeKind::ExprFalse => PolishNodeTree::Leaf("false".to_string()),
             NodeKind::ExprIdentifier(value) => PolishNodeTree::Leaf(value),
             NodeKind::ExprBlock(_) => PolishNodeTree::Leaf("block".to_string()),
-            NodeKind::ExprNew { id, params } => {
+            NodeKind::ExprNew { id, params, .. } => {
                 let mut nodes = PolishNodeTree::from_node_vec(params).unwrap();
                 nodes.insert(0, PolishNodeTree::Leaf(id));
                 PolishNodeTree::Branch("new".to_string(), nodes)
@@ -57,7 +57,7 @@ pub enum TokenKind {
     OpMul,
     OpDiv,
     OpMod,
-    OpPipe, // TODO (expr |> expr)
+    OpPipe,
     // Keywords
     KwUse,
     KwPkg,
@@ -70,7 +70,7 @@ pub enum TokenKind {
     KwIf,
     KwElse,
     KwFor,
-    KwOf, // todo
+    KwOf,
     KwContinue,
     KwBreak,
     KwDefer,
@@ -85,6 +85,9 @@ pub enum TokenKind {
     KwSwitch,
     KwCase,
     KwFall,
+    KwTrait,
+    KwImpl,
+    KwDyn,
     // Literals
     True,
     False,
@@ -96,4 +99,7 @@ pub enum TokenKind {
     String,
     CString,
     Character,
+    // Trivia, only emitted when the lexer is asked to keep it
+    Comment,
+    Newline,
 }
@@ -0,0 +1,26 @@
+use strum::IntoStaticStr;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error, IntoStaticStr)]
+pub enum ReefError {
+    #[error("expected `key = value`")]
+    ExpectedEquals,
+
+    #[error("invalid key: `{0}`, keys can only have letters, numbers, and underscores")]
+    InvalidKey(String),
+
+    #[error("invalid value: `{0}`, expected a string, number, or boolean")]
+    InvalidValue(String),
+
+    #[error("`{0}` is already set")]
+    DuplicateKey(String),
+
+    #[error("unknown key: `{0}`")]
+    UnknownKey(String),
+
+    #[error("`{0}` should be a {1}, but it's a {2}")]
+    WrongType(String, &'static str, &'static str),
+
+    #[error("no such file: `{0}`")]
+    NoSuchFile(String),
+}
@@ -0,0 +1,161 @@
+// `build.reef` tells `sea build` how to compile a project. Paths are relative to
+// the directory that it's in, and lists are separated with semicolons:
+//
+// entry = "main.sea"            - the file to compile, `main.sea` by default
+// output = "game"               - the executable, `.sea/build/main` by default
+// link = "raylib;m"             - libraries to link
+// include = "include;vendor"    - directories to search for C headers
+// ccflags = "-Wall"             - arguments for the C compiler
+// libpaths = "libs"             - paths to search for Sea libraries
+// std = "~/.sea/std/"           - path to the standard library
+// nostd = false                 - disables implicit `use std`
+// cc = "gcc"                    - the C compiler, if `--cc` isn't given
+// dep_json = "vendor/json.tar"  - a dependency, which is `use json` (see deps/mod.rs)
+
+use std::path::{Path, PathBuf};
+
+use crate::{deps::Dependency, diagnostics::Diagnostics, util};
+
+use super::{error::ReefError, ReefValue};
+
+pub const FILE_NAME: &str = "build.reef";
+
+// Dependencies are keys with this prefix, since Reef doesn't have maps
+const DEP_PREFIX: &str = "dep_";
+
+const KEYS: [(&str, &str); 9] = [
+    ("entry", "string"),
+    ("output", "string"),
+    ("link", "string"),
+    ("include", "string"),
+    ("ccflags", "string"),
+    ("libpaths", "string"),
+    ("std", "string"),
+    ("nostd", "boolean"),
+    ("cc", "string"),
+];
+
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub dir: PathBuf,
+    pub entry: PathBuf,
+    pub output: Option<PathBuf>,
+    pub link: Vec<String>,
+    pub include: Vec<String>,
+    pub ccflags: Vec<String>,
+    pub libpaths: Vec<String>,
+    pub std: Option<String>,
+    pub nostd: bool,
+    pub cc: Option<String>,
+    pub deps: Vec<Dependency>,
+}
+
+impl Manifest {
+    // Finds the `build.reef` in `dir` or the closest parent directory.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|it| it.join(FILE_NAME))
+            .find(|it| it.is_file())
+    }
+
+    // Reads a manifest, reporting unknown keys and values of the wrong type.
+    pub fn parse(path: &Path, code: &str, diagnostics: &Diagnostics) -> Self {
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let mut manifest = Manifest {
+            dir: dir.clone(),
+            entry: PathBuf::from("main.sea"),
+            output: None,
+            link: vec![],
+            include: vec![],
+            ccflags: vec![],
+            libpaths: vec![],
+            std: None,
+            nostd: false,
+            cc: None,
+            deps: vec![],
+        };
+
+        let report = |error: ReefError, help: Option<String>, position: (usize, usize, usize)| {
+            super::report(diagnostics, path, code, error, help, position);
+        };
+
+        for entry in super::parse(path, code, diagnostics) {
+            let value = (entry.line, entry.column, entry.len);
+            let dep = entry
+                .key
+                .strip_prefix(DEP_PREFIX)
+                .filter(|it| !it.is_empty());
+            if let Some(name) = dep {
+                match &entry.value {
+                    ReefValue::String(it) => {
+                        if !dir.join(it).exists() {
+                            report(ReefError::NoSuchFile(it.clone()), None, value);
+                        }
+                        let dep = Dependency::new(name.to_string(), it.clone());
+                        manifest.deps.push(dep);
+                    }
+                    it => report(
+                        ReefError::WrongType(entry.key.clone(), "string", it.type_name()),
+                        None,
+                        value,
+                    ),
+                }
+                continue;
+            }
+
+            let Some((_, expected)) = KEYS.iter().find(|(key, _)| *key == entry.key) else {
+                let keys: Vec<&str> = KEYS.iter().map(|(key, _)| *key).collect();
+                let help = util::closest_match(&entry.key, &keys)
+                    .map(|it| format!("did you mean `{it}`?"));
+                let key = (entry.line, entry.key_column, entry.key.len());
+                report(ReefError::UnknownKey(entry.key.clone()), help, key);
+                continue;
+            };
+
+            let found = entry.value.type_name();
+            if found != *expected {
+                report(
+                    ReefError::WrongType(entry.key.clone(), expected, found),
+                    None,
+                    value,
+                );
+                continue;
+            }
+
+            let list = |it: &str| -> Vec<String> {
+                it.split(';')
+                    .map(|it| it.trim().to_string())
+                    .filter(|it| !it.is_empty())
+                    .collect()
+            };
+            match (entry.key.as_str(), &entry.value) {
+                ("entry", ReefValue::String(it)) => {
+                    if !dir.join(it).is_file() {
+                        report(ReefError::NoSuchFile(it.clone()), None, value);
+                    }
+                    manifest.entry = PathBuf::from(it);
+                }
+                ("output", ReefValue::String(it)) => manifest.output = Some(PathBuf::from(it)),
+                ("link", ReefValue::String(it)) => manifest.link = list(it),
+                ("include", ReefValue::String(it)) => manifest.include = list(it),
+                ("ccflags", ReefValue::String(it)) => manifest.ccflags = list(it),
+                ("libpaths", ReefValue::String(it)) => manifest.libpaths = list(it),
+                ("std", ReefValue::String(it)) => manifest.std = Some(it.clone()),
+                ("nostd", ReefValue::Boolean(it)) => manifest.nostd = *it,
+                ("cc", ReefValue::String(it)) => manifest.cc = Some(it.clone()),
+                _ => unreachable!("the key and type were checked above"),
+            }
+        }
+
+        manifest
+    }
+
+    // Flags for the C compiler from `link`, `include`, and `ccflags`.
+    pub fn cc_flags(&self) -> Vec<String> {
+        let mut flags: Vec<String> = vec![];
+        flags.extend(self.include.iter().map(|it| format!("-I{it}")));
+        flags.extend(self.ccflags.iter().cloned());
+        flags.extend(self.link.iter().map(|it| format!("-l{it}")));
+        flags
+    }
+}
@@ -1,25 +1,45 @@
-use std::{collections::HashMap, str::FromStr};
+// Reef is the key/value config format used by `build.reef` (see doc/reef.md).
+// Errors are reported with their line and column, like errors in Sea code.
 
-use thiserror::Error;
+use std::{path::Path, str::FromStr};
 
-#[derive(Debug, Clone, Error)]
-pub enum ReefParseError {
-    #[error("value parsing error")]
-    ValueParseError,
-}
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics, Severity},
+    util,
+};
+
+use error::ReefError;
+
+pub mod error;
+pub mod manifest;
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum ReefValue {
     String(String),
     Number(f64),
     Boolean(bool),
 }
 
+impl ReefValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ReefValue::String(_) => "string",
+            ReefValue::Number(_) => "number",
+            ReefValue::Boolean(_) => "boolean",
+        }
+    }
+}
+
 impl FromStr for ReefValue {
-    type Err = ReefParseError;
+    type Err = ReefError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.starts_with('"') {
-            Ok(Self::String(s[1..s.len() - 1].to_string()))
+        if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+            Ok(Self::String(
+                s[1..s.len() - 1]
+                    .replace("\\\"", "\"")
+                    .replace("\\\\", "\\"),
+            ))
         } else if s == "true" {
             Ok(Self::Boolean(true))
         } else if s == "false" {
@@ -27,23 +47,107 @@ impl FromStr for ReefValue {
         } else if let Ok(num) = s.parse::<f64>() {
             Ok(Self::Number(num))
         } else {
-            Err(ReefParseError::ValueParseError)
+            Err(ReefError::InvalidValue(s.to_string()))
         }
     }
 }
 
-pub fn parse(code: &str) -> Result<HashMap<String, ReefValue>, ReefParseError> {
-    let mut map: HashMap<String, ReefValue> = HashMap::new();
+#[derive(Debug, Clone)]
+pub struct ReefEntry {
+    pub key: String,
+    pub value: ReefValue,
+    pub line: usize,
+    pub key_column: usize,
+    pub column: usize, // of the value
+    pub len: usize,    // of the value
+}
+
+// Reports an error in a Reef file, `code` is the file's contents.
+pub fn report(
+    diagnostics: &Diagnostics,
+    file: &Path,
+    code: &str,
+    error: ReefError,
+    help: Option<String>,
+    (line, column, len): (usize, usize, usize),
+) {
+    diagnostics.report(Diagnostic {
+        severity: Severity::Error,
+        error: error.into(),
+        help,
+        note: None,
+        file: file.to_path_buf(),
+        line,
+        column,
+        len,
+        lines: util::get_lines_from_str(code, line),
+    });
+}
+
+// Parses every valid entry in `code`, reporting the invalid ones.
+pub fn parse(file: &Path, code: &str, diagnostics: &Diagnostics) -> Vec<ReefEntry> {
+    let mut entries: Vec<ReefEntry> = vec![];
 
-    for line in code.lines() {
+    for (index, line) in code.lines().enumerate() {
         let l = line.trim();
         if l.is_empty() || l.starts_with('#') {
             continue;
         }
+        let line_number = index + 1;
+        let indent = line.len() - line.trim_start().len();
+        let error = |error: ReefError, column: usize, len: usize| {
+            report(
+                diagnostics,
+                file,
+                code,
+                error,
+                None,
+                (line_number, column, len),
+            );
+        };
+
+        let Some((key, value)) = l.split_once('=') else {
+            error(ReefError::ExpectedEquals, indent + 1, l.len());
+            continue;
+        };
+
+        let key_column = indent + 1;
+        let value_column = indent + key.len() + 1 + (value.len() - value.trim_start().len()) + 1;
+        let (key, value) = (key.trim(), value.trim());
+
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|it| it.is_ascii_alphanumeric() || it == '_')
+        {
+            error(
+                ReefError::InvalidKey(key.to_string()),
+                key_column,
+                key.len().max(1),
+            );
+            continue;
+        }
+        if entries.iter().any(|it| it.key == key) {
+            error(
+                ReefError::DuplicateKey(key.to_string()),
+                key_column,
+                key.len(),
+            );
+            continue;
+        }
 
-        let (key, value) = l.split_once('=').unwrap();
-        map.insert(key.to_string(), ReefValue::from_str(value)?);
+        match ReefValue::from_str(value) {
+            Ok(it) => entries.push(ReefEntry {
+                key: key.to_string(),
+                value: it,
+                line: line_number,
+                key_column,
+                column: value_column,
+                len: value.len(),
+            }),
+            Err(it) => error(it, value_column, value.len().max(1)),
+        }
     }
 
-    Ok(map)
+    entries
 }
@@ -4,7 +4,8 @@ use text_io::read;
 
 use crate::{
     backend::{backend::Backend, backends::c::CBackend},
-    compile::{self, compiler::Compiler},
+    compile::{self, analysis::Analyzer, compiler::Compiler},
+    diagnostics::{Diagnostics, ErrorFormat},
     parse::{lexer::Lexer, parser::Parser},
 };
 
@@ -117,9 +118,15 @@ pub const COMMANDS: LazyLock<HashMap<&'static str, SandboxCommand>> = LazyLock::
         }),
         cmd("ast", |sandbox, _args| {
             let code = sandbox.get_code();
-            let lexer = Lexer::new(PathBuf::from_str(".sandbox").unwrap(), &code);
+            let diagnostics = Diagnostics::new();
+            let lexer = Lexer::new(
+                PathBuf::from_str(".sandbox").unwrap(),
+                &code,
+                diagnostics.clone(),
+            );
             let mut parser = Parser::new(lexer);
             let program = parser.parse(true);
+            diagnostics.print(sandbox.error_format);
             program.pretty_print();
         }),
         cmd("exec", |sandbox, args| {
@@ -174,6 +181,7 @@ pub struct Sandbox {
     pub output_path: PathBuf,
     pub c_output_path: PathBuf,
     pub libpaths: Vec<PathBuf>,
+    pub error_format: ErrorFormat,
 }
 
 impl Sandbox {
@@ -240,12 +248,23 @@ impl Sandbox {
         args
     }
 
-    pub fn recompile(&mut self) {
+    // Recompiles the sandbox's code, returning false if there were any errors.
+    pub fn recompile(&mut self) -> bool {
         let code = self.get_code();
-        let lexer = Lexer::new(PathBuf::from_str(".sandbox").unwrap(), &code);
+        let diagnostics = Diagnostics::new();
+        let lexer = Lexer::new(
+            PathBuf::from_str(".sandbox").unwrap(),
+            &code,
+            diagnostics.clone(),
+        );
         let mut parser = Parser::new(lexer);
         let program = parser.parse(true);
 
+        if diagnostics.has_errors() {
+            diagnostics.print(self.error_format);
+            return false;
+        }
+
         fs::create_dir_all(self.c_output_path.clone().parent().unwrap())
             .expect("failed to mkdirs .sea/sandbox/");
 
@@ -256,20 +275,35 @@ impl Sandbox {
             self.libpaths.clone(),
             parser,
         );
-        let mut backend = CBackend::new(&mut compiler);
+
+        let program = Analyzer::new(&mut compiler).analyze(program);
+        diagnostics.print(self.error_format);
+        if diagnostics.has_errors() {
+            return false;
+        }
 
         // Write output C code
-        backend.write(program);
+        match CBackend::new(&mut compiler) {
+            Ok(mut backend) => backend.write(program),
+            Err(why) => {
+                self.throw(&format!("failed to write the C code: {why}"));
+                return false;
+            }
+        }
 
         // Exec
         if self.autoexec {
             self.exec(self.program_args.clone())
         }
+
+        true
     }
 
     pub fn exec(&mut self, args: Vec<String>) {
         println!("\x1b[35m: Compiling Sea\x1b[0m");
-        self.recompile();
+        if !self.recompile() {
+            return;
+        }
 
         let compile_res = compile::run_compile_cmds(
             PathBuf::from(".sea/sandbox/program.c"),
@@ -361,6 +395,7 @@ impl Sandbox {
             output_path: PathBuf::from(".sea/sandbox/program"),
             c_output_path: PathBuf::from(".sea/sandbox/program.c"),
             libpaths: vec![],
+            error_format: ErrorFormat::Human,
         }
     }
 }
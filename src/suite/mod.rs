@@ -0,0 +1,211 @@
+// `sea test-suite` compiles each test with `sea c`, runs it, and compares what
+// happened with the `.expected` file next to it. Expectations look like this:
+//
+// error 3:5 AlreadyDeclared     - diagnostics, with their line:column and kind
+// result: exit 0                - or `does not compile`, `C compiler failed`, etc
+// stdout:                       - followed by everything that the program printed
+//
+// Programs whose output changes between runs can have `stdout: ignored` instead,
+// which `--bless` keeps.
+//
+// Tests are compiled by spawning this executable, so a compiler panic only fails
+// the test that caused it.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+
+use crate::format::diff;
+
+// How long a test's program gets to run before it's killed.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const IGNORED: &str = "stdout: ignored";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    Failed(String), // a diff from the expectations to what happened
+    Missing,        // there's no `.expected` file yet
+    Blessed,        // the `.expected` file was written
+}
+
+pub struct Suite {
+    pub cc: String,
+    pub std: String,
+    pub libpaths: Vec<String>,
+    pub bless: bool,
+    sea: PathBuf,
+    build: PathBuf, // where tests are compiled and run
+}
+
+impl Suite {
+    // Tests are compiled in another directory, so library paths are made absolute.
+    pub fn new(cc: String, std: String, libpaths: Vec<String>) -> io::Result<Self> {
+        let absolute = |it: String| match fs::canonicalize(&it) {
+            Ok(path) => path.to_string_lossy().to_string(),
+            Err(_) => it,
+        };
+        let build = env::temp_dir().join(format!("sea-test-suite-{}", std::process::id()));
+        fs::create_dir_all(build.join(".sea/build"))?;
+        Ok(Suite {
+            cc,
+            std: absolute(std),
+            libpaths: libpaths.into_iter().map(absolute).collect(),
+            bless: false,
+            sea: env::current_exe()?,
+            build,
+        })
+    }
+
+    pub fn expectations_of(test: &Path) -> PathBuf {
+        test.with_extension("expected")
+    }
+
+    pub fn run(&self, test: &Path) -> io::Result<Verdict> {
+        let mut outcome = self.outcome(test)?;
+        let expectations = Suite::expectations_of(test);
+        let expected = fs::read_to_string(&expectations).ok();
+
+        let ignored = expected
+            .as_ref()
+            .is_some_and(|it| it.lines().any(|it| it == IGNORED));
+        if let (true, Some(index)) = (ignored, outcome.find("\nstdout:\n")) {
+            outcome.truncate(index + 1);
+            outcome.push_str(IGNORED);
+            outcome.push('\n');
+        }
+
+        if expected.as_ref() == Some(&outcome) {
+            return Ok(Verdict::Passed);
+        }
+        if self.bless {
+            fs::write(&expectations, outcome)?;
+            return Ok(Verdict::Blessed);
+        }
+        let Some(expected) = expected else {
+            return Ok(Verdict::Missing);
+        };
+
+        let name = expectations.to_string_lossy();
+        let diff = diff::unified(&name, &expected, &format!("{name} (actual)"), &outcome);
+        Ok(Verdict::Failed(if diff.is_empty() {
+            format!("\x1b[1m{name}:\x1b[0m the final newline doesn't match\n")
+        } else {
+            diff
+        }))
+    }
+
+    // Compiles and runs the test, writing down what happened.
+    fn outcome(&self, test: &Path) -> io::Result<String> {
+        let test = test.canonicalize()?;
+        let executable = self.build.join("main");
+        _ = fs::remove_file(&executable);
+
+        let mut compile = Command::new(&self.sea);
+        compile
+            .current_dir(&self.build)
+            .arg("c")
+            .arg(&test)
+            .args([
+                "--error-format",
+                "json",
+                "-c",
+                &self.cc,
+                "-s",
+                &self.std,
+                "-o",
+            ])
+            .arg(&executable);
+        for it in &self.libpaths {
+            compile.args(["-l", it]);
+        }
+        let compiled = compile
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()?;
+
+        let mut out = String::new();
+        let stdout = String::from_utf8_lossy(&compiled.stdout);
+        for line in stdout.lines() {
+            if let Ok(diagnostic) = serde_json::from_str::<Value>(line) {
+                out.push_str(&self.diagnostic_of(&diagnostic, &test));
+            }
+        }
+
+        if !compiled.status.success() {
+            let result = if compiled.status.code() == Some(101) {
+                "the compiler panicked"
+            } else if out.contains("error ") {
+                "does not compile"
+            } else {
+                "C compiler failed"
+            };
+            out.push_str(&format!("result: {result}\n"));
+            return Ok(out);
+        }
+
+        // Run the program with a timeout, since a test that hangs shouldn't hang CI
+        let output = self.build.join("stdout");
+        let mut program = Command::new(&executable)
+            .current_dir(&self.build)
+            .stdin(Stdio::null())
+            .stdout(fs::File::create(&output)?)
+            .stderr(Stdio::null())
+            .spawn()?;
+        let start = Instant::now();
+        let status = loop {
+            if let Some(status) = program.try_wait()? {
+                break Some(status);
+            }
+            if start.elapsed() > TIMEOUT {
+                _ = program.kill();
+                _ = program.wait();
+                break None;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        let result = match status.map(|it| it.code()) {
+            Some(Some(code)) => format!("exit {code}"),
+            Some(None) => "killed by a signal".to_string(),
+            None => format!("timed out after {}s", TIMEOUT.as_secs()),
+        };
+        out.push_str(&format!("result: {result}\nstdout:\n"));
+        out.push_str(&String::from_utf8_lossy(&fs::read(&output)?));
+        Ok(out)
+    }
+
+    // Diagnostics in other files (i.e, the std) are written with their path,
+    // relative to the library that they're in.
+    fn diagnostic_of(&self, diagnostic: &Value, test: &Path) -> String {
+        let file = Path::new(diagnostic["file"].as_str().unwrap_or_default());
+        let location = if file == test {
+            String::new()
+        } else {
+            let relative = std::iter::once(&self.std)
+                .chain(&self.libpaths)
+                .find_map(|it| file.strip_prefix(it).ok());
+            format!("{}:", relative.unwrap_or(file).to_string_lossy())
+        };
+        format!(
+            "{} {location}{}:{} {}\n",
+            diagnostic["severity"].as_str().unwrap_or_default(),
+            diagnostic["line"],
+            diagnostic["column"],
+            diagnostic["kind"].as_str().unwrap_or_default()
+        )
+    }
+}
+
+impl Drop for Suite {
+    fn drop(&mut self) {
+        _ = fs::remove_dir_all(&self.build);
+    }
+}
@@ -27,3 +27,36 @@ pub fn get_lines_from_file(file_path: &str, line: usize) -> Vec<(usize, String)>
         line,
     )
 }
+
+// The number of single character insertions, deletions, or substitutions needed
+// to turn `a` into `b` (the Levenshtein distance).
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a_char == *b_char {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+// Gets the option that `word` is most likely a typo of, if any are close enough.
+pub fn closest_match<'a>(word: &str, options: &[&'a str]) -> Option<&'a str> {
+    options
+        .iter()
+        .map(|it| (edit_distance(word, it), *it))
+        .filter(|(distance, it)| *distance <= it.len().div_ceil(3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, it)| it)
+}
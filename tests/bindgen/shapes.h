@@ -0,0 +1,70 @@
+// A small C library to test `sea bindgen` with, see scripts/test-bindgen.sh
+#ifndef SHAPES_H
+#define SHAPES_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define SHAPES_API
+#define SHAPES_VERSION 3
+#define SHAPES_MAX_POINTS (0x10)
+#define SHAPES_NAME "shapes"
+#define SHAPES_SQUARE(x) ((x) * (x))
+#define SHAPES_SHIFT (1 << 4)
+
+#if defined(__cplusplus)
+extern "C" {
+#endif
+
+typedef struct Point {
+    float x, y;
+} Point;
+
+typedef struct {
+    Point points[SHAPES_MAX_POINTS];
+    int count;
+    const char *name;
+} Polygon;
+
+typedef union Value {
+    int32_t i;
+    double f;
+} Value;
+
+struct Circle {
+    Point center;
+    float radius;
+};
+
+typedef enum {
+    SHAPE_POINT,
+    SHAPE_POLYGON = 2,
+    SHAPE_CIRCLE,
+} ShapeKind;
+
+typedef struct Canvas Canvas; // opaque
+struct Brush; // opaque, without a typedef
+typedef float (*AreaFn)(const void *shape);
+typedef unsigned int Color;
+
+SHAPES_API Canvas *canvas_new(size_t width, size_t height);
+SHAPES_API void canvas_free(Canvas *canvas);
+SHAPES_API bool canvas_draw(Canvas *canvas, ShapeKind kind, const void *shape, Color color);
+SHAPES_API float polygon_area(const Polygon *polygon);
+SHAPES_API float circle_area(const struct Circle *circle);
+SHAPES_API void polygon_each(const Polygon *polygon, void (*fn)(Point point, void *data), void *data);
+SHAPES_API uint8_t shape_sides(ShapeKind kind, int in);
+SHAPES_API int shapes_log(const char *format, ...);
+SHAPES_API long double shapes_precise_area(const Polygon *polygon);
+SHAPES_API struct Brush *brush_new(void);
+
+static inline float shapes_square(float x) {
+    return x * x;
+}
+
+#if defined(__cplusplus)
+}
+#endif
+
+#endif
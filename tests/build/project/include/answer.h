@@ -0,0 +1,3 @@
+#include <math.h>
+
+#define ANSWER ((int)sqrt(1600) + ANSWER_OFFSET)